@@ -6,7 +6,10 @@ use criterion::Criterion;
 extern crate libaudiograph;
 
 use libaudiograph::parser;
-use libaudiograph::static_scheduling::algorithms::{cpfd, etf, hlfet, random};
+use libaudiograph::static_scheduling::algorithms::{cpfd, etf, heft, hlfet, random};
+use libaudiograph::static_scheduling::platform::Platform;
+use libaudiograph::task_graph::graph::TaskGraph;
+use libaudiograph::task_graph::task::Task;
 
 fn static_all_schedule_file(filepath: &std::path::PathBuf) {
     let mut graph =
@@ -150,13 +153,69 @@ fn cpfd_little_random_10_benchmark(c: &mut Criterion) {
     }
 }
 
+// Returns a random DAG whose nodes have up to 3 predecessors, always the same
+// for a number of nodes
+fn large_random_graph(nb_nodes: usize) -> TaskGraph {
+    let mut graph = TaskGraph::new(nb_nodes, 3 * nb_nodes);
+    let mut seed: u64 = 42;
+    let mut next = || {
+        seed = seed
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (seed >> 33) as usize
+    };
+
+    for _ in 0..nb_nodes {
+        graph.add_task(Task::Constant(1.0 + (next() % 100) as f64 / 10.0));
+    }
+
+    for node in 1..nb_nodes {
+        for _ in 0..3 {
+            let pred = next() % node;
+
+            if graph.get_communication_cost(pred, node).is_none() {
+                graph.add_edge(pred, node);
+                graph.set_communication_cost(pred, node, (next() % 10) as f64 / 10.0);
+            }
+        }
+    }
+
+    graph
+}
+
+fn big_graph_benchmark(c: &mut Criterion) {
+    let path = std::path::PathBuf::from("Samples/AG/big_graphs/rand-300-node-graph-0.ag");
+
+    c.bench_function("etf_big_graph_300", move |b| b.iter(|| etf_schedule(&path)));
+}
+
+fn large_graph_benchmark(c: &mut Criterion) {
+    c.bench_function("etf_large_graph_1000", |b| {
+        b.iter_with_setup(|| large_random_graph(1000), |mut graph| etf(&mut graph, 8))
+    });
+    c.bench_function("hlfet_large_graph_1000", |b| {
+        b.iter_with_setup(
+            || large_random_graph(1000),
+            |mut graph| hlfet(&mut graph, 8),
+        )
+    });
+    c.bench_function("heft_large_graph_1000", |b| {
+        b.iter_with_setup(
+            || large_random_graph(1000),
+            |mut graph| heft(&mut graph, &Platform::homogeneous(8)),
+        )
+    });
+}
+
 criterion_group!(
     benches,
     little_random_10_benchmark,
     etf_little_random_10_benchmark,
     hlfet_little_random_10_benchmark,
     random_little_random_10_benchmark,
-    cpfd_little_random_10_benchmark
+    cpfd_little_random_10_benchmark,
+    big_graph_benchmark,
+    large_graph_benchmark
 );
 
 criterion_main!(benches);
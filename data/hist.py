@@ -1,24 +1,24 @@
+import csv
 import math
 import subprocess
 import sys
-from os import listdir
-from os.path import isfile, join
+import time
 import matplotlib.pyplot as plt
-import re
 
 def get_scale(data):
     max_count = 0
     max_time = 0.0
 
-    for d in data:
-        for x in d:
-            if x > max_time:
+    for times, counts in data:
+        for x, count in zip(times, counts):
+            if count > 0 and x > max_time:
                 max_time = x
 
     max_time = math.ceil(max_time) + 1
 
-    for d in data:
-        n, bins, patches = plt.hist(d, bins=100, range=(0, max_time))
+    for times, counts in data:
+        n, bins, patches = plt.hist(times, bins=100, range=(0, max_time),
+                                    weights=counts)
 
         for count in n:
             if count > max_count:
@@ -29,95 +29,85 @@ def get_scale(data):
     return max_time, max_count + 1
 
 
+def run(args, duration):
+    """
+    Run an executable for some seconds, then press Enter so that it saves its
+    statistics
+    """
+    process = subprocess.Popen(["cargo", "run", "--release", "--bin", "agp",
+                                "--", "run"] + args,
+                               stdin=subprocess.PIPE)
+    time.sleep(duration)
+    process.communicate(b"\n")
+
 
 def parse_file(path):
     """
-    Function for parsing a file containing measurements
+    Function for reading the histogram saved at the end of a run, as the start
+    of each bucket in ms and its number of cycles
     """
     with open(path, "r") as file:
-        hist = []
+        times = []
+        counts = []
 
-        for line in file:
-            words = line.strip().split(" ")
-            if len(words) == 2 and words[1].endswith("µs"):
-                current_time = int(words[1].rstrip("µs"))
-                if (current_time / 1000 < 100):
-                    hist.append(current_time / 1000)
+        for row in csv.DictReader(file):
+            times.append(int(row["start_us"]) / 1000)
+            counts.append(int(row["cycles"]))
 
-    return hist
+    return times, counts
 
 
 if len(sys.argv) != 3:
     print("Usage: parse_log.py <AG File> <Number of threads>")
     sys.exit(-1)
-timeout = 30
+duration = 30
 
 dag = sys.argv[1]
 
 seq_hist = []
-static_rand_hist = []
+static_rand_hist = ([], [])
 static_hlfet_hist = []
 static_etf_hist = []
 dynamic_hist = []
 
-subprocess.run(["cargo", "build", "--release", "--bin", "seq_exec"])
-subprocess.run(["cargo", "build", "--release", "--bin", "static_sched_exec"])
-subprocess.run(["cargo", "build", "--release", "--bin", "work_stealing_exec"])
+subprocess.run(["cargo", "build", "--release", "--bin", "agp"])
 
 nb_threads = sys.argv[2]
 
 print("**********************************")
 print("File : " + dag)
 
-# We run the audio for 60s using the TimeOutExpired exception
-try:
-    subprocess.run(["cargo", "run", "--release", "--bin", "seq_exec",
-                    dag], timeout=timeout)
-except subprocess.TimeoutExpired:
-    pass
-
-# # We run the audio for 60s using the TimeOutExpired exception
-# try:
-#     subprocess.run(["cargo", "run", "--release", "--bin", "static_sched_exec",
-#                     dag, nb_threads, "rand"], timeout=60.0)
-# except subprocess.TimeoutExpired:
-#     pass
-
-try:
-    subprocess.run(["cargo", "run", "--release", "--bin", "static_sched_exec",
-                    dag, nb_threads, "hlfet"], timeout=timeout)
-except subprocess.TimeoutExpired:
-    pass
-
-try:
-    subprocess.run(["cargo", "run", "--release", "--bin", "static_sched_exec",
-                    dag, nb_threads, "etf"], timeout=timeout)
-except subprocess.TimeoutExpired:
-    pass
-
-    # We run the audio for 60s using the TimeOutExpired exception
-try:
-    subprocess.run(["cargo", "run", "--release", "--bin", "work_stealing_exec",
-                    dag, nb_threads], timeout=timeout)
-except subprocess.TimeoutExpired:
-    pass
-
-# Parse the log for sequential execution
-seq_hist = parse_file("tmp/seq_log.txt")
-
-# Parse the log for work stealing execution
-dynamic_hist = parse_file("tmp/work_stealing_log.txt")
-
-# # Parse the log for rand static scheduling execution
+# We run the audio for 30s
+run([dag], duration)
+
+# run(["--executor", "static", "--threads", nb_threads,
+         "--algorithm", "rand", dag], duration)
+
+run(["--executor", "static", "--threads", nb_threads,
+         "--algorithm", "hlfet", dag], duration)
+
+run(["--executor", "static", "--threads", nb_threads,
+         "--algorithm", "etf", dag], duration)
+
+# We run the audio for 30s
+run(["--executor", "ws", "--threads", nb_threads, dag], duration)
+
+# Read the histogram of sequential execution
+seq_hist = parse_file("tmp/seq_hist.csv")
+
+# Read the histogram of work stealing execution
+dynamic_hist = parse_file("tmp/work_stealing_hist.csv")
+
+# # Read the histogram of rand static scheduling execution
 # static_rand_hist = parse_file(
-#     "tmp/static_rand_sched_log.txt")
+#     "tmp/static_rand_sched_hist.csv")
 
-# Parse the log for hlfet static scheduling execution
+# Read the histogram of hlfet static scheduling execution
 static_hlfet_hist = parse_file(
-    "tmp/static_hlfet_sched_log.txt")
+    "tmp/static_hlfet_sched_hist.csv")
 
-# Parse the log for etf static scheduling execution
-static_etf_hist = parse_file("tmp/static_etf_sched_log.txt")
+# Read the histogram of etf static scheduling execution
+static_etf_hist = parse_file("tmp/static_etf_sched_hist.csv")
 
 data = [seq_hist, dynamic_hist, static_hlfet_hist, static_etf_hist]
 color = ['red', 'green', 'blue', 'grey']
@@ -131,7 +121,7 @@ a = a.ravel()
 
 for idx, ax in enumerate(a):
 
-    ax.hist(data[idx], bins=50, color=color[idx])
+    ax.hist(data[idx][0], bins=50, weights=data[idx][1], color=color[idx])
     ax.set_title(titles[idx])
     ax.set_xlabel(xaxes[idx])
     ax.set_ylabel(yaxes[idx])
@@ -144,7 +134,7 @@ max_x, max_y = get_scale(
     [seq_hist, dynamic_hist, static_rand_hist, static_hlfet_hist, static_etf_hist])
 
 
-plt.hist(seq_hist, bins=100, range=(0, max_x), color='red')
+plt.hist(seq_hist[0], bins=100, range=(0, max_x), weights=seq_hist[1], color='red')
 
 plt.title('Sequential')
 plt.xlabel('Cycle Time (ms)')
@@ -156,7 +146,7 @@ plt.savefig('tmp/hist_seq.png', bbox_inches='tight')
 plt.close()
 
 
-plt.hist(dynamic_hist, bins=100, range=(0, max_x), color='green')
+plt.hist(dynamic_hist[0], bins=100, range=(0, max_x), weights=dynamic_hist[1], color='green')
 
 plt.title('Work Stealing')
 plt.xlabel('Cycle Time (ms)')
@@ -168,7 +158,7 @@ plt.savefig('tmp/hist_ws.png', bbox_inches='tight')
 plt.close()
 
 
-# plt.hist(static_rand_hist, bins=100, range=(0, max_x), color='blue')
+# plt.hist(static_rand_hist[0], bins=100, range=(0, max_x), weights=static_rand_hist[1], color='blue')
 
 # plt.title('Random static scheduling')
 # plt.xlabel('Cycle Time (ms)')
@@ -180,7 +170,7 @@ plt.close()
 # plt.close()
 
 
-plt.hist(static_hlfet_hist, bins=100, range=(0, max_x), color='grey')
+plt.hist(static_hlfet_hist[0], bins=100, range=(0, max_x), weights=static_hlfet_hist[1], color='grey')
 
 plt.title('HLFET')
 plt.xlabel('Cycle Time (ms)')
@@ -192,7 +182,7 @@ plt.savefig('tmp/hist_hlfet.png', bbox_inches='tight')
 plt.close()
 
 
-plt.hist(static_etf_hist, bins=100, range=(0, max_x), color='black')
+plt.hist(static_etf_hist[0], bins=100, range=(0, max_x), weights=static_etf_hist[1], color='black')
 
 plt.title('ETF')
 plt.xlabel('Cycle Time (ms)')
@@ -1,6 +1,7 @@
-import math
+import json
 import subprocess
 import sys
+import time
 from os import listdir
 from os.path import isfile, join
 import matplotlib.pyplot as plt
@@ -22,54 +23,38 @@ def sorted_nicely(l):
     return sorted(l, key=alphanum_key)
 
 
-cycle_time = (2 * int(sys.argv[3]) / 44100)
-print(cycle_time)
-
-cycle_time = cycle_time * 1000000
-print(cycle_time)
+def run(args, duration):
+    """
+    Run an executable for some seconds, then press Enter so that it saves its
+    statistics
+    """
+    process = subprocess.Popen(["cargo", "run", "--release", "--bin", "agp",
+                                "--", "run"] + args,
+                               stdin=subprocess.PIPE)
+    time.sleep(duration)
+    process.communicate(b"\n")
 
 
 def parse_file(path):
     """
-    Function for parsing a file containing measurements
+    Function for reading the statistics saved at the end of a run
     """
     with open(path, "r") as file:
-        worst_time = 0
-        time = 0
-        next = 0
-        nb_times = 0
-        nb_next = 0
-        misses = 0
-
-        for line in file:
-            words = line.strip().split(" ")
-            if len(words) == 2 and words[1].endswith("µs"):
-                current_time = int(words[1].rstrip("µs"))
-                if current_time > worst_time:
-                    worst_time = current_time
-                if current_time > cycle_time:
-                    misses += 1
-                time += current_time
-                nb_times += 1
-            if words[0] == "Time":
-                next += int(words[5].rstrip("µs"))
-                nb_next += 1
-
-        average_time = time // nb_times
-        average_next = next // nb_next
+        stats = json.load(file)
 
         print("\nResults for " + path + ":")
-        print("Cycles count: " + str(nb_times))
-        print("Worst time: " + str(worst_time) + "µs")
-        print("Average time: " + str(average_time) + "µs")
-        print("Average time left before the deadline: "
-              + str(average_next) + "µs")
+        print("Cycles count: " + str(stats["cycles"]))
+        print("Worst time: " + str(stats["max_us"]) + "µs")
+        print("Average time: " + str(stats["mean_us"]) + "µs")
+        print("99th percentile: " + str(stats["p99_us"]) + "µs")
+        print("Deadline misses: " + str(stats["deadline_misses"]))
+        print("Xruns: " + str(stats["xruns"]))
 
-    return (average_time, worst_time, misses)
+    return (stats["mean_us"], stats["max_us"], stats["deadline_misses"])
 
 
-if len(sys.argv) != 4:
-    print("Usage: parse_log.py <AG Directory> <Number of threads> <buffer size>")
+if len(sys.argv) != 3:
+    print("Usage: mesures.py <AG Directory> <Number of threads>")
     sys.exit(-1)
 
 dags = [f for f in listdir(sys.argv[1]) if isfile(join(sys.argv[1], f))]
@@ -94,9 +79,7 @@ dynamic_wtime = []
 dynamic_misses = []
 
 
-subprocess.run(["cargo", "build", "--release", "--bin", "seq_exec"])
-subprocess.run(["cargo", "build", "--release", "--bin", "static_sched_exec"])
-subprocess.run(["cargo", "build", "--release", "--bin", "work_stealing_exec"])
+subprocess.run(["cargo", "build", "--release", "--bin", "agp"])
 
 for dag in dags:
 
@@ -112,66 +95,48 @@ for dag in dags:
 
     x.append(nodes)
 
-    # We run the audio for 2s using the TimeOutExpired exception
-    try:
-        subprocess.run(["cargo", "run", "--release", "--bin", "seq_exec",
-                        file], timeout=3.0)
-    except subprocess.TimeoutExpired:
-        pass
-
-    # We run the audio for 2s using the TimeOutExpired exception
-    # try:
-    #     subprocess.run(["cargo", "run", "--release", "--bin", "static_sched_exec",
-    #                     file, nb_threads, "rand"], timeout=5.0)
-    # except subprocess.TimeoutExpired:
-    #     pass
-
-    try:
-        subprocess.run(["cargo", "run", "--release", "--bin", "static_sched_exec",
-                        file, nb_threads, "hlfet"], timeout=3.0)
-    except subprocess.TimeoutExpired:
-        pass
-
-    try:
-        subprocess.run(["cargo", "run", "--release", "--bin", "static_sched_exec",
-                        file, nb_threads, "etf"], timeout=3.0)
-    except subprocess.TimeoutExpired:
-        pass
-
-    # We run the audio for 2s using the TimeOutExpired exception
-    try:
-        subprocess.run(["cargo", "run", "--release", "--bin", "work_stealing_exec",
-                        file, nb_threads], timeout=3.0)
-    except subprocess.TimeoutExpired:
-        pass
-
-    # Parse the log for sequential execution
-    atime, wtime, misses = parse_file("tmp/seq_log.txt")
+    # We run the audio for 3s
+    run([file], 3.0)
+
+    # run(["--executor", "static", "--threads", nb_threads,
+         "--algorithm", "rand", file], 3.0)
+
+    run(["--executor", "static", "--threads", nb_threads,
+         "--algorithm", "hlfet", file], 3.0)
+
+    run(["--executor", "static", "--threads", nb_threads,
+         "--algorithm", "etf", file], 3.0)
+
+    # We run the audio for 3s
+    run(["--executor", "ws", "--threads", nb_threads, file], 3.0)
+
+    # Read the statistics for sequential execution
+    atime, wtime, misses = parse_file("tmp/seq_stats.json")
     seq.append(atime)
     seq_wtime.append(wtime)
     seq_misses.append(misses)
 
-    # Parse the log for work stealing execution
-    atime, wtime, misses = parse_file("tmp/work_stealing_log.txt")
+    # Read the statistics for work stealing execution
+    atime, wtime, misses = parse_file("tmp/work_stealing_stats.json")
     dynamic.append(atime)
     dynamic_wtime.append(wtime)
     dynamic_misses.append(misses)
 
-    # # Parse the log for rand static scheduling execution
+    # # Read the statistics for rand static scheduling execution
     # atime, wtime ,misses= parse_file(
     #     "tmp/static_rand_sched_log.txt")
     # static_rand.append(atime)
     # static_rand_wtime.append(wtime)
 
-    # Parse the log for hlfet static scheduling execution
+    # Read the statistics for hlfet static scheduling execution
     atime, wtime, misses = parse_file(
-        "tmp/static_hlfet_sched_log.txt")
+        "tmp/static_hlfet_sched_stats.json")
     static_hlfet.append(atime)
     static_hlfet_wtime.append(wtime)
     static_hlfet_misses.append(misses)
 
-    # Parse the log for etf static scheduling execution
-    atime, wtime, misses = parse_file("tmp/static_etf_sched_log.txt")
+    # Read the statistics for etf static scheduling execution
+    atime, wtime, misses = parse_file("tmp/static_etf_sched_stats.json")
     static_etf.append(atime)
 
     static_etf_wtime.append(wtime)
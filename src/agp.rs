@@ -0,0 +1,604 @@
+extern crate clap;
+extern crate crossbeam;
+
+extern crate libaudiograph;
+
+use std::fs;
+use std::path::Path;
+use std::process;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, RwLock};
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use crossbeam::channel::unbounded;
+
+use libaudiograph::execution::config::{RunConfig, EXECUTOR_KINDS};
+use libaudiograph::execution::sequential::run_seq;
+use libaudiograph::execution::static_scheduling::{run_loaded_sched, run_static_sched};
+use libaudiograph::execution::work_stealing::run_work_stealing;
+use libaudiograph::measure::Measure;
+use libaudiograph::parser::audiograph::parser::parse_audio_graph;
+use libaudiograph::parser::config::parser::load_config;
+use libaudiograph::parser::graphml::writer::save_graphml;
+use libaudiograph::parser::json::writer::save_json;
+use libaudiograph::parser::stg::writer::save_stg;
+use libaudiograph::parser::{graphml, json, puredata, stg};
+use libaudiograph::static_scheduling::algorithms::{cpfd, schedule_on, SchedulingAlgorithm};
+use libaudiograph::static_scheduling::gantt::GanttOptions;
+use libaudiograph::static_scheduling::metrics::{ScheduleMetrics, CSV_HEADER};
+use libaudiograph::static_scheduling::schedule::Schedule;
+use libaudiograph::static_scheduling::schedule_file::{load_schedule, save_schedule, EXTENSION};
+use libaudiograph::static_scheduling::validation::validate;
+use libaudiograph::task_graph::generator::random_audio_graph;
+use libaudiograph::task_graph::graph::{create_dot, TaskGraph};
+use libaudiograph::wcet::profiler::{load_profiles, WcetSource};
+
+const ALGORITHMS: &[&str] = &["rand", "hlfet", "etf", "dsc", "lc", "heft"];
+
+// Loads a graph, its format depending on the extension of the file:
+// `.ag`, `.json`, `.graphml`, `.stg` or `.pd`
+fn load_graph(path: &str) -> Result<TaskGraph, String> {
+    if !Path::new(path).is_file() {
+        return Err(format!("{}: no such file", path));
+    }
+
+    // The parsers have different grammars, hence different errors
+    let graph = match Path::new(path).extension().and_then(|e| e.to_str()) {
+        Some("ag") => parse_audio_graph(path).map_err(|e| e.to_string()),
+        Some("json") => json::parser::parse(path).map_err(|e| e.to_string()),
+        Some("graphml") => graphml::parser::parse(path).map_err(|e| e.to_string()),
+        Some("stg") => stg::parser::parse(path).map_err(|e| e.to_string()),
+        Some("pd") => puredata::parser::parse(path).map_err(|e| e.to_string()),
+        _ => return Err(format!("{}: unknown graph format", path)),
+    };
+
+    graph.map_err(|e| format!("{}: {}", path, e))
+}
+
+// Saves a graph, its format depending on the extension of the file: `.json`,
+// `.graphml` or `.stg`
+fn save_graph(graph: &mut TaskGraph, path: &str) -> Result<(), String> {
+    let result = match Path::new(path).extension().and_then(|e| e.to_str()) {
+        Some("json") => save_json(graph, path),
+        Some("graphml") => save_graphml(graph, path),
+        Some("stg") => save_stg(graph, path),
+        _ => return Err(format!("{}: unknown graph format", path)),
+    };
+
+    result.map_err(|e| format!("{}: {}", path, e))
+}
+
+// Returns the value of an option, parsed
+fn get_value<T: FromStr>(matches: &ArgMatches, name: &str) -> Result<T, String> {
+    let value = matches.value_of(name).unwrap_or_default();
+
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for --{}: {}", name, value))
+}
+
+// Returns the run configuration given by `--config`, overridden by the options
+// of the executor, or the default configuration
+fn get_config(matches: &ArgMatches) -> Result<RunConfig, String> {
+    let mut config = match matches.value_of("config") {
+        Some(path) => load_config(path).map_err(|e| format!("{}: {}", path, e))?,
+        None => RunConfig::default(),
+    };
+
+    if let Some(kind) = matches.value_of("executor") {
+        config.executor.kind = kind.to_string();
+    }
+
+    if matches.is_present("threads") {
+        config.executor.threads = get_value(matches, "threads")?;
+    }
+
+    if let Some(name) = matches.value_of("algorithm") {
+        config.executor.algorithm = SchedulingAlgorithm::from_name(name).unwrap();
+    }
+
+    if let Some(path) = matches.value_of("schedule") {
+        config.executor.schedule = Some(path.to_string());
+    }
+
+    if matches.is_present("drift-threshold") {
+        config.executor.drift_threshold = Some(get_value(matches, "drift-threshold")?);
+    }
+
+    if matches.is_present("pwcet") {
+        config.wcet.source = get_wcet_source(matches)?;
+    }
+
+    if let Some(address) = matches.value_of("osc") {
+        config.osc.enabled = true;
+        config.osc.address = address.to_string();
+    }
+
+    if matches.is_present("trace") {
+        config.logging.trace = true;
+    }
+
+    Ok(config)
+}
+
+// Returns the source of the WCETs given by an exceedance probability
+fn get_wcet_source(matches: &ArgMatches) -> Result<WcetSource, String> {
+    match get_value(matches, "pwcet")? {
+        p if p > 0.0 && p < 1.0 => Ok(WcetSource::PWcet(p)),
+        _ => Err("the exceedance probability must be between 0 and 1".to_string()),
+    }
+}
+
+// Sets the buffer size and the sample rate given, which the WCETs depend on
+fn set_audio_parameters(graph: &mut TaskGraph, matches: &ArgMatches) -> Result<(), String> {
+    graph.set_buffer_size(get_value(matches, "buffer-size")?);
+    graph.set_sample_rate(get_value(matches, "sample-rate")?);
+
+    Ok(())
+}
+
+// Loads a graph with the buffer size, the sample rate and the WCETs given
+fn load_profiled_graph(matches: &ArgMatches, config: &RunConfig) -> Result<TaskGraph, String> {
+    let mut graph = load_graph(matches.value_of("graph").unwrap())?;
+    let database = &config.wcet.database;
+
+    set_audio_parameters(&mut graph, matches)?;
+    graph.set_wcet_source(config.wcet.source);
+
+    let nb_profiled =
+        load_profiles(&mut graph, database).map_err(|e| format!("{}: {}", database, e))?;
+
+    println!("Number of DSPs profiled: {}", nb_profiled);
+
+    Ok(graph)
+}
+
+fn run(matches: &ArgMatches) -> Result<(), String> {
+    let graph = load_graph(matches.value_of("graph").unwrap())?;
+    let config = get_config(matches)?;
+
+    let (tx, rx) = unbounded();
+    let mut measure_thread = Measure::new(rx);
+
+    // The statistics of the run are saved when the executor drops its end of
+    // the channel
+    let measure_handle = std::thread::spawn(move || {
+        measure_thread.receive();
+    });
+
+    let result = match config.executor.kind.as_str() {
+        "seq" => run_seq(Arc::new(Mutex::new(graph)), &config, tx),
+        "ws" => run_work_stealing(Arc::new(RwLock::new(graph)), &config, tx),
+        _ => match config.executor.schedule {
+            // Replay a schedule saved by `agp schedule`
+            Some(ref path) => {
+                let sched = load_schedule(&graph, path).map_err(|e| format!("{}: {}", path, e))?;
+
+                run_loaded_sched(Arc::new(RwLock::new(graph)), sched, &config, tx)
+            }
+            None => run_static_sched(Arc::new(RwLock::new(graph)), &config, tx),
+        },
+    };
+
+    measure_handle
+        .join()
+        .map_err(|_| "the measure thread panicked".to_string())?;
+
+    result.map_err(|e| format!("failed to run: {:?}", e))
+}
+
+fn schedule_graph(matches: &ArgMatches) -> Result<(), String> {
+    let config = get_config(matches)?;
+    let mut graph = load_profiled_graph(matches, &config)?;
+    let nb_threads = config.executor.threads;
+    let sched_algo = config.executor.algorithm;
+
+    // The processors of `agp run` with this configuration
+    let (platform, errors) = config.executor.get_platform(&graph, nb_threads);
+
+    for e in errors {
+        println!("{}", e);
+    }
+
+    let sched = schedule_on(&mut graph, &platform, sched_algo);
+    let output = match matches.value_of("output") {
+        Some(output) => output.to_string(),
+        None => format!("tmp/{}.{}", sched_algo.get_name(), EXTENSION),
+    };
+
+    print!("{}", ScheduleMetrics::new(&mut graph, &sched));
+
+    save_schedule(&graph, &sched, &output).map_err(|e| format!("{}: {}", output, e))?;
+
+    println!("Schedule written in {}", output);
+
+    Ok(())
+}
+
+// Saves a schedule in tmp/, with its Gantt chart and its graph, prints its
+// metrics and appends them to the CSV
+fn report(
+    graph: &mut TaskGraph,
+    mut sched: Schedule,
+    name: &str,
+    metrics_csv: &mut String,
+) -> Result<(), String> {
+    let output = format!("tmp/{}.{}", name, EXTENSION);
+    let options = GanttOptions {
+        dependencies: graph.get_nb_node() < 150,
+        deadline: graph.get_deadline(),
+        ..GanttOptions::default()
+    };
+
+    sched.algorithm = Some(name.to_string());
+    sched.output(name).map_err(|e| format!("{}: {}", name, e))?;
+    sched
+        .output_svg(graph, name, &options)
+        .map_err(|e| format!("{}.svg: {}", name, e))?;
+    save_schedule(graph, &sched, &output).map_err(|e| format!("{}: {}", output, e))?;
+
+    if graph.get_nb_node() < 150 {
+        create_dot(graph, &format!("graph_{}", name), Some(&sched));
+    }
+
+    let metrics = ScheduleMetrics::new(graph, &sched);
+
+    print!("{}", metrics);
+    metrics_csv.push_str(&metrics.to_csv(name));
+    metrics_csv.push('\n');
+
+    Ok(())
+}
+
+fn compare(matches: &ArgMatches) -> Result<(), String> {
+    let config = get_config(matches)?;
+    let mut graph = load_profiled_graph(matches, &config)?;
+    let nb_threads = config.executor.threads;
+
+    // The processors of `agp run` with this configuration
+    let (platform, errors) = config.executor.get_platform(&graph, nb_threads);
+
+    for e in errors {
+        println!("{}", e);
+    }
+
+    let analysis = graph.analyze();
+
+    println!(
+        "Critical path: {} nodes, {}s; depth {}, width {}, average parallelism {:.2}",
+        analysis.critical_path.len(),
+        analysis.critical_path_length,
+        analysis.depth,
+        analysis.width,
+        analysis.get_average_parallelism()
+    );
+
+    if graph.get_nb_node() < 150 {
+        create_dot(&mut graph, "graph", None);
+    }
+
+    println!("\nWith {} processors:", nb_threads);
+
+    let mut metrics_csv = format!("{}\n", CSV_HEADER);
+
+    for name in ALGORITHMS {
+        let sched_algo = SchedulingAlgorithm::from_name(name).unwrap();
+
+        println!("\n{}", name);
+
+        let sched = schedule_on(&mut graph, &platform, sched_algo);
+        report(&mut graph, sched, name, &mut metrics_csv)?;
+    }
+
+    // CPFD duplicates nodes, so its schedules cannot be executed
+    for &(name, communication_cost) in &[("cpfd0", 0.0), ("cpfd1", 1.0)] {
+        println!("\n{}", name);
+
+        let sched = cpfd(&mut graph, communication_cost);
+        report(&mut graph, sched, name, &mut metrics_csv)?;
+    }
+
+    fs::write("tmp/metrics.csv", metrics_csv).map_err(|e| format!("tmp/metrics.csv: {}", e))?;
+
+    println!("\nMetrics written in tmp/metrics.csv");
+
+    Ok(())
+}
+
+fn validate_schedule(matches: &ArgMatches) -> Result<(), String> {
+    let mut graph = load_profiled_graph(matches, &get_config(matches)?)?;
+    let path = matches.value_of("schedule").unwrap();
+    let sched = load_schedule(&graph, path).map_err(|e| format!("{}: {}", path, e))?;
+    let violations = validate(&mut graph, &sched);
+
+    if violations.is_empty() {
+        println!("The schedule is valid");
+
+        return Ok(());
+    }
+
+    for violation in &violations {
+        println!("{}", violation);
+    }
+
+    Err(format!("{} violations in {}", violations.len(), path))
+}
+
+fn convert(matches: &ArgMatches) -> Result<(), String> {
+    let mut graph = load_graph(matches.value_of("graph").unwrap())?;
+
+    set_audio_parameters(&mut graph, matches)?;
+    save_graph(&mut graph, matches.value_of("output").unwrap())
+}
+
+fn dot(matches: &ArgMatches) -> Result<(), String> {
+    let path = matches.value_of("graph").unwrap();
+    let mut graph = load_graph(path)?;
+    let name = match matches.value_of("name") {
+        Some(name) => name.to_string(),
+        None => Path::new(path)
+            .file_stem()
+            .unwrap()
+            .to_string_lossy()
+            .to_string(),
+    };
+
+    let sched = match matches.value_of("schedule") {
+        Some(path) => Some(load_schedule(&graph, path).map_err(|e| format!("{}: {}", path, e))?),
+        None => None,
+    };
+
+    create_dot(&mut graph, &name, sched.as_ref());
+
+    Ok(())
+}
+
+fn profile(matches: &ArgMatches) -> Result<(), String> {
+    let mut graph = load_profiled_graph(matches, &get_config(matches)?)?;
+
+    for node_index in 0..graph.get_nb_node() {
+        if let Some(wcet) = graph.get_wcet(node_index) {
+            println!("{}: {}s", graph.get_node_name(node_index), wcet);
+        }
+    }
+
+    Ok(())
+}
+
+fn generate(matches: &ArgMatches) -> Result<(), String> {
+    let seed = match matches.value_of("seed") {
+        Some(_) => get_value(matches, "seed")?,
+        None => rand_seed(),
+    };
+    let mut graph = random_audio_graph(
+        get_value(matches, "nodes")?,
+        get_value(matches, "max-degree")?,
+        seed,
+    );
+
+    println!("Seed: {}", seed);
+    set_audio_parameters(&mut graph, matches)?;
+
+    save_graph(&mut graph, matches.value_of("output").unwrap())
+}
+
+// Returns a seed from the current time
+fn rand_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64)
+}
+
+fn main() {
+    let graph_arg = Arg::with_name("graph")
+        .help("The graph file: .ag, .json, .graphml, .stg or .pd")
+        .required(true);
+    let threads_arg = Arg::with_name("threads")
+        .long("threads")
+        .short("t")
+        .help("The number of threads (default: 2)")
+        .takes_value(true);
+    let algorithm_arg = Arg::with_name("algorithm")
+        .long("algorithm")
+        .short("a")
+        .help("The static scheduling algorithm (default: etf)")
+        .takes_value(true)
+        .possible_values(ALGORITHMS);
+    // The WCETs of the nodes depend on them
+    let audio_args = [
+        Arg::with_name("buffer-size")
+            .long("buffer-size")
+            .help("The buffer size of the graph")
+            .takes_value(true)
+            .default_value("256"),
+        Arg::with_name("sample-rate")
+            .long("sample-rate")
+            .help("The sample rate of the graph")
+            .takes_value(true)
+            .default_value("48000"),
+    ];
+    let pwcet_arg = Arg::with_name("pwcet")
+        .long("pwcet")
+        .help("Use the pWCETs at this exceedance probability instead of the maximal times")
+        .takes_value(true);
+
+    let matches = App::new("agp")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Schedules and executes audio graphs in parallel")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .setting(AppSettings::VersionlessSubcommands)
+        .arg(
+            Arg::with_name("config")
+                .long("config")
+                .short("c")
+                .help("The TOML run configuration, which the options override")
+                .takes_value(true)
+                .global(true),
+        )
+        .subcommand(
+            SubCommand::with_name("run")
+                .about("Executes a graph with JACK until Enter is pressed")
+                .arg(graph_arg.clone())
+                .arg(
+                    Arg::with_name("executor")
+                        .long("executor")
+                        .short("e")
+                        .help("How the nodes are executed (default: seq)")
+                        .takes_value(true)
+                        .possible_values(EXECUTOR_KINDS),
+                )
+                .arg(threads_arg.clone())
+                .arg(algorithm_arg.clone())
+                .arg(
+                    Arg::with_name("schedule")
+                        .long("schedule")
+                        .short("s")
+                        .help("Replay a schedule file instead of scheduling the graph")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("drift-threshold")
+                        .long("drift-threshold")
+                        .help("Schedule the graph again when the execution times drift from the WCETs by this ratio")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("osc")
+                        .long("osc")
+                        .help("Change the parameters of the nodes with OSC messages received on this UDP address, such as 127.0.0.1:9000")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("trace")
+                        .long("trace")
+                        .help("Record the events of the threads in a Chrome trace"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("schedule")
+                .about("Computes a static schedule of a graph and saves it")
+                .arg(graph_arg.clone())
+                .arg(threads_arg.clone())
+                .arg(algorithm_arg)
+                .args(&audio_args)
+                .arg(pwcet_arg.clone())
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .short("o")
+                        .help("The schedule file (default: tmp/<algorithm>.sched)")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("compare")
+                .about("Schedules a graph with every algorithm and compares the schedules, written in tmp/ with their metrics")
+                .arg(graph_arg.clone())
+                .arg(threads_arg)
+                .args(&audio_args)
+                .arg(pwcet_arg.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("validate")
+                .about("Checks a schedule file against a graph")
+                .arg(graph_arg.clone())
+                .arg(
+                    Arg::with_name("schedule")
+                        .help("The schedule file")
+                        .required(true),
+                )
+                .args(&audio_args)
+                .arg(pwcet_arg.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("convert")
+                .about("Converts a graph to another format")
+                .arg(graph_arg.clone())
+                .arg(
+                    Arg::with_name("output")
+                        .help("The converted graph: .json, .graphml or .stg")
+                        .required(true),
+                )
+                .args(&audio_args),
+        )
+        .subcommand(
+            SubCommand::with_name("dot")
+                .about("Draws a graph in tmp/<name>.dot and tmp/<name>.pdf")
+                .arg(graph_arg.clone())
+                .arg(
+                    Arg::with_name("name")
+                        .long("name")
+                        .short("n")
+                        .help("The name of the files (default: the name of the graph file)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("schedule")
+                        .long("schedule")
+                        .short("s")
+                        .help("Colour the nodes with the processors of a schedule file")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("profile")
+                .about("Profiles the DSPs of a graph and prints their WCETs")
+                .arg(graph_arg)
+                .args(&audio_args)
+                .arg(pwcet_arg.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("generate")
+                .about("Generates a random audio graph")
+                .arg(
+                    Arg::with_name("output")
+                        .help("The graph file: .json, .graphml or .stg")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("nodes")
+                        .long("nodes")
+                        .help("The number of nodes")
+                        .takes_value(true)
+                        .default_value("50"),
+                )
+                .arg(
+                    Arg::with_name("max-degree")
+                        .long("max-degree")
+                        .help("The maximal number of inputs or outputs of a node")
+                        .takes_value(true)
+                        .default_value("3"),
+                )
+                .arg(
+                    Arg::with_name("seed")
+                        .long("seed")
+                        .help("The seed of the random generator (default: the current time)")
+                        .takes_value(true),
+                )
+                .args(&audio_args),
+        )
+        .get_matches();
+
+    // The logs, the profiles and the default outputs are written in tmp/
+    if let Err(e) = fs::create_dir_all("tmp") {
+        eprintln!("error: tmp: {}", e);
+        process::exit(1);
+    }
+
+    let result = match matches.subcommand() {
+        ("run", Some(matches)) => run(matches),
+        ("schedule", Some(matches)) => schedule_graph(matches),
+        ("compare", Some(matches)) => compare(matches),
+        ("validate", Some(matches)) => validate_schedule(matches),
+        ("convert", Some(matches)) => convert(matches),
+        ("dot", Some(matches)) => dot(matches),
+        ("profile", Some(matches)) => profile(matches),
+        ("generate", Some(matches)) => generate(matches),
+        _ => unreachable!(),
+    };
+
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
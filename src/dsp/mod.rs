@@ -1,5 +1,7 @@
 use std::sync::{Arc, RwLock};
 
+pub mod wav;
+
 #[derive(Clone, Debug)]
 pub struct DspEdge {
     buffer: Vec<f32>,
@@ -29,12 +31,54 @@ pub enum DspNode {
     Modulator(Modulator),
     InputsOutputsAdaptor(InputsOutputsAdaptor),
     Sink(Sink),
+    Input(Input),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// The parameters of the DSPs which can be changed while a graph runs.
+pub enum Parameter {
+    Frequency,
+    Volume,
+}
+
+impl Parameter {
+    /// Returns the parameter with the given name in the .ag files (`freq` or
+    /// `volume`).
+    ///
+    /// # Arguments
+    /// * `name` - The name of the parameter
+    pub fn from_name(name: &str) -> Option<Parameter> {
+        match name {
+            "freq" => Some(Parameter::Frequency),
+            "volume" => Some(Parameter::Volume),
+            _ => None,
+        }
+    }
+}
+
+impl DspNode {
+    /// Sets a parameter of the DSP. Returns `false` if it does not have it.
+    ///
+    /// # Arguments
+    /// * `parameter` - The parameter
+    /// * `value` - Its new value
+    pub fn set_parameter(&mut self, parameter: Parameter, value: f32) -> bool {
+        match (self, parameter) {
+            (DspNode::Oscillator(ref mut o), Parameter::Frequency) => o.set_frequency(value),
+            (DspNode::Oscillator(ref mut o), Parameter::Volume) => o.set_volume(value),
+            (DspNode::Modulator(ref mut m), Parameter::Frequency) => m.set_frequency(value),
+            (DspNode::Modulator(ref mut m), Parameter::Volume) => m.set_volume(value),
+            _ => return false,
+        }
+
+        true
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
 pub struct Oscillator {
     phase: f32,
-    frequency: u32,
+    frequency: f32,
     volume: f32,
 }
 
@@ -43,7 +87,7 @@ fn sine_wave(phase: f32, volume: f32) -> f32 {
 }
 
 impl Oscillator {
-    pub fn new(phase: f32, frequency: u32, volume: f32) -> Oscillator {
+    pub fn new(phase: f32, frequency: f32, volume: f32) -> Oscillator {
         Oscillator {
             phase,
             frequency,
@@ -51,12 +95,28 @@ impl Oscillator {
         }
     }
 
+    pub fn get_frequency(&self) -> f32 {
+        self.frequency
+    }
+
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.frequency = frequency;
+    }
+
+    pub fn get_volume(&self) -> f32 {
+        self.volume
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+    }
+
     pub fn process(&mut self, output: Arc<RwLock<DspEdge>>) {
         let sample_rate = output.read().unwrap().sample_rate;
 
         for sample in output.write().unwrap().buffer_mut().iter_mut() {
             *sample = sine_wave(self.phase, self.volume);
-            self.phase += self.frequency as f32 / sample_rate as f32;
+            self.phase += self.frequency / sample_rate as f32;
         }
     }
 }
@@ -64,12 +124,12 @@ impl Oscillator {
 #[derive(Clone, Copy, Debug)]
 pub struct Modulator {
     phase: f32,
-    frequency: u32,
+    frequency: f32,
     volume: f32,
 }
 
 impl Modulator {
-    pub fn new(phase: f32, frequency: u32, volume: f32) -> Modulator {
+    pub fn new(phase: f32, frequency: f32, volume: f32) -> Modulator {
         Modulator {
             phase,
             frequency,
@@ -77,6 +137,22 @@ impl Modulator {
         }
     }
 
+    pub fn get_frequency(&self) -> f32 {
+        self.frequency
+    }
+
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.frequency = frequency;
+    }
+
+    pub fn get_volume(&self) -> f32 {
+        self.volume
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+    }
+
     pub fn process(&mut self, input: Arc<RwLock<DspEdge>>, output: Arc<RwLock<DspEdge>>) {
         debug_assert_eq!(
             output.read().unwrap().buffer().len(),
@@ -94,7 +170,7 @@ impl Modulator {
             .zip(input.read().unwrap().buffer().iter())
         {
             *sample_out = *sample_in * sine_wave(self.phase, self.volume);
-            self.phase += self.frequency as f32 / samplerate as f32;
+            self.phase += self.frequency / samplerate as f32;
         }
     }
 }
@@ -135,6 +211,14 @@ impl InputsOutputsAdaptor {
         }
     }
 
+    pub fn get_nb_inputs(&self) -> usize {
+        self.nb_inputs
+    }
+
+    pub fn get_nb_outputs(&self) -> usize {
+        self.nb_outputs
+    }
+
     pub fn process(
         &mut self,
         inputs: Vec<Arc<RwLock<DspEdge>>>,
@@ -183,6 +267,10 @@ impl Sink {
         }
     }
 
+    pub fn get_nb_channels(&self) -> usize {
+        self.nb_channels
+    }
+
     pub fn set_buffer(&mut self, out_buffer: *mut f32, frames: u32) {
         self.out_buffer = Some(out_buffer);
         self.frames = Some(frames as usize);
@@ -199,3 +287,52 @@ impl Sink {
 }
 
 unsafe impl Send for Sink {}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Input {
+    nb_channels: usize,
+    in_buffer: Option<*const f32>,
+    frames: Option<usize>,
+}
+
+impl Input {
+    pub fn new(nb_channels: usize) -> Input {
+        Input {
+            nb_channels,
+            in_buffer: None,
+            frames: None,
+        }
+    }
+
+    pub fn get_nb_channels(&self) -> usize {
+        self.nb_channels
+    }
+
+    pub fn set_buffer(&mut self, in_buffer: *const f32, frames: u32) {
+        self.in_buffer = Some(in_buffer);
+        self.frames = Some(frames as usize);
+    }
+
+    // Without a buffer, the input is silent
+    pub fn process(&mut self, output: Arc<RwLock<DspEdge>>) {
+        let mut output = output.write().unwrap();
+        let output = output.buffer_mut();
+
+        match (self.in_buffer, self.frames) {
+            (Some(in_buffer), Some(frames)) => unsafe {
+                let in_buffer = std::slice::from_raw_parts(in_buffer, frames);
+
+                for (sample_out, sample_in) in output.iter_mut().zip(in_buffer) {
+                    *sample_out = *sample_in;
+                }
+            },
+            _ => {
+                for sample in output.iter_mut() {
+                    *sample = 0.0;
+                }
+            }
+        }
+    }
+}
+
+unsafe impl Send for Input {}
@@ -0,0 +1,208 @@
+//! Read the WAV files played by the input nodes of the offline runs: PCM in 8,
+//! 16, 24 or 32 bits, or IEEE floats in 32 bits, the channels being mixed
+//! down to one.
+
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+const PCM: u16 = 1;
+const IEEE_FLOAT: u16 = 3;
+const EXTENSIBLE: u16 = 0xfffe;
+
+#[derive(Clone, Debug, PartialEq)]
+/// The samples of a WAV file, mixed down to one channel.
+pub struct WavFile {
+    pub samples: Vec<f32>,
+    pub sample_rate: usize,
+}
+
+// The format of the samples, given by the `fmt ` chunk
+struct Format {
+    tag: u16,
+    nb_channels: usize,
+    sample_rate: usize,
+    bits_per_sample: usize,
+}
+
+// Returns an error about the content of the file
+fn invalid_data(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+// Reads a little-endian integer
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+// Reads a little-endian integer
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+// Returns the format of a `fmt ` chunk
+fn parse_format(chunk: &[u8]) -> Result<Format> {
+    if chunk.len() < 16 {
+        return Err(invalid_data("fmt chunk too short"));
+    }
+
+    let mut tag = read_u16(chunk, 0);
+
+    // The actual format follows the extension
+    if tag == EXTENSIBLE {
+        if chunk.len() < 26 {
+            return Err(invalid_data("fmt chunk too short"));
+        }
+
+        tag = read_u16(chunk, 24);
+    }
+
+    Ok(Format {
+        tag,
+        nb_channels: read_u16(chunk, 2) as usize,
+        sample_rate: read_u32(chunk, 4) as usize,
+        bits_per_sample: read_u16(chunk, 14) as usize,
+    })
+}
+
+// Returns the value of a sample, between -1 and 1
+fn decode_sample(format: &Format, bytes: &[u8]) -> f32 {
+    match (format.tag, format.bits_per_sample) {
+        (IEEE_FLOAT, _) => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        (_, 8) => (f32::from(bytes[0]) - 128.0) / 128.0,
+        (_, 16) => f32::from(i16::from_le_bytes([bytes[0], bytes[1]])) / 32768.0,
+        (_, 24) => {
+            (i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8) as f32 / 8_388_608.0
+        }
+        _ => i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32 / 2_147_483_648.0,
+    }
+}
+
+/// Decodes the content of a WAV file.
+///
+/// # Arguments
+/// * `bytes` - The content of the file
+pub fn parse_wav(bytes: &[u8]) -> Result<WavFile> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(invalid_data("not a WAV file"));
+    }
+
+    let mut format = None;
+    let mut offset = 12;
+
+    while offset + 8 <= bytes.len() {
+        let id = &bytes[offset..offset + 4];
+        let size = read_u32(bytes, offset + 4) as usize;
+        let chunk = &bytes[offset + 8..bytes.len().min(offset + 8 + size)];
+
+        if id == b"fmt " {
+            format = Some(parse_format(chunk)?);
+        } else if id == b"data" {
+            let format = format.ok_or_else(|| invalid_data("data before the fmt chunk"))?;
+
+            match (format.tag, format.bits_per_sample) {
+                (PCM, 8) | (PCM, 16) | (PCM, 24) | (PCM, 32) | (IEEE_FLOAT, 32) => {}
+                _ => return Err(invalid_data("unsupported sample format")),
+            }
+
+            if format.nb_channels == 0 {
+                return Err(invalid_data("no channel"));
+            }
+
+            let frame_size = format.nb_channels * format.bits_per_sample / 8;
+            let sample_size = format.bits_per_sample / 8;
+            let samples = chunk
+                .chunks_exact(frame_size)
+                .map(|frame| {
+                    frame
+                        .chunks_exact(sample_size)
+                        .map(|sample| decode_sample(&format, sample))
+                        .sum::<f32>()
+                        / format.nb_channels as f32
+                })
+                .collect();
+
+            return Ok(WavFile {
+                samples,
+                sample_rate: format.sample_rate,
+            });
+        }
+
+        // The chunks are aligned on two bytes
+        offset += 8 + size + size % 2;
+    }
+
+    Err(invalid_data("no data chunk"))
+}
+
+/// Reads a WAV file.
+///
+/// # Arguments
+/// * `path` - The path of the file
+pub fn read_wav<P: AsRef<Path>>(path: P) -> Result<WavFile> {
+    parse_wav(&fs::read(path)?)
+}
+
+#[cfg(test)]
+mod wav_test {
+    use super::*;
+
+    // Returns a WAV file with the given format and data
+    fn wav(tag: u16, nb_channels: u16, bits_per_sample: u16, data: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let block_align = nb_channels * bits_per_sample / 8;
+
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&tag.to_le_bytes());
+        bytes.extend_from_slice(&nb_channels.to_le_bytes());
+        bytes.extend_from_slice(&44100u32.to_le_bytes());
+        bytes.extend_from_slice(&(44100 * u32::from(block_align)).to_le_bytes());
+        bytes.extend_from_slice(&block_align.to_le_bytes());
+        bytes.extend_from_slice(&bits_per_sample.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(data);
+
+        bytes
+    }
+
+    #[test]
+    fn test_parse_wav() {
+        // Two stereo frames in 16 bits, mixed down
+        let data: Vec<u8> = [16384i16, 0, -32768, -32768]
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes().to_vec())
+            .collect();
+        let file = parse_wav(&wav(PCM, 2, 16, &data)).unwrap();
+
+        assert_eq!(file.sample_rate, 44100);
+        assert_eq!(file.samples, vec![0.25, -1.0]);
+
+        let data: Vec<u8> = [0.5f32, -0.125]
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes().to_vec())
+            .collect();
+
+        assert_eq!(
+            parse_wav(&wav(IEEE_FLOAT, 1, 32, &data)).unwrap().samples,
+            vec![0.5, -0.125]
+        );
+        assert_eq!(
+            parse_wav(&wav(PCM, 1, 24, &[0, 0, 0x40, 0, 0, 0xc0]))
+                .unwrap()
+                .samples,
+            vec![0.5, -0.5]
+        );
+
+        assert!(parse_wav(b"RIFF0000AVI ").is_err());
+        assert!(parse_wav(&wav(IEEE_FLOAT, 1, 64, &[0; 8])).is_err());
+    }
+}
@@ -0,0 +1,472 @@
+//! This module describes how an audio graph is run: the JACK client, the
+//! executor and its scheduling, the CPUs of the threads, the source of the
+//! WCETs, where the logs are written, where the output ports are connected
+//! and the OSC server. The executables load it from a TOML file with
+//! `parser::config::parser::load_config`, then override it with their command
+//! line.
+
+use std::collections::HashMap;
+
+use parser::config::parser::load_config;
+use static_scheduling::algorithms::SchedulingAlgorithm;
+use static_scheduling::cost_model::CostModel;
+use static_scheduling::platform::Platform;
+use task_graph::graph::TaskGraph;
+use wcet::database::DATABASE_PATH;
+use wcet::observation::ReschedulePolicy;
+use wcet::profiler::WcetSource;
+
+use super::engine::Executor;
+
+/// The kinds of executors: sequential, work stealing and static scheduling.
+pub const EXECUTOR_KINDS: &[&str] = &["seq", "ws", "static"];
+
+#[derive(Clone, Debug, Default, PartialEq)]
+/// The JACK client of a run.
+pub struct BackendConfig {
+    pub client_name: Option<String>, // The name of the executor if `None`
+    pub start_server: bool,          // Start a JACK server if none is running
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// The executor of a run and its scheduling.
+pub struct ExecutorConfig {
+    pub kind: String, // One of `EXECUTOR_KINDS`
+    pub threads: usize,
+    pub algorithm: SchedulingAlgorithm,
+    pub schedule: Option<String>, // A schedule file replayed instead of scheduling
+    pub drift_threshold: Option<f64>,
+    pub cores: Vec<usize>, // The logical CPUs of the threads, the first ones if empty
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Where the WCETs of the DSPs come from.
+pub struct WcetConfig {
+    pub source: WcetSource,
+    pub database: String, // The file of the profiles
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Where the measurements and the traces of a run are written.
+pub struct LoggingConfig {
+    pub directory: String,
+    pub name: Option<String>, // The prefix of the files, the executor's if `None`
+    pub trace: bool,          // Record the events of the threads in a Chrome trace
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// The ports of a run, an output port for each exit node and an input port
+/// for each input node, and the JACK ports they are connected to.
+pub struct PortsConfig {
+    pub prefix: String,     // The ports are named `<prefix><id of the node>`
+    pub auto_connect: bool, // Connect the other ports to the playback and capture ports
+    pub playback: String,   // The regular expression of the playback ports
+    pub capture: String,    // The regular expression of the capture ports
+    pub connections: HashMap<String, Vec<String>>, // The JACK ports of nodes, by id
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// The OSC server changing the parameters of the nodes while a graph runs.
+pub struct OscConfig {
+    pub enabled: bool,
+    pub address: String, // The UDP address listened on, the loopback by default
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+/// The configuration of a run.
+pub struct RunConfig {
+    pub backend: BackendConfig,
+    pub executor: ExecutorConfig,
+    pub wcet: WcetConfig,
+    pub logging: LoggingConfig,
+    pub ports: PortsConfig,
+    pub osc: OscConfig,
+}
+
+impl RunConfig {
+    /// Loads the configuration given by a `--config <file>` option of a
+    /// command line, removing the option from it, or returns the default
+    /// configuration without this option.
+    ///
+    /// # Arguments
+    /// * `args` - The arguments of the command line
+    pub fn from_args(args: &mut Vec<String>) -> Result<RunConfig, String> {
+        let position = match args.iter().position(|arg| arg == "--config") {
+            Some(position) => position,
+            None => return Ok(RunConfig::default()),
+        };
+
+        if position + 1 >= args.len() {
+            return Err("--config needs a file".to_string());
+        }
+
+        let path = args.remove(position + 1);
+        args.remove(position);
+
+        load_config(&path).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+impl BackendConfig {
+    /// Returns the name of the JACK client.
+    ///
+    /// # Arguments
+    /// * `default_name` - The name of the executor
+    pub fn get_client_name(&self, default_name: &str) -> String {
+        self.client_name
+            .clone()
+            .unwrap_or_else(|| default_name.to_string())
+    }
+
+    /// Returns the options the JACK client is opened with.
+    pub fn get_client_options(&self) -> jack::ClientOptions {
+        if self.start_server {
+            jack::ClientOptions::empty()
+        } else {
+            jack::ClientOptions::NO_START_SERVER
+        }
+    }
+}
+
+impl Default for ExecutorConfig {
+    fn default() -> ExecutorConfig {
+        ExecutorConfig {
+            kind: "seq".to_string(),
+            threads: 2,
+            algorithm: SchedulingAlgorithm::ETF,
+            schedule: None,
+            drift_threshold: None,
+            cores: Vec::new(),
+        }
+    }
+}
+
+impl ExecutorConfig {
+    /// Returns the executor of the run.
+    pub fn get_executor(&self) -> Executor {
+        match self.kind.as_str() {
+            "ws" => Executor::WorkStealing,
+            "static" => Executor::StaticScheduling(self.algorithm),
+            _ => Executor::Sequential,
+        }
+    }
+
+    /// Returns the logical CPUs of the threads, the CPUs of the configuration
+    /// being reused if there are more threads, or nothing if it gives none.
+    ///
+    /// # Arguments
+    /// * `nb_threads` - The number of threads
+    pub fn get_cores(&self, nb_threads: usize) -> Vec<usize> {
+        if self.cores.is_empty() {
+            return Vec::new();
+        }
+
+        (0..nb_threads)
+            .map(|i| self.cores[i % self.cores.len()])
+            .collect()
+    }
+
+    /// Returns the processors a graph is scheduled on, pinned on the CPUs of
+    /// the threads, with their speeds and the communication costs between
+    /// them detected on the machine, and the messages of the detections which
+    /// failed, the processors being then identical and the costs those of
+    /// the graph.
+    ///
+    /// # Arguments
+    /// * `graph` - The graph scheduled, with its buffer size
+    /// * `nb_threads` - The number of threads
+    pub fn get_platform(&self, graph: &TaskGraph, nb_threads: usize) -> (Platform, Vec<String>) {
+        let cores = self.get_cores(nb_threads);
+        let mut errors = Vec::new();
+
+        // The first CPUs are used unless the configuration gives them
+        let detected = if cores.is_empty() {
+            Platform::detect(nb_threads)
+        } else {
+            Platform::detect_on(&cores)
+        };
+        let mut platform = detected.unwrap_or_else(|e| {
+            errors.push(format!("No processor model: {}", e));
+
+            let mut platform = Platform::homogeneous(nb_threads);
+
+            for (processor, &core_id) in platform.processors.iter_mut().zip(&cores) {
+                processor.core_id = Some(core_id);
+            }
+
+            platform
+        });
+
+        // Set the communication costs between these CPUs, keeping the costs
+        // given in the graph file
+        if let Err(e) =
+            CostModel::from_sysfs().and_then(|cost_model| cost_model.apply(graph, &mut platform))
+        {
+            errors.push(format!("No communication cost model: {}", e));
+        }
+
+        (platform, errors)
+    }
+
+    /// Returns when the graph is scheduled again from the execution times
+    /// observed.
+    pub fn get_reschedule_policy(&self) -> ReschedulePolicy {
+        ReschedulePolicy {
+            drift_threshold: self.drift_threshold,
+            ..ReschedulePolicy::default()
+        }
+    }
+}
+
+impl Default for WcetConfig {
+    fn default() -> WcetConfig {
+        WcetConfig {
+            source: WcetSource::Max,
+            database: DATABASE_PATH.to_string(),
+        }
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> LoggingConfig {
+        LoggingConfig {
+            directory: "tmp".to_string(),
+            name: None,
+            trace: false,
+        }
+    }
+}
+
+impl LoggingConfig {
+    // Returns the path of a file of the run
+    fn get_path(&self, default_name: &str, suffix: &str) -> String {
+        format!(
+            "{}/{}_{}",
+            self.directory,
+            self.name
+                .as_ref()
+                .map_or(default_name, |name| name.as_str()),
+            suffix
+        )
+    }
+
+    /// Returns the path of the log of the measurements, next to which their
+    /// statistics are saved.
+    ///
+    /// # Arguments
+    /// * `default_name` - The name of the executor
+    pub fn get_log_path(&self, default_name: &str) -> String {
+        self.get_path(default_name, "log.txt")
+    }
+
+    /// Returns the path of the log of the measurements of a worker thread.
+    ///
+    /// # Arguments
+    /// * `default_name` - The name of the executor
+    /// * `worker` - The index of the worker
+    pub fn get_worker_log_path(&self, default_name: &str, worker: usize) -> String {
+        self.get_path(default_name, &format!("worker{}_log.txt", worker))
+    }
+
+    /// Returns the path of the Chrome trace of the run.
+    ///
+    /// # Arguments
+    /// * `default_name` - The name of the executor
+    pub fn get_trace_path(&self, default_name: &str) -> String {
+        self.get_path(default_name, "trace.json")
+    }
+}
+
+impl Default for PortsConfig {
+    fn default() -> PortsConfig {
+        PortsConfig {
+            prefix: String::new(),
+            auto_connect: true,
+            playback: "system:playback_.*".to_string(),
+            capture: "system:capture_.*".to_string(),
+            connections: HashMap::new(),
+        }
+    }
+}
+
+impl Default for OscConfig {
+    fn default() -> OscConfig {
+        OscConfig {
+            enabled: false,
+            address: "127.0.0.1:9000".to_string(),
+        }
+    }
+}
+
+impl PortsConfig {
+    /// Returns the name of the port of an exit or an input node.
+    ///
+    /// # Arguments
+    /// * `node_id` - The id of the node
+    pub fn get_port_name(&self, node_id: &str) -> String {
+        format!("{}{}", self.prefix, node_id)
+    }
+
+    /// Returns the connections of the output or the input ports, as the names
+    /// of the ports and of the JACK ports they are connected to. The
+    /// connections of the configuration replace those of the graph file, and
+    /// the ports connected by neither are connected to the physical ports in
+    /// turn.
+    ///
+    /// # Arguments
+    /// * `nodes` - The ids of the exit or the input nodes, with the
+    ///   connections of the graph file
+    /// * `physical` - The playback or the capture ports, used if
+    ///   `auto_connect` is set
+    pub fn get_connections(
+        &self,
+        nodes: &[(String, Vec<String>)],
+        physical: &[String],
+    ) -> Vec<(String, String)> {
+        let mut connections = Vec::new();
+        let mut nb_auto_connected = 0;
+
+        for (node_id, graph_connections) in nodes {
+            let port_name = self.get_port_name(node_id);
+            let destinations = self.connections.get(node_id).unwrap_or(graph_connections);
+
+            if !destinations.is_empty() {
+                for destination in destinations {
+                    connections.push((port_name.clone(), destination.clone()));
+                }
+            } else if self.auto_connect && !physical.is_empty() {
+                let destination = &physical[nb_auto_connected % physical.len()];
+
+                connections.push((port_name, destination.clone()));
+                nb_auto_connected += 1;
+            }
+        }
+
+        connections
+    }
+}
+
+#[cfg(test)]
+mod config_test {
+    use super::*;
+
+    #[test]
+    fn test_defaults() {
+        let mut config = RunConfig::default();
+
+        assert_eq!(
+            config.backend.get_client_name("audio_graph_sequential"),
+            "audio_graph_sequential"
+        );
+        assert_eq!(config.logging.get_log_path("seq"), "tmp/seq_log.txt");
+        assert_eq!(config.ports.get_port_name("sink"), "sink");
+        assert_eq!(config.executor.get_executor().get_name(), "seq");
+        assert!(config.executor.get_cores(4).is_empty());
+
+        config.executor.kind = "static".to_string();
+        config.executor.algorithm = SchedulingAlgorithm::HEFT;
+        config.logging.directory = "runs".to_string();
+        config.logging.name = Some("heft_4".to_string());
+        config.executor.cores = vec![2, 3];
+
+        assert_eq!(config.executor.get_executor().get_name(), "heft");
+        assert_eq!(config.executor.get_cores(3), vec![2, 3, 2]);
+        assert_eq!(
+            config.logging.get_trace_path("static_heft_sched"),
+            "runs/heft_4_trace.json"
+        );
+        assert_eq!(
+            config.logging.get_worker_log_path("static_heft_sched", 1),
+            "runs/heft_4_worker1_log.txt"
+        );
+
+        let mut args = vec!["a.ag".to_string(), "4".to_string()];
+
+        assert_eq!(RunConfig::from_args(&mut args), Ok(RunConfig::default()));
+        assert_eq!(args.len(), 2);
+
+        args.push("--config".to_string());
+
+        assert!(RunConfig::from_args(&mut args).is_err());
+
+        args.push("missing.toml".to_string());
+
+        assert!(RunConfig::from_args(&mut args).is_err());
+        assert_eq!(args, vec!["a.ag".to_string(), "4".to_string()]);
+    }
+
+    #[test]
+    fn test_get_platform() {
+        let mut executor = RunConfig::default().executor;
+        let graph = TaskGraph::new(0, 0);
+
+        executor.cores = vec![3, 5];
+
+        // The processors are pinned on the CPUs of the configuration, even
+        // if the machine cannot be detected
+        let (platform, errors) = executor.get_platform(&graph, 2);
+
+        assert_eq!(platform.get_core_ids(), vec![3, 5]);
+
+        // The buffer size of the graph is needed for the communication costs
+        assert!(errors
+            .iter()
+            .any(|e| e.starts_with("No communication cost model")));
+    }
+
+    #[test]
+    fn test_get_connections() {
+        let mut ports = PortsConfig::default();
+        let sinks: Vec<_> = ["left", "right", "sub", "monitor"]
+            .iter()
+            .map(|id| (id.to_string(), Vec::new()))
+            .collect();
+        let playback = vec![
+            "system:playback_1".to_string(),
+            "system:playback_2".to_string(),
+        ];
+        let pairs = |connections: &[(&str, &str)]| -> Vec<(String, String)> {
+            connections
+                .iter()
+                .map(|(port, destination)| (port.to_string(), destination.to_string()))
+                .collect()
+        };
+
+        assert_eq!(
+            ports.get_connections(&sinks, &playback),
+            pairs(&[
+                ("left", "system:playback_1"),
+                ("right", "system:playback_2"),
+                ("sub", "system:playback_1"),
+                ("monitor", "system:playback_2"),
+            ])
+        );
+        assert!(ports.get_connections(&sinks, &[]).is_empty());
+
+        // The configuration replaces the graph file, which replaces the
+        // playback ports
+        let mut sinks = sinks;
+        sinks[2].1 = vec!["lfe:in".to_string()];
+        sinks[3].1 = vec!["recorder:in_1".to_string()];
+        ports.prefix = "out_".to_string();
+        ports.connections.insert(
+            "monitor".to_string(),
+            vec!["phones:in_1".to_string(), "phones:in_2".to_string()],
+        );
+
+        assert_eq!(
+            ports.get_connections(&sinks, &playback),
+            pairs(&[
+                ("out_left", "system:playback_1"),
+                ("out_right", "system:playback_2"),
+                ("out_sub", "lfe:in"),
+                ("out_monitor", "phones:in_1"),
+                ("out_monitor", "phones:in_2"),
+            ])
+        );
+
+        ports.auto_connect = false;
+
+        assert_eq!(ports.get_connections(&sinks, &playback).len(), 3);
+    }
+}
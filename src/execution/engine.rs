@@ -0,0 +1,589 @@
+//! This module executes the cycles of an audio graph with any of the
+//! executors, either offline, as fast as possible and without JACK, or driven
+//! by JACK, for a given number of cycles, so that experiments can be run
+//! without user interaction. Offline, the input nodes play the WAV file of
+//! the graph file, or silence. The MIDI file of the graph file drives the
+//! parameters of its nodes in both cases.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crossbeam::channel::unbounded;
+
+use crate::dsp::wav::read_wav;
+use crate::dsp::{DspEdge, DspNode};
+use crate::measure::ring::ring_buffer;
+use crate::measure::stats::CycleStats;
+use crate::measure::trace::Trace;
+use crate::measure::{MeasureDestination, MeasureRecord};
+use crate::midi::file::read_midi_file;
+use crate::midi::{apply_midi_message, MidiMessage};
+use crate::static_scheduling::algorithms::{schedule_on, SchedulingAlgorithm};
+use crate::task_graph::graph::TaskGraph;
+use crate::task_graph::state::TaskState;
+use crate::wcet::database::DATABASE_PATH;
+use crate::wcet::observation::ExecutionStatistics;
+use crate::wcet::profiler::load_profiles;
+
+use super::config::{ExecutorConfig, RunConfig};
+use super::thread_pool::static_scheduling::ThreadPool as StaticThreadPool;
+use super::thread_pool::work_stealing::ThreadPool as WorkStealingThreadPool;
+use super::utils::{
+    connect_ports, exec_task, get_input_nodes, get_sinks, get_sources, new_dsp_edges,
+    register_in_ports, register_out_ports, XrunNotifier,
+};
+
+// How often the end of a run driven by JACK is checked
+const POLL_PERIOD: Duration = Duration::from_millis(10);
+
+#[derive(Clone, Copy)]
+/// How the nodes of an audio graph are executed.
+pub enum Executor {
+    Sequential,
+    StaticScheduling(SchedulingAlgorithm),
+    WorkStealing,
+}
+
+impl Executor {
+    /// Returns the name of the executor: `seq`, `ws`, or the name of the
+    /// scheduling algorithm.
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Executor::Sequential => "seq",
+            Executor::StaticScheduling(sched_algo) => sched_algo.get_name(),
+            Executor::WorkStealing => "ws",
+        }
+    }
+
+    /// Returns the executor with a name, if there is one.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the executor
+    pub fn from_name(name: &str) -> Option<Executor> {
+        match name {
+            "seq" => Some(Executor::Sequential),
+            "ws" => Some(Executor::WorkStealing),
+            _ => SchedulingAlgorithm::from_name(name).map(Executor::StaticScheduling),
+        }
+    }
+
+    /// Returns `true` if the executor runs the nodes on several threads.
+    pub fn is_parallel(&self) -> bool {
+        !matches!(self, Executor::Sequential)
+    }
+}
+
+// The threads executing the nodes
+enum Pool {
+    Sequential(Vec<usize>), // The topological order
+    StaticScheduling(StaticThreadPool),
+    WorkStealing(WorkStealingThreadPool),
+}
+
+/// An audio graph ready to be executed, cycle after cycle, the sinks writing
+/// into buffers of the engine and the input nodes reading from others.
+pub struct Engine {
+    graph: Arc<RwLock<TaskGraph>>,
+    dsp_edges: Arc<RwLock<HashMap<(usize, usize), Arc<RwLock<DspEdge>>>>>,
+    pool: Pool,
+    exit_nodes: Vec<usize>,
+    outputs: Vec<Vec<f32>>, // A buffer for each exit node
+    input_nodes: Vec<usize>,
+    inputs: Vec<Vec<f32>>,      // A buffer for each input node
+    input_files: Vec<Vec<f32>>, // The samples played offline, silence if empty
+    input_position: usize,      // The position of the next samples in the files
+    // The messages of the MIDI file with their frames, the index of the next
+    // one and the first frame of the next cycle
+    midi_events: Vec<(usize, MidiMessage)>,
+    midi_position: usize,
+    frame: usize,
+    buffer_size: usize,
+}
+
+// Returns the samples of the WAV file of an input node, or nothing if it has
+// none or if it can not be read
+fn load_input_file(graph: &TaskGraph, node_index: usize, sample_rate: usize) -> Vec<f32> {
+    let path = match graph.get_input_file(node_index) {
+        Some(path) => path,
+        None => return Vec::new(),
+    };
+
+    match read_wav(path) {
+        Ok(file) => {
+            if file.sample_rate != sample_rate {
+                eprintln!(
+                    "{}: sampled at {} Hz instead of {} Hz",
+                    path, file.sample_rate, sample_rate
+                );
+            }
+
+            file.samples
+        }
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path, e);
+            Vec::new()
+        }
+    }
+}
+
+// Returns the messages of the MIDI file of a graph, or nothing if it has none
+// or if it can not be read
+fn load_midi_file(graph: &TaskGraph, sample_rate: usize) -> Vec<(usize, MidiMessage)> {
+    let path = match graph.get_midi_file() {
+        Some(path) => path,
+        None => return Vec::new(),
+    };
+
+    read_midi_file(path, sample_rate).unwrap_or_else(|e| {
+        eprintln!("Failed to read {}: {}", path, e);
+        Vec::new()
+    })
+}
+
+impl Engine {
+    /// Prepares the execution of an audio graph. The static schedulings are
+    /// computed from the WCETs of the profiles of the DSPs.
+    ///
+    /// # Arguments
+    /// * `graph` - The audio graph to be executed
+    /// * `executor` - How the nodes are executed
+    /// * `nb_threads` - The number of threads of a parallel executor
+    /// * `buffer_size` - The size of the audio buffers
+    /// * `sample_rate` - The sample rate
+    pub fn new(
+        mut graph: TaskGraph,
+        executor: Executor,
+        nb_threads: usize,
+        buffer_size: usize,
+        sample_rate: usize,
+    ) -> Engine {
+        graph.set_buffer_size(buffer_size);
+        graph.set_sample_rate(sample_rate);
+
+        let exit_nodes = graph.get_exit_nodes();
+        let outputs = vec![vec![0.0; buffer_size]; exit_nodes.len()];
+        let input_nodes = get_input_nodes(&graph);
+        let inputs = vec![vec![0.0; buffer_size]; input_nodes.len()];
+        let input_files = input_nodes
+            .iter()
+            .map(|&node_index| load_input_file(&graph, node_index, sample_rate))
+            .collect();
+        let midi_events = load_midi_file(&graph, sample_rate);
+        let dsp_edges = Arc::new(RwLock::new(new_dsp_edges(&graph, buffer_size, sample_rate)));
+
+        let sched = match executor {
+            Executor::StaticScheduling(sched_algo) => {
+                if let Err(e) = load_profiles(&mut graph, DATABASE_PATH) {
+                    eprintln!("No WCET profiles: {}", e);
+                }
+
+                // The processors of a run on the first CPUs
+                let (platform, errors) = ExecutorConfig::default().get_platform(&graph, nb_threads);
+
+                for e in errors {
+                    eprintln!("{}", e);
+                }
+
+                Some(schedule_on(&mut graph, &platform, sched_algo))
+            }
+            _ => None,
+        };
+
+        let nb_nodes = graph.get_nb_node();
+        let exec_order = graph.get_topological_order();
+        let graph = Arc::new(RwLock::new(graph));
+
+        let pool = match (executor, sched) {
+            (Executor::StaticScheduling(_), Some(sched)) => {
+                Pool::StaticScheduling(StaticThreadPool::create(
+                    nb_threads,
+                    graph.clone(),
+                    dsp_edges.clone(),
+                    sched,
+                    Arc::new(ExecutionStatistics::new(nb_nodes)),
+                    Arc::new(Trace::new(nb_threads, false)),
+                    Vec::new(),
+                ))
+            }
+            (Executor::WorkStealing, _) => Pool::WorkStealing(WorkStealingThreadPool::create(
+                nb_threads,
+                &[],
+                graph.clone(),
+                dsp_edges.clone(),
+                Arc::new(ExecutionStatistics::new(nb_nodes)),
+                Arc::new(Trace::new(nb_threads, false)),
+                Vec::new(),
+            )),
+            _ => Pool::Sequential(exec_order),
+        };
+
+        Engine {
+            graph,
+            dsp_edges,
+            pool,
+            exit_nodes,
+            outputs,
+            input_nodes,
+            inputs,
+            input_files,
+            input_position: 0,
+            midi_events,
+            midi_position: 0,
+            frame: 0,
+            buffer_size,
+        }
+    }
+
+    /// Executes a cycle of the audio graph.
+    pub fn process(&mut self) {
+        let graph = self.graph.clone();
+
+        // The sinks mix into the buffers of the engine, cleared first
+        for (output, &node_index) in self.outputs.iter_mut().zip(&self.exit_nodes) {
+            for sample in output.iter_mut() {
+                *sample = 0.0;
+            }
+
+            let sink = graph.read().unwrap().get_dsp(node_index);
+            let sink = &mut *sink.lock().unwrap();
+
+            if let Some(sink) = sink {
+                if let DspNode::Sink(ref mut s) = sink.dsp {
+                    s.set_buffer(output.as_mut_ptr(), output.len() as u32);
+                }
+            }
+        }
+
+        // The input nodes read the buffers of the engine
+        for (input, &node_index) in self.inputs.iter().zip(&self.input_nodes) {
+            let dsp = graph.read().unwrap().get_dsp(node_index);
+            let dsp = &mut *dsp.lock().unwrap();
+
+            if let Some(dsp) = dsp {
+                if let DspNode::Input(ref mut i) = dsp.dsp {
+                    i.set_buffer(input.as_ptr(), input.len() as u32);
+                }
+            }
+        }
+
+        reset_states(&mut graph.write().unwrap());
+
+        match self.pool {
+            Pool::Sequential(ref exec_order) => {
+                for &node_index in exec_order {
+                    exec_task(node_index, graph.clone(), self.dsp_edges.clone(), None);
+                }
+            }
+            Pool::StaticScheduling(ref mut pool) => pool.start(None),
+            Pool::WorkStealing(ref mut pool) => pool.start(None),
+        }
+    }
+
+    /// Returns the buffers written by the sinks during the last cycle.
+    pub fn get_outputs(&self) -> &[Vec<f32>] {
+        &self.outputs
+    }
+
+    /// Returns the buffers the input nodes read during the next cycle.
+    pub fn get_inputs_mut(&mut self) -> &mut [Vec<f32>] {
+        &mut self.inputs
+    }
+
+    /// Fills the buffers of the input nodes with the next samples of their
+    /// files, played in a loop, or with silence.
+    pub fn read_input_files(&mut self) {
+        let position = self.input_position;
+
+        for (input, samples) in self.inputs.iter_mut().zip(&self.input_files) {
+            for (i, sample) in input.iter_mut().enumerate() {
+                *sample = if samples.is_empty() {
+                    0.0
+                } else {
+                    samples[(position + i) % samples.len()]
+                };
+            }
+        }
+
+        self.input_position += self.inputs.first().map_or(0, |input| input.len());
+    }
+
+    /// Applies the messages of the MIDI file played before the end of the
+    /// next cycle to the parameters of the nodes.
+    pub fn play_midi_file(&mut self) {
+        let end = self.frame + self.buffer_size;
+        let graph = self.graph.read().unwrap();
+
+        while let Some(&(frame, ref message)) = self.midi_events.get(self.midi_position) {
+            if frame >= end {
+                break;
+            }
+
+            apply_midi_message(&graph, message);
+            self.midi_position += 1;
+        }
+
+        self.frame = end;
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        if let Pool::WorkStealing(ref pool) = self.pool {
+            pool.stop();
+        }
+    }
+}
+
+// Sets the nodes without predecessors ready and the others waiting for all of
+// them, before a cycle
+fn reset_states(graph: &mut TaskGraph) {
+    for node_index in 0..graph.get_nb_node() {
+        if let Some(predecessors) = graph.get_predecessors(node_index) {
+            graph.set_state(
+                node_index,
+                if predecessors.is_empty() {
+                    TaskState::Ready
+                } else {
+                    TaskState::WaitingDependencies(predecessors.len())
+                },
+            );
+        }
+    }
+}
+
+/// Executes cycles of an engine one after the other, without JACK, and
+/// returns their statistics, the deadline of a cycle being the duration of
+/// its buffer.
+///
+/// # Arguments
+/// * `engine` - The engine to run
+/// * `nb_cycles` - The number of cycles
+/// * `deadline` - The duration of a cycle, in seconds
+pub fn run_offline(engine: &mut Engine, nb_cycles: usize, deadline: f64) -> CycleStats {
+    let mut stats = CycleStats::new();
+    let deadline = (deadline * 1_000_000.0) as i64;
+
+    for _ in 0..nb_cycles {
+        engine.read_input_files();
+        engine.play_midi_file();
+
+        let start_time = SystemTime::now();
+        let timer = Instant::now();
+
+        engine.process();
+
+        let duration = timer.elapsed().as_micros() as i64;
+
+        stats.add_record(&MeasureRecord::new(start_time, deadline - duration));
+    }
+
+    stats
+}
+
+/// Executes cycles of an engine driven by a JACK client, whose buffer size is
+/// set to the one of the engine, and returns their statistics. The ports are
+/// named after the nodes and connected as in the other runs, the connections
+/// being printed.
+///
+/// # Arguments
+/// * `client` - The JACK client, whose sample rate must be the one of the
+///   engine
+/// * `engine` - The engine to run
+/// * `config` - The names and the connections of the ports
+/// * `nb_cycles` - The number of cycles
+pub fn run_jack(
+    client: jack::Client,
+    mut engine: Engine,
+    config: &RunConfig,
+    nb_cycles: usize,
+) -> Result<CycleStats, jack::Error> {
+    let buffer_size = engine
+        .get_outputs()
+        .first()
+        .map_or(0, |output| output.len());
+
+    if client.buffer_size() as usize != buffer_size {
+        client.set_buffer_size(buffer_size as u32)?;
+    }
+
+    // The ports follow the exit and the input nodes, as the buffers of the
+    // engine
+    let (sinks, sources) = {
+        let mut graph = engine.graph.write().unwrap();
+
+        (get_sinks(&mut graph), get_sources(&graph))
+    };
+    let mut out_ports = register_out_ports(&client, config, &sinks)?;
+    let in_ports = register_in_ports(&client, config, &sources)?;
+
+    let (mut records, mut records_consumer) = ring_buffer(nb_cycles);
+    let done = Arc::new(AtomicBool::new(false));
+    let mut nb_done = 0;
+
+    let callback = jack::ClosureProcessHandler::new(clone!(done => move |_, ps| {
+        if nb_done == nb_cycles {
+            done.store(true, Ordering::Relaxed);
+            return jack::Control::Continue;
+        }
+
+        let start_time = SystemTime::now();
+
+        for (port, input) in in_ports.iter().zip(engine.get_inputs_mut()) {
+            input.copy_from_slice(port.as_slice(ps));
+        }
+
+        engine.play_midi_file();
+        engine.process();
+
+        for (port, output) in out_ports.iter_mut().zip(engine.get_outputs()) {
+            port.as_mut_slice(ps).copy_from_slice(output);
+        }
+
+        let time_left = ps.cycle_times().unwrap().next_usecs as i64 - jack::get_time() as i64;
+
+        records.push(MeasureRecord::new(start_time, time_left));
+        nb_done += 1;
+
+        jack::Control::Continue
+    }));
+
+    let (tx, rx) = unbounded();
+    let active_client = client.activate_async(XrunNotifier::new(tx.clone(), ""), callback)?;
+
+    connect_ports(active_client.as_client(), config, &sinks, &sources, false, &tx, "");
+
+    while !done.load(Ordering::Relaxed) {
+        thread::sleep(POLL_PERIOD);
+    }
+
+    active_client.deactivate()?;
+
+    let mut stats = CycleStats::new();
+
+    while let Some(record) = records_consumer.pop() {
+        stats.add_record(&record);
+    }
+
+    for dest in rx.try_iter() {
+        match dest {
+            MeasureDestination::Xrun(_) => stats.add_xrun(),
+            MeasureDestination::File(_, message) => println!("{}", message),
+            _ => (),
+        }
+    }
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod engine_test {
+    use super::*;
+    use dsp::Parameter;
+    use midi::{MidiMapping, MidiSource};
+    use task_graph::task::DspTask;
+
+    #[test]
+    fn test_run_offline() {
+        // An oscillator mixed into a sink
+        let new_graph = || {
+            let mut g = TaskGraph::new(3, 2);
+
+            g.add_dsp(DspTask::new_oscillator("a".to_string(), 440.0, 1.0));
+            g.add_dsp(DspTask::new_io_adaptor("m".to_string(), 1, 1));
+            g.add_dsp(DspTask::new_sink("s".to_string(), 1));
+            g.add_edge(0, 1);
+            g.add_edge(1, 2);
+
+            g
+        };
+
+        assert!(Executor::from_name("etf").unwrap().is_parallel());
+        assert_eq!(Executor::from_name("seq").unwrap().get_name(), "seq");
+        assert!(Executor::from_name("fifo").is_none());
+
+        for &executor in &[Executor::Sequential, Executor::WorkStealing] {
+            let mut engine = Engine::new(new_graph(), executor, 1, 64, 48000);
+            let summary = run_offline(&mut engine, 10, 64.0 / 48000.0).get_summary();
+
+            assert_eq!(summary.nb_cycles, 10);
+            assert_eq!(engine.get_outputs().len(), 1);
+            assert_eq!(engine.get_outputs()[0].len(), 64);
+            // The oscillator reached the sink
+            assert!(engine.get_outputs()[0].iter().any(|&sample| sample != 0.0));
+        }
+    }
+
+    #[test]
+    fn test_inputs() {
+        // An input copied into two sinks
+        let mut g = TaskGraph::new(4, 3);
+
+        g.add_dsp(DspTask::new_input("in".to_string(), 1));
+        g.add_dsp(DspTask::new_io_adaptor("m".to_string(), 1, 2));
+        g.add_dsp(DspTask::new_sink("l".to_string(), 1));
+        g.add_dsp(DspTask::new_sink("r".to_string(), 1));
+        g.add_edge(0, 1);
+        g.add_edge(1, 2);
+        g.add_edge(1, 3);
+        g.set_input_file(0, "missing.wav");
+
+        let mut engine = Engine::new(g, Executor::Sequential, 1, 4, 48000);
+
+        // Without its file, the input is silent
+        engine.read_input_files();
+        engine.process();
+
+        assert_eq!(engine.get_outputs(), &[vec![0.0; 4], vec![0.0; 4]]);
+
+        // The buffers of the input nodes, as filled by JACK
+        engine.get_inputs_mut()[0].copy_from_slice(&[0.5, -0.5, 0.25, 0.0]);
+        engine.process();
+
+        assert_eq!(engine.get_outputs()[1], vec![0.5, -0.5, 0.25, 0.0]);
+
+        // The file is played in a loop
+        engine.input_files[0] = vec![0.1, 0.2, 0.3];
+        engine.read_input_files();
+        engine.process();
+
+        assert_eq!(engine.get_outputs()[0], vec![0.2, 0.3, 0.1, 0.2]);
+    }
+
+    #[test]
+    fn test_midi_file() {
+        let mut g = TaskGraph::new(2, 1);
+
+        g.add_dsp(DspTask::new_oscillator("a".to_string(), 440.0, 1.0));
+        g.add_dsp(DspTask::new_sink("s".to_string(), 1));
+        g.add_edge(0, 1);
+        g.add_midi_mapping(MidiMapping {
+            source: MidiSource::ControlChange(7),
+            channel: None,
+            node_index: 0,
+            parameter: Parameter::Volume,
+        });
+
+        let mut engine = Engine::new(g, Executor::Sequential, 1, 64, 48000);
+        let volume = |value| MidiMessage::ControlChange {
+            channel: 0,
+            controller: 7,
+            value,
+        };
+
+        // Muted during the first cycle, then played again during the third
+        engine.midi_events = vec![(10, volume(0)), (128, volume(127))];
+
+        for &played in &[false, false, true] {
+            engine.play_midi_file();
+            engine.process();
+
+            assert_eq!(
+                engine.get_outputs()[0].iter().any(|&sample| sample != 0.0),
+                played
+            );
+        }
+    }
+}
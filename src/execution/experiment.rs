@@ -0,0 +1,143 @@
+//! This module describes a sweep of experiments, the configurations of a
+//! matrix of graphs, executors, thread counts and buffer sizes, and writes
+//! their results in a single CSV table.
+
+use measure::stats::{StatsSummary, CSV_HEADER};
+
+use super::engine::Executor;
+
+/// A run of an audio graph.
+pub struct Configuration {
+    pub graph: String, // The path of the graph file
+    pub executor: Executor,
+    pub nb_threads: usize,
+    pub buffer_size: usize,
+}
+
+/// The configurations to run, every combination of its values. The
+/// sequential executor is only run with a single thread.
+pub struct Matrix {
+    pub graphs: Vec<String>,
+    pub executors: Vec<Executor>,
+    pub thread_counts: Vec<usize>,
+    pub buffer_sizes: Vec<usize>,
+}
+
+impl Matrix {
+    /// Returns the configurations of the matrix, graph by graph.
+    pub fn get_configurations(&self) -> Vec<Configuration> {
+        let mut configurations = Vec::new();
+
+        for graph in &self.graphs {
+            for &buffer_size in &self.buffer_sizes {
+                for &executor in &self.executors {
+                    let thread_counts = if executor.is_parallel() {
+                        self.thread_counts.clone()
+                    } else {
+                        vec![1]
+                    };
+
+                    for nb_threads in thread_counts {
+                        configurations.push(Configuration {
+                            graph: graph.clone(),
+                            executor,
+                            nb_threads,
+                            buffer_size,
+                        });
+                    }
+                }
+            }
+        }
+
+        configurations
+    }
+}
+
+/// The statistics of the cycles of a configuration.
+pub struct ExperimentResult {
+    pub configuration: Configuration,
+    pub nb_nodes: usize,
+    pub sample_rate: usize,
+    pub mode: &'static str, // `offline` or `jack`
+    pub summary: StatsSummary,
+}
+
+/// Returns the results of experiments as a CSV table, a line per
+/// configuration.
+///
+/// # Arguments
+/// * `results` - The results of the configurations
+pub fn write_results(results: &[ExperimentResult]) -> String {
+    let mut csv = format!(
+        "graph,nodes,executor,threads,buffer_size,sample_rate,mode,{}\n",
+        CSV_HEADER
+    );
+
+    for result in results {
+        let configuration = &result.configuration;
+
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{}\n",
+            configuration.graph,
+            result.nb_nodes,
+            configuration.executor.get_name(),
+            configuration.nb_threads,
+            configuration.buffer_size,
+            result.sample_rate,
+            result.mode,
+            result.summary.write_csv_values()
+        ));
+    }
+
+    csv
+}
+
+#[cfg(test)]
+mod experiment_test {
+    use super::*;
+    use measure::stats::CycleStats;
+    use static_scheduling::algorithms::SchedulingAlgorithm;
+
+    #[test]
+    fn test_matrix() {
+        let matrix = Matrix {
+            graphs: vec!["a.ag".to_string(), "b.ag".to_string()],
+            executors: vec![
+                Executor::Sequential,
+                Executor::WorkStealing,
+                Executor::StaticScheduling(SchedulingAlgorithm::ETF),
+            ],
+            thread_counts: vec![2, 4],
+            buffer_sizes: vec![64, 256],
+        };
+
+        let configurations = matrix.get_configurations();
+
+        // (1 + 2 + 2) configurations for each graph and buffer size
+        assert_eq!(configurations.len(), 20);
+        assert_eq!(configurations[0].executor.get_name(), "seq");
+        assert_eq!(configurations[4].executor.get_name(), "etf");
+        assert_eq!(configurations[4].nb_threads, 4);
+        assert_eq!(configurations[5].buffer_size, 256);
+
+        let results: Vec<ExperimentResult> = configurations
+            .into_iter()
+            .take(2)
+            .map(|configuration| ExperimentResult {
+                configuration,
+                nb_nodes: 3,
+                sample_rate: 48000,
+                mode: "offline",
+                summary: CycleStats::new().get_summary(),
+            })
+            .collect();
+
+        let csv = write_results(&results);
+
+        assert_eq!(csv.lines().count(), 3);
+        assert!(
+            csv.starts_with("graph,nodes,executor,threads,buffer_size,sample_rate,mode,cycles,")
+        );
+        assert!(csv.contains("\na.ag,3,ws,2,64,48000,offline,0,0.000,"));
+    }
+}
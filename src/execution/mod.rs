@@ -1,6 +1,9 @@
 #[macro_use]
 mod utils;
 
+pub mod config;
+pub mod engine;
+pub mod experiment;
 pub mod sequential;
 pub mod static_scheduling;
 mod thread_pool;
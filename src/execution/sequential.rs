@@ -3,54 +3,71 @@ use std::sync::{Arc, Mutex, RwLock};
 use crossbeam::channel::Sender;
 
 use crate::dsp::DspNode;
-use crate::measure::MeasureDestination;
+use crate::measure::trace::{save_chrome_trace, Trace, TraceEventKind};
+use crate::measure::ring::ring_buffer;
+use crate::measure::{MeasureDestination, MeasureRecord, RING_CAPACITY};
+use crate::osc::apply_parameter_updates;
+use crate::osc::server::UPDATES_CAPACITY;
 use crate::task_graph::graph::TaskGraph;
+use crate::wcet::observation::ExecutionStatistics;
 
-use super::utils::build_dsp_edges;
+use super::config::RunConfig;
+use super::utils::{
+    apply_midi_port, build_dsp_edges, connect_ports, get_input_nodes, get_sinks, get_sources,
+    log_observed_wcets, open_client, register_in_ports, register_midi_port, register_out_ports,
+    start_osc_server, XrunNotifier,
+};
 
 /// Sequentially executes an audio graph with JACK.
 ///
 /// # Arguments
 ///
 /// * `graph` - The audio graph to be executed
+/// * `config` - The JACK client, the logs and the output ports of the run
 /// * `tx` - The channel used for sending statistical measurements
 pub fn run_seq(
     graph: Arc<Mutex<TaskGraph>>,
+    config: &RunConfig,
     tx: Sender<MeasureDestination>,
 ) -> Result<(), jack::Error> {
+    let log_path = config.logging.get_log_path("seq");
+    let client = open_client(config, "audio_graph_sequential")?;
+
     tx.send(MeasureDestination::File(
-        "tmp/seq_log.txt".to_string(),
+        log_path.clone(),
         "Beginning of the execution".to_string(),
     ))
     .expect("logging error");
 
-    let (client, _) = jack::Client::new(
-        "audio_graph_sequential",
-        jack::ClientOptions::NO_START_SERVER,
-    )?;
-
     graph.lock().unwrap().set_sample_rate(client.sample_rate());
     graph
         .lock()
         .unwrap()
         .set_buffer_size(client.buffer_size() as usize);
 
-    let nb_exit_nodes = graph.lock().unwrap().get_exit_nodes().len();
+    let sinks = get_sinks(&mut graph.lock().unwrap());
+    let sources = get_sources(&graph.lock().unwrap());
+    let input_nodes = get_input_nodes(&graph.lock().unwrap());
 
     tx.send(MeasureDestination::File(
-        "tmp/seq_log.txt".to_string(),
-        format!("Number of exit nodes: {}", nb_exit_nodes),
+        log_path.clone(),
+        format!("Number of exit nodes: {}", sinks.len()),
     ))
     .expect("logging error");
 
-    let mut out_ports = Vec::with_capacity(nb_exit_nodes);
+    tx.send(MeasureDestination::File(
+        log_path.clone(),
+        format!("Number of input nodes: {}", sources.len()),
+    ))
+    .expect("logging error");
 
-    for i in 0..nb_exit_nodes {
-        let mut out_port =
-            client.register_port(&format!("port_{}", i), jack::AudioOut::default())?;
+    let mut out_ports = register_out_ports(&client, config, &sinks)?;
+    let in_ports = register_in_ports(&client, config, &sources)?;
+    let midi_port = register_midi_port(&client, config, &graph.lock().unwrap())?;
+    let midi = midi_port.is_some();
 
-        out_ports.push(out_port);
-    }
+    // The changes of parameters received by the OSC server
+    let (updates, mut updates_consumer) = ring_buffer(UPDATES_CAPACITY);
 
     // Allocate the audio buffers used by the DSPs of the audio graph
     let dsp_edges = Arc::new(Mutex::new(build_dsp_edges(
@@ -61,15 +78,31 @@ pub fn run_seq(
     // Get the sequential scheduling of the audio graph
     let exec_order = Arc::new(RwLock::new(graph.lock().unwrap().get_topological_order()));
 
+    // The execution times of the nodes, whose WCETs are estimated at the end
+    let statistics = Arc::new(ExecutionStatistics::new(graph.lock().unwrap().get_nb_node()));
+
+    // The events of the cycles, all on the JACK thread, collected by the
+    // measure thread and written in a Chrome trace at the end if asked
+    let trace = Arc::new(Trace::new(0, config.logging.trace));
+    let mut writer = trace.get_writer(0);
+
+    tx.send(MeasureDestination::Trace(trace.clone()))
+        .expect("logging error");
+
+    // The measurements of the cycles, written by the measure thread
+    let (mut records, records_consumer) = ring_buffer(RING_CAPACITY);
+
+    tx.send(MeasureDestination::Records(
+        log_path.clone(),
+        records_consumer,
+    ))
+    .expect("logging error");
+
     // The audio callback funtion
-    let callback = jack::ClosureProcessHandler::new(clone!(dsp_edges => move | _ , ps | {
+    let callback = jack::ClosureProcessHandler::new(clone!(dsp_edges, graph, statistics, trace => move | _ , ps | {
         // Save the time at which the function started its execution
         let start_time = std::time::SystemTime::now();
-        tx.send(MeasureDestination::File(
-            "tmp/seq_log.txt".to_string(),
-            format!("\nBeginning of a cycle at: {:#?}", start_time),
-        ))
-        .expect("logging error");
+        let cycle_start = trace.start_cycle();
 
         let graph = &mut *graph.lock().unwrap();
         let dsp_edges = &mut *dsp_edges.lock().unwrap();
@@ -90,6 +123,24 @@ pub fn run_seq(
             }
         }
 
+        // The input nodes read the buffers of the input ports
+        for (in_port, &node_index) in in_ports.iter().zip(&input_nodes) {
+            let buffer = in_port.as_slice(ps);
+
+            let input = graph.get_dsp(node_index);
+            let input = &mut *input.lock().unwrap();
+
+            if let Some(input) = input {
+                if let DspNode::Input(ref mut i) = input.dsp {
+                    i.set_buffer(buffer.as_ptr(), ps.n_frames());
+                }
+            }
+        }
+
+        // The MIDI messages and the OSC changes received during the last
+        // cycle drive the parameters of the nodes
+        apply_midi_port(graph, &midi_port, ps);
+        apply_parameter_updates(graph, &mut updates_consumer);
 
         // The execution of the audio graph happens here
         for &node_index in exec_order.read().unwrap().iter() {
@@ -116,6 +167,7 @@ pub fn run_seq(
 
                 let task = graph.get_dsp(node_index);
                 let task = &mut *task.lock().unwrap();
+                let task_start = writer.now();
 
                 if let Some(task) = task {
                     match task.dsp {
@@ -125,37 +177,57 @@ pub fn run_seq(
                         }
                         DspNode::InputsOutputsAdaptor(ref mut ioa) => ioa.process(in_edges, out_edges),
                         DspNode::Sink(ref mut s) => s.process(in_edges[0].clone()),
+                        DspNode::Input(ref mut i) => i.process(out_edges[0].clone()),
                     }
                 }
+
+                statistics.record(node_index, writer.now() - task_start);
+                writer.record(TraceEventKind::Task(node_index), task_start);
             }
         }
 
-        // Get the time spent for the execution of the audio graph
-        let elapsed_time = start_time.elapsed().unwrap();
-        let time_left = ps.cycle_times().unwrap().next_usecs - jack::get_time();
-
-        tx.send(MeasureDestination::File(
-            "tmp/seq_log.txt".to_string(),
-            format!(
-                "\nEnd of cycle at: {:#?} \nIn: {}µs\nTime left before the deadline: {}µs",
-                start_time,
-                elapsed_time.as_micros(),
-                time_left,
-            ),
-        ))
-        .expect("logging error");
+        writer.record(TraceEventKind::Cycle, cycle_start);
+
+        // Get the time left for the execution of the audio graph
+        let time_left = ps.cycle_times().unwrap().next_usecs as i64 - jack::get_time() as i64;
+
+        // Hand the measurements to the measure thread without allocating
+        records.push(MeasureRecord::new(start_time, time_left));
 
         // JACK will continue to call this function
         jack::Control::Continue
     }));
 
     // Tell JACK to start calling the callback function
-    let _active_client = client.activate_async((), callback)?;
+    let active_client =
+        client.activate_async(XrunNotifier::new(tx.clone(), &log_path), callback)?;
+
+    connect_ports(active_client.as_client(), config, &sinks, &sources, midi, &tx, &log_path);
+    let _osc_server = start_osc_server(config, &graph.lock().unwrap(), updates, &tx, &log_path);
 
     // Wait for an input from the user in order to not immediately exit
     // the program
     let mut user_input = String::new();
     let _ignored = std::io::stdin().read_line(&mut user_input);
 
+    log_observed_wcets(
+        &tx,
+        &log_path,
+        &graph.lock().unwrap(),
+        &statistics,
+        &config.executor.get_reschedule_policy(),
+    );
+
+    if trace.is_enabled() {
+        if let Err(e) = save_chrome_trace(
+            config.logging.get_trace_path("seq"),
+            &trace,
+            &graph.lock().unwrap(),
+            None,
+        ) {
+            eprintln!("Failed to write the trace: {}", e);
+        }
+    }
+
     Ok(())
 }
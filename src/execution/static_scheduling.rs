@@ -1,15 +1,138 @@
-use std::sync::{Arc, RwLock};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::Duration;
 
-use crossbeam::channel::Sender;
+use crossbeam::channel::{bounded, Receiver, Sender};
 
-use crate::dsp::DspNode;
-use crate::measure::MeasureDestination;
-use crate::static_scheduling::algorithms::{schedule, SchedulingAlgorithm};
+use crate::dsp::{DspEdge, DspNode};
+use crate::measure::trace::{save_chrome_trace, Trace, TraceEventKind};
+use crate::measure::ring::ring_buffer;
+use crate::measure::{MeasureDestination, MeasureRecord, RING_CAPACITY};
+use crate::osc::apply_parameter_updates;
+use crate::osc::server::UPDATES_CAPACITY;
+use crate::static_scheduling::algorithms::{schedule_on, SchedulingAlgorithm};
+use crate::static_scheduling::platform::Platform;
+use crate::static_scheduling::schedule::Schedule;
+use crate::static_scheduling::validation::{validate, Violation};
 use crate::task_graph::graph::TaskGraph;
 use crate::task_graph::state::TaskState;
+use crate::wcet::observation::{ExecutionStatistics, ReschedulePolicy};
+use crate::wcet::profiler::load_profiles;
 
 use super::thread_pool::static_scheduling::ThreadPool;
-use super::utils::build_dsp_edges;
+use super::config::RunConfig;
+use super::utils::{
+    apply_midi_port, build_dsp_edges, connect_ports, get_input_nodes, get_sinks, get_sources,
+    log_observed_wcets, open_client, register_in_ports, register_midi_port, register_out_ports,
+    register_worker_records, start_osc_server, XrunNotifier,
+};
+
+// How often the drift of the execution times is checked
+const DRIFT_CHECK_PERIOD: Duration = Duration::from_secs(1);
+
+// The buffers of the edges of the audio graph
+type DspEdges = Arc<RwLock<HashMap<(usize, usize), Arc<RwLock<DspEdge>>>>>;
+
+// Where the static scheduling executed comes from
+enum ScheduleSource {
+    Algorithm(SchedulingAlgorithm),
+    Loaded(Schedule),
+}
+
+// Schedules the audio graph again from the execution times observed during
+// the audio cycles and replaces the thread pool following the old schedule
+struct Rescheduler {
+    graph: Arc<RwLock<TaskGraph>>,
+    // A copy of the audio graph with the WCETs of the schedule, so that the
+    // audio graph is not locked while scheduling
+    sched_graph: Mutex<TaskGraph>,
+    dsp_edges: DspEdges,
+    // The schedule of the last thread pool handed to the audio callback,
+    // locked during a scheduling so that only one happens at a time
+    schedule: Mutex<Schedule>,
+    // The new thread pools, taken by the audio callback at the start of a
+    // cycle, and the ones they replaced, dropped out of the audio thread
+    pools: Sender<ThreadPool>,
+    retired_pools: Receiver<ThreadPool>,
+    // The execution times recorded by the last thread pool, replaced with
+    // it so that the times of the old schedule are forgotten
+    statistics: Mutex<Arc<ExecutionStatistics>>,
+    trace: Arc<Trace>,
+    platform: Platform,
+    sched_algo: SchedulingAlgorithm,
+    policy: ReschedulePolicy,
+    output_file: String,
+    worker_logs: Vec<String>, // The measurements of the cycles of each worker
+    tx: Sender<MeasureDestination>,
+}
+
+impl Rescheduler {
+    // Returns the drift of the observed execution times from the WCETs of the
+    // current schedule
+    fn get_drift(&self) -> f64 {
+        let statistics = self.statistics.lock().unwrap().clone();
+
+        statistics.get_drift(
+            &self.sched_graph.lock().unwrap(),
+            self.policy.estimator,
+            self.policy.min_samples,
+        )
+    }
+
+    // Computes a new schedule with the observed execution times and executes
+    // it from the next audio cycle, without blocking the audio cycles
+    fn reschedule(&self, reason: &str) {
+        let mut schedule = self.schedule.lock().unwrap();
+
+        // Schedule the copy of the graph, so that the audio cycles go on
+        // while the algorithm runs
+        let mut graph = self.sched_graph.lock().unwrap();
+        let statistics = self.statistics.lock().unwrap().clone();
+        let nb_updated =
+            statistics.apply(&mut graph, self.policy.estimator, self.policy.min_samples);
+        let sched = schedule_on(&mut graph, &self.platform, self.sched_algo);
+
+        self.tx
+            .send(MeasureDestination::File(
+                self.output_file.clone(),
+                format!(
+                    "\nScheduled again ({}) with {} observed WCETs, makespan: {}s",
+                    reason,
+                    nb_updated,
+                    sched.get_completion_time()
+                ),
+            ))
+            .expect("logging error");
+
+        // The drift is measured from the WCETs of the new schedule, with the
+        // execution times recorded by its thread pool only
+        let statistics = Arc::new(ExecutionStatistics::new(graph.get_nb_node()));
+        let thread_pool = ThreadPool::create(
+            self.platform.get_nb_processor(),
+            self.graph.clone(),
+            self.dsp_edges.clone(),
+            sched.clone(),
+            statistics.clone(),
+            self.trace.clone(),
+            register_worker_records(&self.tx, &self.worker_logs),
+        );
+
+        // Drop the thread pools replaced since the last scheduling, and hand
+        // the new one to the audio callback
+        while self.retired_pools.try_recv().is_ok() {}
+
+        self.pools
+            .send(thread_pool)
+            .expect("the audio callback is gone");
+        *schedule = sched;
+        *self.statistics.lock().unwrap() = statistics;
+
+        // The trace is compared with the new schedule
+        self.trace.clear();
+    }
+}
 
 /// Makes a parallel execution, with a static scheduling, of an audio graph
 /// with JACK.
@@ -17,21 +140,68 @@ use super::utils::build_dsp_edges;
 /// # Arguments
 ///
 /// * `graph` - The audio graph to be executed
-/// * `nb_threads` - The number of threads used for the execution
-/// * `sched_algo` - The algorithm to use for making the static scheduling
+/// * `config` - The number of threads used for the execution, their CPUs,
+///   the algorithm to use for making the static scheduling, when the audio
+///   graph is scheduled again from the execution times observed, the source
+///   of the WCETs, the JACK client, the logs and the output ports of the run
 /// * `tx` - The channel used for sending statistical measurements
 pub fn run_static_sched(
+    graph: Arc<RwLock<TaskGraph>>,
+    config: &RunConfig,
+    tx: Sender<MeasureDestination>,
+) -> Result<(), jack::Error> {
+    let sched_algo = config.executor.algorithm;
+
+    // Select the files to write into according to the scheduling algorithm
+    run(
+        graph,
+        config.executor.threads,
+        ScheduleSource::Algorithm(sched_algo),
+        config,
+        &format!("static_{}_sched", sched_algo.get_name()),
+        tx,
+    )
+}
+
+/// Makes a parallel execution of an audio graph with JACK, following a static
+/// scheduling computed beforehand, with a thread for each of its processors.
+///
+/// # Arguments
+///
+/// * `graph` - The audio graph to be executed
+/// * `sched` - The static scheduling of the audio graph
+/// * `config` - When the audio graph is scheduled again from the execution
+///   times observed, with the algorithm of the loaded scheduling, the source
+///   of the WCETs, the JACK client, the logs and the output ports of the run
+/// * `tx` - The channel used for sending statistical measurements
+pub fn run_loaded_sched(
+    graph: Arc<RwLock<TaskGraph>>,
+    sched: Schedule,
+    config: &RunConfig,
+    tx: Sender<MeasureDestination>,
+) -> Result<(), jack::Error> {
+    run(
+        graph,
+        sched.get_nb_processor(),
+        ScheduleSource::Loaded(sched),
+        config,
+        "static_loaded_sched",
+        tx,
+    )
+}
+
+// Executes the audio graph with the static scheduling of `source`
+fn run(
     graph: Arc<RwLock<TaskGraph>>,
     nb_threads: usize,
-    sched_algo: SchedulingAlgorithm,
+    source: ScheduleSource,
+    config: &RunConfig,
+    name: &str,
     tx: Sender<MeasureDestination>,
 ) -> Result<(), jack::Error> {
-    // Select the file to write into according to the scheduling algorithm
-    let output_file = String::from(match sched_algo {
-        SchedulingAlgorithm::Random => "tmp/static_rand_sched_log.txt",
-        SchedulingAlgorithm::HLFET => "tmp/static_hlfet_sched_log.txt",
-        SchedulingAlgorithm::ETF => "tmp/static_etf_sched_log.txt",
-    });
+    let policy = config.executor.get_reschedule_policy();
+    let output_file = config.logging.get_log_path(name);
+    let client = open_client(config, "audio_graph_static_sched")?;
 
     tx.send(MeasureDestination::File(
         output_file.clone(),
@@ -39,33 +209,53 @@ pub fn run_static_sched(
     ))
     .expect("logging error");
 
-    let (client, _) = jack::Client::new(
-        "audio_graph_static_sched",
-        jack::ClientOptions::NO_START_SERVER,
-    )?;
-
     graph.write().unwrap().set_sample_rate(client.sample_rate());
     graph
         .write()
         .unwrap()
         .set_buffer_size(client.buffer_size() as usize);
 
-    let nb_exit_nodes = graph.write().unwrap().get_exit_nodes().len();
+    // Take the WCETs of the DSPs from the profiles of the previous runs
+    graph.write().unwrap().set_wcet_source(config.wcet.source);
+
+    match load_profiles(&mut graph.write().unwrap(), &config.wcet.database) {
+        Ok(nb_profiled) => tx
+            .send(MeasureDestination::File(
+                output_file.clone(),
+                format!("\nNumber of DSPs profiled: {}", nb_profiled),
+            ))
+            .expect("logging error"),
+        Err(e) => tx
+            .send(MeasureDestination::File(
+                output_file.clone(),
+                format!("\nNo WCET profiles: {}", e),
+            ))
+            .expect("logging error"),
+    }
+
+    let sinks = get_sinks(&mut graph.write().unwrap());
+    let sources = get_sources(&graph.read().unwrap());
+    let input_nodes = get_input_nodes(&graph.read().unwrap());
 
     tx.send(MeasureDestination::File(
         output_file.clone(),
-        format!("Number of exit nodes: {}", nb_exit_nodes),
+        format!("Number of exit nodes: {}", sinks.len()),
     ))
     .expect("logging error");
 
-    let mut out_ports = Vec::with_capacity(nb_exit_nodes);
+    tx.send(MeasureDestination::File(
+        output_file.clone(),
+        format!("Number of input nodes: {}", sources.len()),
+    ))
+    .expect("logging error");
 
-    for i in 0..nb_exit_nodes {
-        let mut out_port =
-            client.register_port(&format!("port_{}", i), jack::AudioOut::default())?;
+    let mut out_ports = register_out_ports(&client, config, &sinks)?;
+    let in_ports = register_in_ports(&client, config, &sources)?;
+    let midi_port = register_midi_port(&client, config, &graph.read().unwrap())?;
+    let midi = midi_port.is_some();
 
-        out_ports.push(out_port);
-    }
+    // The changes of parameters received by the OSC server
+    let (updates, mut updates_consumer) = ring_buffer(UPDATES_CAPACITY);
 
     // Allocate the audio buffers used by the DSPs of the audio graph
     let dsp_edges = Arc::new(RwLock::new(build_dsp_edges(
@@ -73,26 +263,118 @@ pub fn run_static_sched(
         &client,
     )));
 
-    // Get the static scheduling of the audio graph
-    let sched = schedule(&mut graph.write().unwrap(), nb_threads, sched_algo);
+    // Detect the speeds of the CPUs the threads will be pinned on and the
+    // communication costs between them
+    let (platform, errors) = config
+        .executor
+        .get_platform(&graph.read().unwrap(), nb_threads);
+
+    for e in errors {
+        tx.send(MeasureDestination::File(
+            output_file.clone(),
+            format!("\n{}", e),
+        ))
+        .expect("logging error");
+    }
+
+    let (sched, sched_algo) = match source {
+        ScheduleSource::Algorithm(sched_algo) => {
+            // Get the static scheduling of the audio graph
+            (
+                schedule_on(&mut graph.write().unwrap(), &platform, sched_algo),
+                sched_algo,
+            )
+        }
+        ScheduleSource::Loaded(sched) => {
+            // The WCETs measured now differ from the ones of the saved
+            // schedule, so only the other violations are reported
+            let violations = validate(&mut graph.write().unwrap(), &sched);
+
+            for violation in violations
+                .iter()
+                .filter(|violation| !matches!(violation, Violation::Overrun { .. }))
+            {
+                tx.send(MeasureDestination::File(
+                    output_file.clone(),
+                    format!("\nInvalid schedule: {}", violation),
+                ))
+                .expect("logging error");
+            }
+
+            // The graph is scheduled again with the algorithm of the file
+            let sched_algo = sched
+                .algorithm
+                .as_ref()
+                .and_then(|name| SchedulingAlgorithm::from_name(name))
+                .unwrap_or(SchedulingAlgorithm::ETF);
+
+            (sched, sched_algo)
+        }
+    };
+
+    let nb_nodes = graph.read().unwrap().get_nb_node();
+    let statistics = Arc::new(ExecutionStatistics::new(nb_nodes));
+
+    // The events of the threads, collected by the measure thread and written
+    // in a Chrome trace at the end if asked
+    let trace = Arc::new(Trace::new(nb_threads, config.logging.trace));
+    let mut writer = trace.get_writer(trace.get_jack_thread());
+
+    tx.send(MeasureDestination::Trace(trace.clone()))
+        .expect("logging error");
+
+    // The measurements of the cycles of each worker, in their own logs
+    let worker_logs: Vec<String> = (0..nb_threads)
+        .map(|worker| config.logging.get_worker_log_path(name, worker))
+        .collect();
 
     // Create the thread pool with the appropriate number of threads
-    let thread_pool = Arc::new(RwLock::new(ThreadPool::create(
+    let mut thread_pool = ThreadPool::create(
         nb_threads,
         graph.clone(),
         dsp_edges.clone(),
-        sched,
-    )));
+        sched.clone(),
+        statistics.clone(),
+        trace.clone(),
+        register_worker_records(&tx, &worker_logs),
+    );
+
+    // A new thread pool waits for the next cycle while the next scheduling
+    // starts, so at most two pools are replaced between two schedulings
+    let (pools, new_pools) = bounded(1);
+    let (retired_pools_tx, retired_pools) = bounded(2);
+
+    let rescheduler = Arc::new(Rescheduler {
+        graph: graph.clone(),
+        sched_graph: Mutex::new(graph.read().unwrap().clone()),
+        dsp_edges: dsp_edges.clone(),
+        schedule: Mutex::new(sched),
+        pools,
+        retired_pools,
+        statistics: Mutex::new(statistics),
+        trace: trace.clone(),
+        platform,
+        sched_algo,
+        policy,
+        output_file: output_file.clone(),
+        worker_logs,
+        tx: tx.clone(),
+    });
+
+    // The measurements of the cycles, written by the measure thread
+    let (mut records, records_consumer) = ring_buffer(RING_CAPACITY);
+
+    tx.send(MeasureDestination::Records(
+        output_file.clone(),
+        records_consumer,
+    ))
+    .expect("logging error");
 
     // The audio callback funtion
-    let callback = jack::ClosureProcessHandler::new(clone!(thread_pool => move |_, ps| {
+    let callback = jack::ClosureProcessHandler::new(clone!(graph => move |_, ps| {
         // Save the time at which the function started its execution
         let start_time = std::time::SystemTime::now();
-        tx.send(MeasureDestination::File(
-            output_file.clone(),
-            format!("\nBeginning of a cycle at: {:#?}", start_time),
-        ))
-        .expect("logging error");
+        let cycle_start = trace.start_cycle();
 
         // We must give new buffers for the sinks to write into,
         // every time this callback function is called by JACK
@@ -111,6 +393,25 @@ pub fn run_static_sched(
             }
         }
 
+        // The input nodes read the buffers of the input ports
+        for (in_port, &node_index) in in_ports.iter().zip(&input_nodes) {
+            let buffer = in_port.as_slice(ps);
+
+            let input = graph.read().unwrap().get_dsp(node_index);
+            let input = &mut *input.lock().unwrap();
+
+            if let Some(input) = input {
+                if let DspNode::Input(ref mut i) = input.dsp {
+                    i.set_buffer(buffer.as_ptr(), ps.n_frames());
+                }
+            }
+        }
+
+        // The MIDI messages and the OSC changes received during the last
+        // cycle drive the parameters of the nodes
+        apply_midi_port(&graph.read().unwrap(), &midi_port, ps);
+        apply_parameter_updates(&graph.read().unwrap(), &mut updates_consumer);
+
         // We must reset the activation counters of each node
         let nb_nodes = graph.read().unwrap().get_nb_node();
         for node_index in 0..nb_nodes {
@@ -126,35 +427,95 @@ pub fn run_static_sched(
             }
         }
 
+        // Follow the new schedule from this cycle if the graph was scheduled
+        // again, the replaced thread pool being dropped by the rescheduler
+        if let Ok(new_pool) = new_pools.try_recv() {
+            let old_pool = std::mem::replace(&mut thread_pool, new_pool);
+            retired_pools_tx.try_send(old_pool).ok();
+        }
+
         // Execute the audio graph with the thread pool
-        thread_pool.write().unwrap().start();
+        let deadline = ps.cycle_times().unwrap().next_usecs;
+        thread_pool.start(Some(deadline));
+        writer.record(TraceEventKind::Cycle, cycle_start);
 
-        // Get the time spent for the execution of the audio graph
-        let elapsed_time = start_time.elapsed().unwrap();
-        let time_left = ps.cycle_times().unwrap().next_usecs - jack::get_time();
+        // Get the time left for the execution of the audio graph
+        let time_left = deadline as i64 - jack::get_time() as i64;
 
-        tx.send(MeasureDestination::File(
-            output_file.clone(),
-            format!(
-                "\nEnd of cycle at: {:#?} \nIn: {}µs\nTime left before the deadline: {}µs",
-                start_time,
-                elapsed_time.as_micros(),
-                time_left,
-            ),
-        ))
-        .expect("logging error");
+        // Hand the measurements to the measure thread without allocating
+        records.push(MeasureRecord::new(start_time, time_left));
 
         // JACK will continue to call this function
         jack::Control::Continue
     }));
 
     // Tell JACK to start calling the callback function
-    let _active_client = client.activate_async((), callback)?;
+    let active_client =
+        client.activate_async(XrunNotifier::new(tx.clone(), &output_file), callback)?;
+
+    connect_ports(active_client.as_client(), config, &sinks, &sources, midi, &tx, &output_file);
+    let _osc_server = start_osc_server(config, &graph.read().unwrap(), updates, &tx, &output_file);
+
+    // Schedule the audio graph again when the execution times drift from
+    // the WCETs of its schedule
+    let running = Arc::new(AtomicBool::new(true));
+
+    if let Some(drift_threshold) = policy.drift_threshold {
+        thread::spawn(clone!(rescheduler, running => move || {
+            while running.load(Ordering::Relaxed) {
+                thread::sleep(DRIFT_CHECK_PERIOD);
+
+                let drift = rescheduler.get_drift();
+
+                if drift > drift_threshold {
+                    rescheduler.reschedule(&format!("drift of {:.0}%", drift * 100.0));
+                }
+            }
+        }));
+    }
+
+    rescheduler
+        .tx
+        .send(MeasureDestination::Stdout(String::from(
+            "Press r and Enter to schedule the graph again from the observed execution times, \
+             Enter to quit",
+        )))
+        .expect("logging error");
 
     // Wait for an input from the user in order to not immediately exit
     // the program
-    let mut user_input = String::new();
-    let _ignored = std::io::stdin().read_line(&mut user_input);
+    loop {
+        let mut user_input = String::new();
+
+        match std::io::stdin().read_line(&mut user_input) {
+            Ok(_) if user_input.trim() == "r" => rescheduler.reschedule("on request"),
+            _ => break,
+        }
+    }
+
+    running.store(false, Ordering::Relaxed);
+
+    let statistics = rescheduler.statistics.lock().unwrap().clone();
+
+    log_observed_wcets(
+        &tx,
+        &output_file,
+        &rescheduler.sched_graph.lock().unwrap(),
+        &statistics,
+        &policy,
+    );
+
+    // Compare the last cycles with the schedule they followed
+    if rescheduler.trace.is_enabled() {
+        if let Err(e) = save_chrome_trace(
+            config.logging.get_trace_path(name),
+            &rescheduler.trace,
+            &graph.read().unwrap(),
+            Some(&rescheduler.schedule.lock().unwrap()),
+        ) {
+            eprintln!("Failed to write the trace: {}", e);
+        }
+    }
 
     Ok(())
 }
@@ -1,19 +1,24 @@
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 use std::thread;
+use std::time::SystemTime;
 
 use crossbeam::channel::{unbounded, Receiver, Sender};
 use crossbeam::utils::Backoff;
 
 use crate::dsp::DspEdge;
 use crate::execution::utils::exec_task;
+use crate::measure::ring::Producer;
+use crate::measure::trace::{Trace, TraceEventKind};
+use crate::measure::MeasureRecord;
 use crate::static_scheduling::schedule::Schedule;
 use crate::task_graph::graph::TaskGraph;
 use crate::task_graph::state::TaskState;
+use crate::wcet::observation::ExecutionStatistics;
 
 #[derive(Clone, Copy, PartialEq)]
 enum CtrlMsg {
-    Start, // Used for telling a worker to start the execution
+    Start(Option<u64>), // Used for telling a worker to start the execution, before the JACK deadline if any
     Reset, // Used for preventing deadlocks when a new cycle starts while some workers are not finished
 }
 
@@ -37,19 +42,34 @@ impl ThreadPool {
     /// * `task_graph` - The audio graph to be executed by the thread pool
     /// * `dsp_edges` - The buffers of the graph
     /// * `sched` - The static scheduling to be followed by the threads
+    /// * `statistics` - Where the execution times of the nodes are recorded
+    /// * `trace` - Where the executions and the waits of the threads are
+    ///   recorded, each thread having its own `TraceWriter`
+    /// * `records` - The rings in which the threads send the measurements of
+    ///   their cycles, one for each thread, or none
     pub fn create(
         threads_count: usize,
         task_graph: Arc<RwLock<TaskGraph>>,
         dsp_edges: Arc<RwLock<HashMap<(usize, usize), Arc<RwLock<DspEdge>>>>>,
         sched: Schedule,
+        statistics: Arc<ExecutionStatistics>,
+        trace: Arc<Trace>,
+        records: Vec<Producer<MeasureRecord>>,
     ) -> ThreadPool {
         let core_ids = core_affinity::get_core_ids().expect("Failed to get core IDs.");
+        let mut records = records.into_iter();
         let mut ctrl_chans = Vec::with_capacity(threads_count);
         let mut fb_chans = Vec::with_capacity(threads_count);
 
         for i in 0..threads_count {
-            let current_id = core_ids[i];
+            // Pin the thread on the CPU its processor was scheduled for
+            let current_id = sched.processors[i]
+                .core_id
+                .map(|id| core_affinity::CoreId { id })
+                .unwrap_or(core_ids[i]);
             let sched = sched.clone();
+            let mut writer = trace.get_writer(i);
+            let mut records = records.next();
 
             let (tx, rx) = unbounded();
             ctrl_chans.push(tx);
@@ -57,7 +77,7 @@ impl ThreadPool {
             let (f_tx, f_rx) = unbounded();
             fb_chans.push(f_rx);
 
-            thread::spawn(clone!(task_graph, dsp_edges => move || {
+            thread::spawn(clone!(task_graph, dsp_edges, statistics => move || {
                 // Set the affinity so that a thread will always be executed
                 // on the same CPU
                 core_affinity::set_for_current(current_id);
@@ -65,16 +85,16 @@ impl ThreadPool {
                 // The main loop of the worker
                 loop {
                     // Wait for the next audio cycle
-                    match rx.recv() {
-                        Err(_) => {
-                            println!("Failed to get more control messages");
-                            break;
-                        }
+                    let deadline = match rx.recv() {
+                        // The thread pool has been dropped, when the graph is
+                        // scheduled again
+                        Err(_) => break,
                         Ok(ctrl_msg) => match ctrl_msg {
                             CtrlMsg::Reset => continue, // Ignore the reset message
-                            CtrlMsg::Start => {} // Start of the cycle
+                            CtrlMsg::Start(deadline) => deadline, // Start of the cycle
                         }
-                    }
+                    };
+                    let cycle_start = SystemTime::now();
 
                     // Execute sequentially the tasks scheduled on the worker's `Processor`
                     'processing: for node_index in sched.processors[i].time_slots.iter().map(|ts| ts.get_node()) {
@@ -87,6 +107,9 @@ impl ThreadPool {
                         );
 
                         // Wait the the task to become ready
+                        let wait_start = writer.now();
+                        let mut waited = false;
+
                         while task_graph.read().unwrap().get_state(node_index) != Some(TaskState::Ready) {
                             // Do not wait any longer if the next cycle already started
                             if rx.try_recv() == Ok(CtrlMsg::Reset) {
@@ -94,10 +117,25 @@ impl ThreadPool {
                             }
 
                             backoff.snooze();
+                            waited = true;
+                        }
+
+                        if waited {
+                            writer.record(TraceEventKind::Wait(node_index), wait_start);
                         }
 
-                        // Execute the task
+                        // Execute the task and record its execution time
+                        let start_time = writer.now();
                         exec_task(node_index, task_graph.clone(), dsp_edges.clone(), None);
+                        statistics.record(node_index, writer.now() - start_time);
+                        writer.record(TraceEventKind::Task(node_index), start_time);
+                    }
+
+                    // Hand the measurements of the worker to the measure
+                    // thread without allocating
+                    if let Some(ref mut records) = records {
+                        let time_left = deadline.map_or(0, |deadline| deadline as i64 - jack::get_time() as i64);
+                        records.push(MeasureRecord::new(cycle_start, time_left));
                     }
 
                     // Notify the main thread the worker is done for this cycle
@@ -115,7 +153,12 @@ impl ThreadPool {
     /// Tell the thread pool the audio cycle has started so that it will
     /// execute the audio graph. This method blocks until the end of the
     /// execution.
-    pub fn start(&mut self) {
+    ///
+    /// # Arguments
+    ///
+    /// * `deadline` - The JACK time, in µs, at which the cycle must be done,
+    ///   if any, from which the workers measure their time left
+    pub fn start(&mut self, deadline: Option<u64>) {
         // Reset any worker which would still be executing tasks from
         // the previous cycle
         for chan in self.ctrl_chans.iter() {
@@ -124,7 +167,7 @@ impl ThreadPool {
 
         // Notify the workers a new cycle started
         for chan in self.ctrl_chans.iter() {
-            chan.send(CtrlMsg::Start).unwrap();
+            chan.send(CtrlMsg::Start(deadline)).unwrap();
         }
 
         // Wait for every workers to be done
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 use std::thread;
+use std::time::SystemTime;
 
 use crossbeam::channel::{unbounded, Receiver, Sender};
 use crossbeam::deque::{Injector, Steal, Worker};
@@ -8,11 +9,15 @@ use crossbeam::sync::ShardedLock;
 
 use crate::dsp::DspEdge;
 use crate::execution::utils::exec_task;
+use crate::measure::ring::Producer;
+use crate::measure::trace::{Trace, TraceEventKind, TraceWriter};
+use crate::measure::MeasureRecord;
 use crate::task_graph::graph::TaskGraph;
+use crate::wcet::observation::ExecutionStatistics;
 
 #[derive(Clone, Copy)]
 enum CtrlMsg {
-    Start, // Used for telling a worker to start the execution
+    Start(Option<u64>), // Used for telling a worker to start the execution, before the JACK deadline if any
     Stop, // Used for telling a worker to exit
 }
 
@@ -36,14 +41,29 @@ impl ThreadPool {
     /// # Arguments
     ///
     /// * `threads_count` - The number of threads of the pool
+    /// * `cores` - The logical CPUs the threads are pinned on, the first
+    ///   ones of the machine if empty
     /// * `task_graph` - The audio graph to be executed by the thread pool
     /// * `dsp_edges` - The buffers of the graph
+    /// * `statistics` - Where the execution times of the nodes are recorded
+    /// * `trace` - Where the executions and the steals of the threads are
+    ///   recorded, each thread having its own `TraceWriter`
+    /// * `records` - The rings in which the threads send the measurements of
+    ///   their cycles, one for each thread, or none
     pub fn create(
         threads_count: usize,
+        cores: &[usize],
         task_graph: Arc<RwLock<TaskGraph>>,
         dsp_edges: Arc<RwLock<HashMap<(usize, usize), Arc<RwLock<DspEdge>>>>>,
+        statistics: Arc<ExecutionStatistics>,
+        trace: Arc<Trace>,
+        records: Vec<Producer<MeasureRecord>>,
     ) -> ThreadPool {
-        let core_ids = core_affinity::get_core_ids().expect("Failed to get core IDs.");
+        let core_ids: Vec<core_affinity::CoreId> = if cores.is_empty() {
+            core_affinity::get_core_ids().expect("Failed to get core IDs.")
+        } else {
+            cores.iter().map(|&id| core_affinity::CoreId { id }).collect()
+        };
         let mut join_handles = Vec::with_capacity(threads_count);
         // The main queue used for the initial distribution of tasks to the
         // workers
@@ -52,9 +72,10 @@ impl ThreadPool {
         let stealers = Arc::new(ShardedLock::new(Vec::with_capacity(threads_count)));
         let mut ctrl_chans = Vec::with_capacity(threads_count);
         let mut fb_chans = Vec::with_capacity(threads_count);
+        let mut records = records.into_iter();
 
         for i in 0..threads_count {
-            let current_id = core_ids[i];
+            let current_id = core_ids[i % core_ids.len()];
 
             // The queue of the worker
             let worker_queue = Worker::new_lifo();
@@ -66,9 +87,23 @@ impl ThreadPool {
             let (f_tx, f_rx) = unbounded();
             fb_chans.push(f_rx);
 
+            let mut writer = trace.get_writer(i);
+            let mut records = records.next();
+
             join_handles.push(thread::spawn(
-                clone!(main_queue, stealers, task_graph, dsp_edges => move || {
+                clone!(main_queue, stealers, task_graph, dsp_edges, statistics => move || {
                     let mut init = true;
+                    let mut cycle_start = SystemTime::now();
+                    let mut deadline = None;
+
+                    // Execute a task and record its execution
+                    let exec = |node_index: usize, worker_queue: &Worker<usize>, writer: &mut TraceWriter| {
+                        let start_time = writer.now();
+                        exec_task(node_index, task_graph.clone(), dsp_edges.clone(), Some(worker_queue));
+                        statistics.record(node_index, writer.now() - start_time);
+                        writer.record(TraceEventKind::Task(node_index), start_time);
+                    };
+
                     // Set the affinity so that a thread will always be
                     // executed on the same CPU
                     core_affinity::set_for_current(current_id);
@@ -90,6 +125,16 @@ impl ThreadPool {
                                             // the main the worker is done
                                             // for this cycle.
                                             if !init {
+                                                // Hand the measurements of the
+                                                // worker to the measure thread
+                                                // without allocating
+                                                if let Some(ref mut records) = records {
+                                                    let time_left = deadline.map_or(0, |deadline: u64| {
+                                                        deadline as i64 - jack::get_time() as i64
+                                                    });
+                                                    records.push(MeasureRecord::new(cycle_start, time_left));
+                                                }
+
                                                 f_tx.send(FeedbackMsg::Done).unwrap();
                                             } else {
                                                 init = false;
@@ -103,7 +148,9 @@ impl ThreadPool {
                                                 }
                                                 Ok(ctrl_msg) => match ctrl_msg {
                                                     CtrlMsg::Stop => break,
-                                                    CtrlMsg::Start => {
+                                                    CtrlMsg::Start(cycle_deadline) => {
+                                                        cycle_start = SystemTime::now();
+                                                        deadline = cycle_deadline;
                                                         continue;
                                                     }
                                                 }
@@ -114,15 +161,16 @@ impl ThreadPool {
                                             // one of them and execute it
                                             for j in 0..threads_count {
                                                 if j != i {
+                                                    let steal_start = writer.now();
+
                                                     if let Steal::Success(node_index) =
-                                                        stealers.read().unwrap()[i].steal()
+                                                        stealers.read().unwrap()[j].steal()
                                                     {
-                                                        exec_task(
-                                                            node_index,
-                                                            task_graph.clone(),
-                                                            dsp_edges.clone(),
-                                                            Some(&worker_queue),
+                                                        writer.record(
+                                                            TraceEventKind::Steal { node: node_index, victim: j },
+                                                            steal_start,
                                                         );
+                                                        exec(node_index, &worker_queue, &mut writer);
                                                     }
                                                 }
                                             }
@@ -131,23 +179,14 @@ impl ThreadPool {
                                     // If there is a task in the main queue
                                     // take and execute it
                                     Steal::Success(node_index) => {
-                                        exec_task(
-                                            node_index,
-                                            task_graph.clone(),
-                                            dsp_edges.clone(),
-                                            Some(&worker_queue),
-                                        );
+                                        exec(node_index, &worker_queue, &mut writer);
                                     }
                                 }
                             }
                             // If there is a task in the worker's queue
                             // execute it
                             Some(node_index) => {
-                                exec_task(node_index,
-                                    task_graph.clone(),
-                                    dsp_edges.clone(),
-                                    Some(&worker_queue)
-                                );
+                                exec(node_index, &worker_queue, &mut writer);
                             }
                         }
                     }
@@ -167,7 +206,12 @@ impl ThreadPool {
     /// Tell the thread pool the audio cycle has started so that it will
     /// execute the audio graph. This method blocks until the end of the
     /// execution.
-    pub fn start(&mut self) {
+    ///
+    /// # Arguments
+    ///
+    /// * `deadline` - The JACK time, in µs, at which the cycle must be done,
+    ///   if any, from which the workers measure their time left
+    pub fn start(&mut self, deadline: Option<u64>) {
         let entry_nodes = self.task_graph.write().unwrap().get_entry_nodes();
 
         // Put the first tasks to be ready in the main queue
@@ -177,7 +221,7 @@ impl ThreadPool {
 
         // Notify the workers a new cycle started
         for chan in self.ctrl_chans.iter() {
-            chan.send(CtrlMsg::Start).unwrap();
+            chan.send(CtrlMsg::Start(deadline)).unwrap();
         }
 
         // Wait for every workers to be done
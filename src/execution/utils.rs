@@ -1,11 +1,20 @@
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 
+use crossbeam::channel::Sender;
 use crossbeam::deque::Worker;
 
 use crate::dsp::{DspEdge, DspNode};
+use crate::measure::ring::{ring_buffer, Producer};
+use crate::measure::{MeasureDestination, MeasureRecord, RING_CAPACITY};
+use crate::midi::{apply_midi_message, MidiMessage};
+use crate::osc::server::OscServer;
+use crate::osc::ParameterUpdate;
+
+use super::config::RunConfig;
 use crate::task_graph::graph::TaskGraph;
 use crate::task_graph::state::TaskState;
+use crate::wcet::observation::{ExecutionStatistics, ReschedulePolicy};
 
 // Make moving clones into closures more convenient
 macro_rules! clone {
@@ -34,15 +43,27 @@ macro_rules! clone {
 pub fn build_dsp_edges(
     graph: &TaskGraph,
     client: &jack::Client,
+) -> HashMap<(usize, usize), Arc<RwLock<DspEdge>>> {
+    new_dsp_edges(graph, client.buffer_size() as usize, client.sample_rate())
+}
+
+/// Allocate the audio buffers for the audio graph, without JACK.
+///
+/// # Arguments
+///
+/// * `graph` - The audio graph to allocate the buffers for
+/// * `buffer_size` - The size of the buffers
+/// * `sample_rate` - The sample rate
+pub fn new_dsp_edges(
+    graph: &TaskGraph,
+    buffer_size: usize,
+    sample_rate: usize,
 ) -> HashMap<(usize, usize), Arc<RwLock<DspEdge>>> {
     let g_edges = graph.get_edges();
     let mut edges = HashMap::with_capacity(g_edges.len());
 
     for (src, dst) in g_edges.keys() {
-        let buff = Arc::new(RwLock::new(DspEdge::new(
-            client.buffer_size() as usize,
-            client.sample_rate(),
-        )));
+        let buff = Arc::new(RwLock::new(DspEdge::new(buffer_size, sample_rate)));
 
         edges.insert((*src, *dst), buff);
     }
@@ -50,6 +71,390 @@ pub fn build_dsp_edges(
     edges
 }
 
+/// Reports the xruns of a JACK client to the measure thread, for the
+/// statistics of a run.
+pub struct XrunNotifier {
+    tx: Sender<MeasureDestination>,
+    path: String, // The file of the records of the run
+}
+
+impl XrunNotifier {
+    /// Creates an `XrunNotifier`.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - The channel used for sending statistical measurements
+    /// * `path` - The file of the records of the run
+    pub fn new(tx: Sender<MeasureDestination>, path: &str) -> XrunNotifier {
+        XrunNotifier {
+            tx,
+            path: path.to_string(),
+        }
+    }
+}
+
+impl jack::NotificationHandler for XrunNotifier {
+    fn xrun(&mut self, _: &jack::Client) -> jack::Control {
+        // The notifications are not handled by the real-time thread
+        let _ = self.tx.send(MeasureDestination::Xrun(self.path.clone()));
+
+        jack::Control::Continue
+    }
+}
+
+/// Opens the JACK client of a run, after creating the directory of its logs.
+///
+/// # Arguments
+///
+/// * `config` - The configuration of the run
+/// * `default_name` - The name of the client if the configuration gives none
+pub fn open_client(config: &RunConfig, default_name: &str) -> Result<jack::Client, jack::Error> {
+    if let Err(e) = std::fs::create_dir_all(&config.logging.directory) {
+        eprintln!("Failed to create {}: {}", config.logging.directory, e);
+    }
+
+    let (client, _) = jack::Client::new(
+        &config.backend.get_client_name(default_name),
+        config.backend.get_client_options(),
+    )?;
+
+    Ok(client)
+}
+
+/// Returns the ids of the exit nodes of a graph, in the order of their output
+/// ports, with the JACK ports the graph file connects them to.
+///
+/// # Arguments
+///
+/// * `graph` - The audio graph of the run
+pub fn get_sinks(graph: &mut TaskGraph) -> Vec<(String, Vec<String>)> {
+    let exit_nodes = graph.get_exit_nodes();
+
+    describe_port_nodes(graph, &exit_nodes)
+}
+
+/// Returns the input nodes of a graph, in the order of their input ports.
+///
+/// # Arguments
+///
+/// * `graph` - The audio graph of the run
+pub fn get_input_nodes(graph: &TaskGraph) -> Vec<usize> {
+    (0..graph.get_nb_node())
+        .filter(|&node_index| {
+            let dsp = graph.get_dsp(node_index);
+            let dsp = dsp.lock().unwrap();
+
+            matches!(dsp.as_ref().map(|dsp| &dsp.dsp), Some(DspNode::Input(_)))
+        })
+        .collect()
+}
+
+/// Returns the ids of the input nodes of a graph, in the order of their input
+/// ports, with the JACK ports the graph file connects them to.
+///
+/// # Arguments
+///
+/// * `graph` - The audio graph of the run
+pub fn get_sources(graph: &TaskGraph) -> Vec<(String, Vec<String>)> {
+    describe_port_nodes(graph, &get_input_nodes(graph))
+}
+
+// Returns the ids of nodes with a port, and the JACK ports of the graph file
+fn describe_port_nodes(graph: &TaskGraph, node_indices: &[usize]) -> Vec<(String, Vec<String>)> {
+    node_indices
+        .iter()
+        .map(|&node_index| {
+            (
+                graph.get_node_name(node_index),
+                graph.get_connections(node_index).to_vec(),
+            )
+        })
+        .collect()
+}
+
+/// Registers the output ports of a run, one for each exit node, named after
+/// the node.
+///
+/// # Arguments
+///
+/// * `client` - The JACK client of the run
+/// * `config` - The configuration of the run, which names the ports
+/// * `sinks` - The exit nodes, given by `get_sinks`
+pub fn register_out_ports(
+    client: &jack::Client,
+    config: &RunConfig,
+    sinks: &[(String, Vec<String>)],
+) -> Result<Vec<jack::Port<jack::AudioOut>>, jack::Error> {
+    sinks
+        .iter()
+        .map(|(sink_id, _)| {
+            client.register_port(
+                &config.ports.get_port_name(sink_id),
+                jack::AudioOut::default(),
+            )
+        })
+        .collect()
+}
+
+/// Registers the input ports of a run, one for each input node, named after
+/// the node.
+///
+/// # Arguments
+///
+/// * `client` - The JACK client of the run
+/// * `config` - The configuration of the run, which names the ports
+/// * `sources` - The input nodes, given by `get_sources`
+pub fn register_in_ports(
+    client: &jack::Client,
+    config: &RunConfig,
+    sources: &[(String, Vec<String>)],
+) -> Result<Vec<jack::Port<jack::AudioIn>>, jack::Error> {
+    sources
+        .iter()
+        .map(|(input_id, _)| {
+            client.register_port(
+                &config.ports.get_port_name(input_id),
+                jack::AudioIn::default(),
+            )
+        })
+        .collect()
+}
+
+/// The name of the MIDI input port of the runs, before the prefix of the
+/// ports.
+pub const MIDI_PORT: &str = "midi_in";
+
+/// Registers the MIDI input port of a run if its graph maps MIDI messages to
+/// the parameters of its nodes.
+///
+/// # Arguments
+///
+/// * `client` - The JACK client of the run
+/// * `config` - The configuration of the run, which names the port
+/// * `graph` - The audio graph of the run
+pub fn register_midi_port(
+    client: &jack::Client,
+    config: &RunConfig,
+    graph: &TaskGraph,
+) -> Result<Option<jack::Port<jack::MidiIn>>, jack::Error> {
+    if graph.get_midi_mappings().is_empty() {
+        return Ok(None);
+    }
+
+    client
+        .register_port(
+            &config.ports.get_port_name(MIDI_PORT),
+            jack::MidiIn::default(),
+        )
+        .map(Some)
+}
+
+/// Applies the MIDI messages received during the last cycle to the parameters
+/// of the nodes, before the graph is processed.
+///
+/// # Arguments
+///
+/// * `graph` - The audio graph of the run
+/// * `midi_port` - The MIDI input port, given by `register_midi_port`
+/// * `ps` - The process scope of the cycle
+pub fn apply_midi_port(
+    graph: &TaskGraph,
+    midi_port: &Option<jack::Port<jack::MidiIn>>,
+    ps: &jack::ProcessScope,
+) {
+    if let Some(midi_port) = midi_port {
+        for event in midi_port.iter(ps) {
+            if let Some(message) = MidiMessage::parse(event.bytes) {
+                apply_midi_message(graph, &message);
+            }
+        }
+    }
+}
+
+/// Connects the ports of a run to the ports of its configuration or of its
+/// graph file, or else the output ports to the playback ports and the input
+/// ports to the capture ports, writing the connections and their failures in
+/// its log.
+///
+/// # Arguments
+///
+/// * `client` - The JACK client of the run, once activated
+/// * `config` - The configuration of the run
+/// * `sinks` - The exit nodes, given by `get_sinks`
+/// * `sources` - The input nodes, given by `get_sources`
+/// * `midi` - Whether the run has a MIDI input port, connected to a physical
+///   MIDI port
+/// * `tx` - The channel used for sending statistical measurements
+/// * `path` - The log of the run
+pub fn connect_ports(
+    client: &jack::Client,
+    config: &RunConfig,
+    sinks: &[(String, Vec<String>)],
+    sources: &[(String, Vec<String>)],
+    midi: bool,
+    tx: &Sender<MeasureDestination>,
+    path: &str,
+) {
+    // The physical ports, which receive the outputs and send the inputs
+    let get_physical_ports = |pattern: &str, flags: jack::PortFlags| {
+        if config.ports.auto_connect {
+            client.ports(Some(pattern), Some("audio"), flags)
+        } else {
+            Vec::new()
+        }
+    };
+    let playback = get_physical_ports(&config.ports.playback, jack::PortFlags::IS_INPUT);
+    let capture = get_physical_ports(&config.ports.capture, jack::PortFlags::IS_OUTPUT);
+
+    let outputs = config
+        .ports
+        .get_connections(sinks, &playback)
+        .into_iter()
+        .map(|(port_name, destination)| (format!("{}:{}", client.name(), port_name), destination));
+    let inputs = config
+        .ports
+        .get_connections(sources, &capture)
+        .into_iter()
+        .map(|(port_name, source)| (source, format!("{}:{}", client.name(), port_name)));
+
+    let midi_capture = if midi && config.ports.auto_connect {
+        client.ports(
+            None,
+            Some("midi"),
+            jack::PortFlags::IS_OUTPUT | jack::PortFlags::IS_PHYSICAL,
+        )
+    } else {
+        Vec::new()
+    };
+    let midi_sources = if midi {
+        vec![(MIDI_PORT.to_string(), Vec::new())]
+    } else {
+        Vec::new()
+    };
+    let midi_inputs = config
+        .ports
+        .get_connections(&midi_sources, &midi_capture)
+        .into_iter()
+        .map(|(port_name, source)| (source, format!("{}:{}", client.name(), port_name)));
+
+    for (source, destination) in outputs.chain(inputs).chain(midi_inputs) {
+        let message = match client.connect_ports_by_name(&source, &destination) {
+            Ok(()) => format!("Connected {} to {}", source, destination),
+            Err(e) => format!("Failed to connect {} to {}: {:?}", source, destination, e),
+        };
+
+        tx.send(MeasureDestination::File(path.to_string(), message))
+            .expect("logging error");
+    }
+}
+
+/// Starts the OSC server of a run if its configuration enables it, writing its
+/// address or its failure in its log. The server stops when it is dropped.
+///
+/// # Arguments
+///
+/// * `config` - The configuration of the run
+/// * `graph` - The audio graph of the run
+/// * `updates` - The ring the changes of parameters are sent to the audio
+///   thread on
+/// * `tx` - The channel used for sending statistical measurements
+/// * `path` - The log of the run
+pub fn start_osc_server(
+    config: &RunConfig,
+    graph: &TaskGraph,
+    updates: Producer<ParameterUpdate>,
+    tx: &Sender<MeasureDestination>,
+    path: &str,
+) -> Option<OscServer> {
+    if !config.osc.enabled {
+        return None;
+    }
+
+    let (server, message) =
+        match OscServer::start(&config.osc.address, graph, updates, tx.clone(), path) {
+            Ok(server) => {
+                let message = format!("OSC server listening on {}", server.get_address());
+                (Some(server), message)
+            }
+            Err(e) => (
+                None,
+                format!(
+                    "Failed to start the OSC server on {}: {}",
+                    config.osc.address, e
+                ),
+            ),
+        };
+
+    tx.send(MeasureDestination::File(path.to_string(), message))
+        .expect("logging error");
+
+    server
+}
+
+/// Creates the rings in which the worker threads of a thread pool send the
+/// measurements of their cycles, each drained by the measure thread into its
+/// own log.
+///
+/// # Arguments
+/// * `tx` - The channel used for sending statistical measurements
+/// * `paths` - The logs of the workers
+pub fn register_worker_records(
+    tx: &Sender<MeasureDestination>,
+    paths: &[String],
+) -> Vec<Producer<MeasureRecord>> {
+    paths
+        .iter()
+        .map(|path| {
+            let (records, consumer) = ring_buffer(RING_CAPACITY);
+
+            tx.send(MeasureDestination::Records(path.clone(), consumer))
+                .expect("logging error");
+
+            records
+        })
+        .collect()
+}
+
+/// Writes in the log of a run the WCETs of the nodes estimated from the
+/// execution times observed during its cycles, and their drift from the WCETs
+/// of the graph.
+///
+/// # Arguments
+/// * `tx` - The channel used for sending statistical measurements
+/// * `path` - The log of the run
+/// * `graph` - The audio graph of the run
+/// * `statistics` - The execution times of the nodes
+/// * `policy` - How the WCETs are estimated, and from how many observations
+pub fn log_observed_wcets(
+    tx: &Sender<MeasureDestination>,
+    path: &str,
+    graph: &TaskGraph,
+    statistics: &ExecutionStatistics,
+    policy: &ReschedulePolicy,
+) {
+    for node_index in 0..graph.get_nb_node() {
+        if let Some(wcet) = statistics.estimate(node_index, policy.estimator, policy.min_samples) {
+            tx.send(MeasureDestination::File(
+                path.to_string(),
+                format!(
+                    "Observed WCET of {}: {}s",
+                    graph.get_node_name(node_index),
+                    wcet
+                ),
+            ))
+            .expect("logging error");
+        }
+    }
+
+    let drift = statistics.get_drift(graph, policy.estimator, policy.min_samples);
+
+    tx.send(MeasureDestination::File(
+        path.to_string(),
+        format!("Drift of the observed WCETs: {:.0}%", drift * 100.0),
+    ))
+    .expect("logging error");
+}
+
 /// Helper function for executing a single task.
 ///
 /// # Arguments
@@ -102,6 +507,7 @@ pub fn exec_task(
                 }
                 DspNode::InputsOutputsAdaptor(ref mut ioa) => ioa.process(in_edges, out_edges),
                 DspNode::Sink(ref mut s) => s.process(in_edges[0].clone()),
+                DspNode::Input(ref mut i) => i.process(out_edges[0].clone()),
             }
         }
 
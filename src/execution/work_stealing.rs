@@ -3,12 +3,22 @@ use std::sync::{Arc, RwLock};
 use crossbeam::channel::Sender;
 
 use crate::dsp::DspNode;
-use crate::measure::MeasureDestination;
+use crate::measure::trace::{save_chrome_trace, Trace, TraceEventKind};
+use crate::measure::ring::ring_buffer;
+use crate::measure::{MeasureDestination, MeasureRecord, RING_CAPACITY};
+use crate::osc::apply_parameter_updates;
+use crate::osc::server::UPDATES_CAPACITY;
 use crate::task_graph::graph::TaskGraph;
 use crate::task_graph::state::TaskState;
+use crate::wcet::observation::ExecutionStatistics;
 
 use super::thread_pool::work_stealing::ThreadPool;
-use super::utils::build_dsp_edges;
+use super::config::RunConfig;
+use super::utils::{
+    apply_midi_port, build_dsp_edges, connect_ports, get_input_nodes, get_sinks, get_sources,
+    log_observed_wcets, open_client, register_in_ports, register_midi_port, register_out_ports,
+    register_worker_records, start_osc_server, XrunNotifier,
+};
 
 /// Makes a parallel execution, with a dynamic work stealing scheduling, of
 /// an audio graph with JACK.
@@ -16,46 +26,53 @@ use super::utils::build_dsp_edges;
 /// # Arguments
 ///
 /// * `graph` - The audio graph to be executed
-/// * `nb_threads` - The number of threads used for the execution
+/// * `config` - The number of threads used for the execution, their CPUs,
+///   the JACK client, the logs and the output ports of the run
 /// * `tx` - The channel used for sending statistical measurements
 pub fn run_work_stealing(
     graph: Arc<RwLock<TaskGraph>>,
-    nb_threads: usize,
+    config: &RunConfig,
     tx: Sender<MeasureDestination>,
 ) -> Result<(), jack::Error> {
+    let nb_threads = config.executor.threads;
+    let log_path = config.logging.get_log_path("work_stealing");
+    let client = open_client(config, "audio_graph_work_stealing")?;
+
     tx.send(MeasureDestination::File(
-        "tmp/work_stealing_log.txt".to_string(),
+        log_path.clone(),
         format!("Beginning of the execution"),
     ))
     .expect("logging error");
 
-    let (client, _) = jack::Client::new(
-        "audio_graph_work_stealing",
-        jack::ClientOptions::NO_START_SERVER,
-    )?;
-
     graph.write().unwrap().set_sample_rate(client.sample_rate());
     graph
         .write()
         .unwrap()
         .set_buffer_size(client.buffer_size() as usize);
 
-    let nb_exit_nodes = graph.write().unwrap().get_exit_nodes().len();
+    let sinks = get_sinks(&mut graph.write().unwrap());
+    let sources = get_sources(&graph.read().unwrap());
+    let input_nodes = get_input_nodes(&graph.read().unwrap());
 
     tx.send(MeasureDestination::File(
-        "tmp/work_stealing_log.txt".to_string(),
-        format!("Number of exit nodes: {}", nb_exit_nodes),
+        log_path.clone(),
+        format!("Number of exit nodes: {}", sinks.len()),
     ))
     .expect("logging error");
 
-    let mut out_ports = Vec::with_capacity(nb_exit_nodes);
+    tx.send(MeasureDestination::File(
+        log_path.clone(),
+        format!("Number of input nodes: {}", sources.len()),
+    ))
+    .expect("logging error");
 
-    for i in 0..nb_exit_nodes {
-        let mut out_port =
-            client.register_port(&format!("port_{}", i), jack::AudioOut::default())?;
+    let mut out_ports = register_out_ports(&client, config, &sinks)?;
+    let in_ports = register_in_ports(&client, config, &sources)?;
+    let midi_port = register_midi_port(&client, config, &graph.read().unwrap())?;
+    let midi = midi_port.is_some();
 
-        out_ports.push(out_port);
-    }
+    // The changes of parameters received by the OSC server
+    let (updates, mut updates_consumer) = ring_buffer(UPDATES_CAPACITY);
 
     // Allocate the audio buffers used by the DSPs of the audio graph
     let dsp_edges = Arc::new(RwLock::new(build_dsp_edges(
@@ -63,21 +80,46 @@ pub fn run_work_stealing(
         &client,
     )));
 
+    // The execution times of the nodes, whose WCETs are estimated at the end
+    let statistics = Arc::new(ExecutionStatistics::new(graph.read().unwrap().get_nb_node()));
+
+    // The events of the threads, collected by the measure thread and written
+    // in a Chrome trace at the end if asked
+    let trace = Arc::new(Trace::new(nb_threads, config.logging.trace));
+    let mut writer = trace.get_writer(trace.get_jack_thread());
+
+    tx.send(MeasureDestination::Trace(trace.clone()))
+        .expect("logging error");
+
+    // The measurements of the cycles of each worker, in their own logs
+    let worker_logs: Vec<String> = (0..nb_threads)
+        .map(|worker| config.logging.get_worker_log_path("work_stealing", worker))
+        .collect();
+
     // Create the thread pool with the appropriate number of threads
     let thread_pool = Arc::new(RwLock::new(ThreadPool::create(
         nb_threads,
+        &config.executor.cores,
         graph.clone(),
         dsp_edges.clone(),
+        statistics.clone(),
+        trace.clone(),
+        register_worker_records(&tx, &worker_logs),
     )));
 
+    // The measurements of the cycles, written by the measure thread
+    let (mut records, records_consumer) = ring_buffer(RING_CAPACITY);
+
+    tx.send(MeasureDestination::Records(
+        log_path.clone(),
+        records_consumer,
+    ))
+    .expect("logging error");
+
     // The audio callback funtion
-    let callback = jack::ClosureProcessHandler::new(clone!(thread_pool => move |_, ps| {
+    let callback = jack::ClosureProcessHandler::new(clone!(thread_pool, graph, trace => move |_, ps| {
         let start_time = std::time::SystemTime::now();;
-        tx.send(MeasureDestination::File(
-            "tmp/work_stealing_log.txt".to_string(),
-            format!("\nBeginning of a cycle at: {:#?}", start_time),
-        ))
-        .expect("logging error");
+        let cycle_start = trace.start_cycle();
 
         // We must give new buffers for the sinks to write into,
         // every time this callback function is called by JACK
@@ -96,6 +138,25 @@ pub fn run_work_stealing(
             }
         }
 
+        // The input nodes read the buffers of the input ports
+        for (in_port, &node_index) in in_ports.iter().zip(&input_nodes) {
+            let buffer = in_port.as_slice(ps);
+
+            let input = graph.read().unwrap().get_dsp(node_index);
+            let input = &mut *input.lock().unwrap();
+
+            if let Some(input) = input {
+                if let DspNode::Input(ref mut i) = input.dsp {
+                    i.set_buffer(buffer.as_ptr(), ps.n_frames());
+                }
+            }
+        }
+
+        // The MIDI messages and the OSC changes received during the last
+        // cycle drive the parameters of the nodes
+        apply_midi_port(&graph.read().unwrap(), &midi_port, ps);
+        apply_parameter_updates(&graph.read().unwrap(), &mut updates_consumer);
+
         // We must reset the activation counters of each node
         let nb_nodes = graph.read().unwrap().get_nb_node();
         for node_index in 0..nb_nodes {
@@ -112,29 +173,26 @@ pub fn run_work_stealing(
         }
 
         // Execute the audio graph with the thread pool
-        thread_pool.write().unwrap().start();
-
-        // Get the time spent for the execution of the audio graph
-        let elapsed_time = start_time.elapsed().unwrap();
-        let time_left = ps.cycle_times().unwrap().next_usecs - jack::get_time();
-
-        tx.send(MeasureDestination::File(
-            "tmp/work_stealing_log.txt".to_string(),
-            format!(
-                "\nEnd of cycle at: {:#?} \nIn: {}µs\nTime left before the deadline: {}µs",
-                start_time,
-                elapsed_time.as_micros(),
-                time_left,
-            ),
-        ))
-        .expect("logging error");
+        let deadline = ps.cycle_times().unwrap().next_usecs;
+        thread_pool.write().unwrap().start(Some(deadline));
+        writer.record(TraceEventKind::Cycle, cycle_start);
+
+        // Get the time left for the execution of the audio graph
+        let time_left = deadline as i64 - jack::get_time() as i64;
+
+        // Hand the measurements to the measure thread without allocating
+        records.push(MeasureRecord::new(start_time, time_left));
 
         // JACK will continue to call this function
         jack::Control::Continue
     }));
 
     // Tell JACK to start calling the callback function
-    let _active_client = client.activate_async((), callback)?;
+    let active_client =
+        client.activate_async(XrunNotifier::new(tx.clone(), &log_path), callback)?;
+
+    connect_ports(active_client.as_client(), config, &sinks, &sources, midi, &tx, &log_path);
+    let _osc_server = start_osc_server(config, &graph.read().unwrap(), updates, &tx, &log_path);
 
     // Wait for an input from the user in order to not immediately exit
     // the program
@@ -143,5 +201,24 @@ pub fn run_work_stealing(
 
     thread_pool.read().unwrap().stop();
 
+    log_observed_wcets(
+        &tx,
+        &log_path,
+        &graph.read().unwrap(),
+        &statistics,
+        &config.executor.get_reschedule_policy(),
+    );
+
+    if trace.is_enabled() {
+        if let Err(e) = save_chrome_trace(
+            config.logging.get_trace_path("work_stealing"),
+            &trace,
+            &graph.read().unwrap(),
+            None,
+        ) {
+            eprintln!("Failed to write the trace: {}", e);
+        }
+    }
+
     Ok(())
 }
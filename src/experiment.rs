@@ -0,0 +1,229 @@
+extern crate jack;
+
+extern crate libaudiograph;
+
+use std::fs;
+use std::path::Path;
+
+use libaudiograph::execution::config::RunConfig;
+use libaudiograph::execution::engine::{run_jack, run_offline, Engine, Executor};
+use libaudiograph::execution::experiment::{
+    write_results, Configuration, ExperimentResult, Matrix,
+};
+use libaudiograph::parser::audiograph::parser::parse_audio_graph;
+use libaudiograph::static_scheduling::algorithms::SchedulingAlgorithm;
+
+const USAGE: &str = "Usage: experiment [options] <AG files or directories>...
+Options:
+    --executors <seq,ws,static,...>  The executors, static standing for the algorithms (default: seq,ws,static)
+    --algorithms <hlfet,etf,...>     The static scheduling algorithms (default: etf)
+    --threads <2,4,...>              The thread counts of the parallel executors (default: 2)
+    --buffers <64,256,...>           The buffer sizes (default: 256)
+    --cycles <n>                     The number of cycles of each run (default: 1000)
+    --sample-rate <rate>             The sample rate of the offline runs (default: 48000)
+    --jack                           Drive the runs with JACK instead of offline
+    --config <file>                  The TOML run configuration of the JACK client and its ports
+    --output <file>                  The results table (default: tmp/experiment.csv)";
+
+// The options of the command line
+struct Options {
+    matrix: Matrix,
+    nb_cycles: usize,
+    sample_rate: usize,
+    jack: bool,
+    output: String,
+    config: RunConfig,
+}
+
+// Returns the values of a list separated by commas
+fn parse_list<T, F: Fn(&str) -> Option<T>>(option: &str, list: &str, parse: F) -> Vec<T> {
+    list.split(',')
+        .map(|value| {
+            parse(value.trim())
+                .unwrap_or_else(|| panic!("Bad value for {}: {}\n{}", option, value, USAGE))
+        })
+        .collect()
+}
+
+// Returns the graph files of a path: the file itself, or the `.ag` files of a
+// directory
+fn get_graphs(path: &str) -> Vec<String> {
+    if !Path::new(path).is_dir() {
+        return vec![path.to_string()];
+    }
+
+    let mut graphs: Vec<String> = fs::read_dir(path)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {}", path, e))
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .map_or(false, |extension| extension == "ag")
+        })
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+
+    graphs.sort();
+
+    graphs
+}
+
+fn parse_options(mut args: Vec<String>) -> Options {
+    let config = RunConfig::from_args(&mut args).unwrap_or_else(|e| panic!("{}\n{}", e, USAGE));
+    let mut executor_names = vec!["seq".to_string(), "ws".to_string(), "static".to_string()];
+    let mut algorithms = vec![Executor::StaticScheduling(SchedulingAlgorithm::ETF)];
+    let mut options = Options {
+        matrix: Matrix {
+            graphs: Vec::new(),
+            executors: Vec::new(),
+            thread_counts: vec![2],
+            buffer_sizes: vec![256],
+        },
+        nb_cycles: 1000,
+        sample_rate: 48000,
+        jack: false,
+        output: "tmp/experiment.csv".to_string(),
+        config,
+    };
+
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if arg == "--jack" {
+            options.jack = true;
+            continue;
+        }
+
+        if !arg.starts_with("--") {
+            options.matrix.graphs.extend(get_graphs(arg));
+            continue;
+        }
+
+        let value = args
+            .next()
+            .unwrap_or_else(|| panic!("No value for {}\n{}", arg, USAGE));
+        let parse_usize = |value: &str| value.parse().ok().filter(|&n: &usize| n > 0);
+
+        match arg.as_str() {
+            "--executors" => executor_names = parse_list(arg, value, |name| Some(name.to_string())),
+            "--algorithms" => {
+                algorithms = parse_list(arg, value, |name| {
+                    SchedulingAlgorithm::from_name(name).map(Executor::StaticScheduling)
+                })
+            }
+            "--threads" => options.matrix.thread_counts = parse_list(arg, value, parse_usize),
+            "--buffers" => options.matrix.buffer_sizes = parse_list(arg, value, parse_usize),
+            "--cycles" => options.nb_cycles = parse_usize(value).expect(USAGE),
+            "--sample-rate" => options.sample_rate = parse_usize(value).expect(USAGE),
+            "--output" => options.output = value.clone(),
+            _ => panic!("Unknown option {}\n{}", arg, USAGE),
+        }
+    }
+
+    for name in executor_names {
+        if name == "static" {
+            options.matrix.executors.extend(algorithms.iter().cloned());
+        } else {
+            options
+                .matrix
+                .executors
+                .push(Executor::from_name(&name).expect(USAGE));
+        }
+    }
+
+    if options.matrix.graphs.is_empty() {
+        panic!("No files supplied\n{}", USAGE);
+    }
+
+    options
+}
+
+// Runs a configuration and returns its result
+fn run(configuration: Configuration, options: &Options) -> Result<ExperimentResult, String> {
+    let graph = parse_audio_graph(&configuration.graph).map_err(|e| e.to_string())?;
+    let nb_nodes = graph.get_nb_node();
+
+    let (sample_rate, stats) = if options.jack {
+        let backend = &options.config.backend;
+        let (client, _) = jack::Client::new(
+            &backend.get_client_name("audio_graph_experiment"),
+            backend.get_client_options(),
+        )
+        .map_err(|e| format!("{:?}", e))?;
+        let sample_rate = client.sample_rate();
+        let engine = Engine::new(
+            graph,
+            configuration.executor,
+            configuration.nb_threads,
+            configuration.buffer_size,
+            sample_rate,
+        );
+
+        (
+            sample_rate,
+            run_jack(client, engine, &options.config, options.nb_cycles)
+                .map_err(|e| format!("{:?}", e))?,
+        )
+    } else {
+        let mut engine = Engine::new(
+            graph,
+            configuration.executor,
+            configuration.nb_threads,
+            configuration.buffer_size,
+            options.sample_rate,
+        );
+        let deadline = configuration.buffer_size as f64 / options.sample_rate as f64;
+
+        (
+            options.sample_rate,
+            run_offline(&mut engine, options.nb_cycles, deadline),
+        )
+    };
+
+    Ok(ExperimentResult {
+        configuration,
+        nb_nodes,
+        sample_rate,
+        mode: if options.jack { "jack" } else { "offline" },
+        summary: stats.get_summary(),
+    })
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = parse_options(args);
+    let configurations = options.matrix.get_configurations();
+    let nb_configurations = configurations.len();
+    let mut results = Vec::with_capacity(nb_configurations);
+
+    for (i, configuration) in configurations.into_iter().enumerate() {
+        println!(
+            "[{}/{}] {}: {} with {} threads, buffer of {}",
+            i + 1,
+            nb_configurations,
+            configuration.graph,
+            configuration.executor.get_name(),
+            configuration.nb_threads,
+            configuration.buffer_size
+        );
+
+        match run(configuration, &options) {
+            Ok(result) => {
+                println!(
+                    "    mean {:.1}µs, p99 {}µs, max {}µs, {} deadline misses",
+                    result.summary.mean,
+                    result.summary.p99,
+                    result.summary.max,
+                    result.summary.nb_deadline_misses
+                );
+
+                results.push(result);
+            }
+            Err(e) => eprintln!("    Failed to run because: {}", e),
+        }
+    }
+
+    match fs::write(&options.output, write_results(&results)) {
+        Ok(_) => println!("Results written in {}", options.output),
+        Err(e) => eprintln!("Failed to write {}: {}", options.output, e),
+    }
+}
@@ -3,6 +3,7 @@
 extern crate core_affinity;
 extern crate crossbeam;
 extern crate jack;
+extern crate midly;
 extern crate pest;
 #[macro_use]
 extern crate pest_derive;
@@ -11,6 +12,9 @@ extern crate rand;
 pub mod dsp;
 pub mod execution;
 pub mod measure;
+pub mod midi;
+pub mod osc;
 pub mod parser;
 pub mod static_scheduling;
 pub mod task_graph;
+pub mod wcet;
@@ -1,18 +1,89 @@
+pub mod ring;
+pub mod stats;
+pub mod trace;
+
 use std::collections::HashMap;
+use std::fmt::{Display, Error as FmtError, Formatter};
 use std::fs::File;
 use std::io::Write;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crossbeam::channel::{Receiver, RecvTimeoutError, Sender};
+
+use self::ring::Consumer;
+use self::stats::{CycleStats, StatsSummary};
+use self::trace::Trace;
+
+/// The number of `MeasureRecord`s a thread can send before they are drained.
+pub const RING_CAPACITY: usize = 4096;
 
-use crossbeam::channel::Receiver;
+// How often the rings of records and of trace events are drained
+const DRAIN_PERIOD: Duration = Duration::from_millis(10);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// The measurements of an audio cycle, sent by the JACK thread without
+/// allocating.
+pub struct MeasureRecord {
+    pub start: u64,     // µs since the UNIX epoch
+    pub duration: u64,  // µs
+    pub time_left: i64, // µs before the deadline, negative if it was missed
+}
+
+impl MeasureRecord {
+    /// Creates the record of a cycle ending now.
+    ///
+    /// # Arguments
+    /// * `start_time` - When the cycle started
+    /// * `time_left` - The time left before the deadline, in µs
+    pub fn new(start_time: SystemTime, time_left: i64) -> MeasureRecord {
+        MeasureRecord {
+            start: start_time
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_micros() as u64,
+            duration: start_time.elapsed().unwrap_or_default().as_micros() as u64,
+            time_left,
+        }
+    }
+}
+
+impl Display for MeasureRecord {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), FmtError> {
+        let start_time = UNIX_EPOCH + Duration::from_micros(self.start);
+
+        write!(
+            fmt,
+            "\nBeginning of a cycle at: {:#?}\nEnd of cycle at: {:#?} \nIn: {}µs\nTime left before the deadline: {}µs",
+            start_time,
+            start_time + Duration::from_micros(self.duration),
+            self.duration,
+            self.time_left,
+        )
+    }
+}
 
 pub enum MeasureDestination {
     Stdout(String),       // Content
     Stderr(String),       // Content
     File(String, String), // Filename and content
+    // Filename and the ring of records of a thread, written in the file
+    Records(String, Consumer<MeasureRecord>),
+    Trace(Arc<Trace>), // A trace whose events are collected from its rings
+    Xrun(String),      // Filename of the records of the run
+    // Filename of the records of the run and the channel their current
+    // statistics are sent to
+    Stats(String, Sender<StatsSummary>),
 }
 
 pub struct Measure {
     pub rx: Receiver<MeasureDestination>,
     pub files: HashMap<String, File>,
+    // The rings drained, with their file and the number of overflows written
+    records: Vec<(String, Consumer<MeasureRecord>, usize)>,
+    traces: Vec<Arc<Trace>>,
+    // The statistics of the records of each file, saved at the end
+    stats: HashMap<String, CycleStats>,
 }
 
 impl Measure {
@@ -20,20 +91,91 @@ impl Measure {
         Measure {
             rx,
             files: HashMap::new(),
+            records: Vec::new(),
+            traces: Vec::new(),
+            stats: HashMap::new(),
         }
     }
 
     pub fn receive(&mut self) {
         loop {
-            match self.rx.recv() {
+            match self.rx.recv_timeout(DRAIN_PERIOD) {
                 Ok(dest) => {
                     // println!("Measure, channel: {}, msg: {}", channel, message);
                     self.write(dest).unwrap();
                 }
-                Err(_) => {
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.drain().unwrap();
+                    self.save_stats();
                     break;
                 }
             }
+
+            self.drain().unwrap();
+        }
+    }
+
+    /// Writes the records of the rings in their files, with the number of
+    /// records lost since the last drain, and collects the events of the
+    /// traces.
+    pub fn drain(&mut self) -> std::io::Result<()> {
+        for trace in &self.traces {
+            trace.collect();
+        }
+
+        let mut contents = Vec::with_capacity(self.records.len());
+
+        for (path, consumer, nb_overflows) in self.records.iter_mut() {
+            let mut content = String::new();
+            let stats = self.stats.entry(path.clone()).or_default();
+
+            while let Some(record) = consumer.pop() {
+                content.push_str(&record.to_string());
+                stats.add_record(&record);
+            }
+
+            let overflows = consumer.get_overflows();
+
+            if overflows > *nb_overflows {
+                stats.add_lost(overflows - *nb_overflows);
+                content.push_str(&format!(
+                    "\nMeasurements lost: {}",
+                    overflows - *nb_overflows
+                ));
+                *nb_overflows = overflows;
+            }
+
+            if !content.is_empty() {
+                contents.push(MeasureDestination::File(path.clone(), content));
+            }
+        }
+
+        // The rings of the thread pools replaced when a graph is scheduled
+        // again are dropped once drained
+        self.records
+            .retain(|(_, consumer, _)| !consumer.is_abandoned() || !consumer.is_empty());
+
+        for dest in contents {
+            self.write(dest)?;
+        }
+
+        Ok(())
+    }
+
+    /// Saves the statistics of the records of each file next to it, in
+    /// `<run>_stats.csv`, `<run>_stats.json` and `<run>_hist.csv` for a file
+    /// `<run>_log.txt`.
+    pub fn save_stats(&self) {
+        for (path, stats) in &self.stats {
+            let prefix = path
+                .strip_suffix("_log.txt")
+                .or_else(|| path.strip_suffix(".txt"))
+                .unwrap_or(path);
+
+            if let Err(e) = stats.get_summary().save(prefix) {
+                eprintln!("Failed to write the statistics of {}: {}", path, e);
+            }
         }
     }
 
@@ -58,8 +200,83 @@ impl Measure {
                     write!(file, "{}", msg)?;
                 }
             }
+            MeasureDestination::Records(path, consumer) => {
+                self.records.push((path, consumer, 0));
+            }
+            MeasureDestination::Trace(trace) => self.traces.push(trace),
+            MeasureDestination::Xrun(path) => {
+                self.stats.entry(path).or_default().add_xrun();
+            }
+            MeasureDestination::Stats(path, reply) => {
+                let summary = self
+                    .stats
+                    .get(&path)
+                    .map_or_else(|| CycleStats::new().get_summary(), CycleStats::get_summary);
+
+                // The receiver may have stopped waiting
+                let _ignored = reply.send(summary);
+            }
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod measure_test {
+    use super::ring::ring_buffer;
+    use super::*;
+    use crossbeam::channel::unbounded;
+
+    #[test]
+    fn test_records() {
+        let path = std::env::temp_dir().join("audio_graph_measure_test_log.txt");
+        let path = path.to_str().unwrap().to_string();
+
+        let (tx, rx) = unbounded();
+        let (mut producer, consumer) = ring_buffer(2);
+        let mut measure = Measure::new(rx);
+
+        tx.send(MeasureDestination::Records(path.clone(), consumer))
+            .unwrap();
+
+        let record = MeasureRecord {
+            start: 1_500_000,
+            duration: 250,
+            time_left: -12,
+        };
+
+        for _ in 0..3 {
+            producer.push(record);
+        }
+
+        tx.send(MeasureDestination::Xrun(path.clone())).unwrap();
+        drop(producer);
+        drop(tx);
+        measure.receive();
+
+        // The ring is dropped once drained, its producer being gone
+        assert!(measure.records.is_empty());
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(content.matches("\nIn: 250µs\n").count(), 2);
+        assert!(content.contains("Time left before the deadline: -12µs"));
+        assert!(content.contains(&format!(
+            "End of cycle at: {:#?}",
+            UNIX_EPOCH + Duration::from_micros(1_500_250)
+        )));
+        assert!(content.ends_with("\nMeasurements lost: 1"));
+
+        // The statistics are saved when the channel is closed
+        let prefix = path.trim_end_matches("_log.txt");
+        let csv = std::fs::read_to_string(format!("{}_stats.csv", prefix)).unwrap();
+
+        for suffix in &["_stats.csv", "_stats.json", "_hist.csv"] {
+            std::fs::remove_file(format!("{}{}", prefix, suffix)).unwrap();
+        }
+
+        assert!(csv.ends_with("\n2,250.000,250,250,250,250,2,1,1\n"));
+    }
+}
@@ -0,0 +1,186 @@
+//! This module implements a lock-free ring buffer with a single producer and a
+//! single consumer, so that a real-time thread can hand measurements to
+//! another thread without blocking nor allocating.
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+// The records of the ring, shared by its two ends
+struct Ring<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    mask: usize,            // The capacity, a power of two, minus one
+    read: AtomicUsize,      // The next record to pop, only written by the consumer
+    write: AtomicUsize,     // The next record to push, only written by the producer
+    overflows: AtomicUsize, // The number of records dropped because the ring was full
+}
+
+// A slot is only accessed by the producer before it is published, and by the
+// consumer after
+unsafe impl<T: Send> Sync for Ring<T> {}
+
+/// The end of a ring buffer on which records are pushed.
+pub struct Producer<T> {
+    ring: Arc<Ring<T>>,
+}
+
+/// The end of a ring buffer from which records are popped.
+pub struct Consumer<T> {
+    ring: Arc<Ring<T>>,
+}
+
+/// Creates a ring buffer, returning its producer and its consumer.
+///
+/// # Arguments
+/// * `capacity` - The number of records the ring can hold, rounded up to a
+///   power of two
+pub fn ring_buffer<T: Copy>(capacity: usize) -> (Producer<T>, Consumer<T>) {
+    let capacity = capacity.max(1).next_power_of_two();
+
+    let ring = Arc::new(Ring {
+        slots: (0..capacity)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect(),
+        mask: capacity - 1,
+        read: AtomicUsize::new(0),
+        write: AtomicUsize::new(0),
+        overflows: AtomicUsize::new(0),
+    });
+
+    (Producer { ring: ring.clone() }, Consumer { ring })
+}
+
+impl<T: Copy> Producer<T> {
+    /// Pushes a record without blocking nor allocating. Returns `false`, and
+    /// counts an overflow, if the ring is full.
+    ///
+    /// # Arguments
+    /// * `record` - The record to push
+    pub fn push(&mut self, record: T) -> bool {
+        let ring = &*self.ring;
+        let write = ring.write.load(Ordering::Relaxed);
+
+        if write.wrapping_sub(ring.read.load(Ordering::Acquire)) > ring.mask {
+            ring.overflows.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+
+        unsafe {
+            *ring.slots[write & ring.mask].get() = MaybeUninit::new(record);
+        }
+
+        ring.write.store(write.wrapping_add(1), Ordering::Release);
+
+        true
+    }
+
+    /// Returns the number of records dropped because the ring was full.
+    pub fn get_overflows(&self) -> usize {
+        self.ring.overflows.load(Ordering::Relaxed)
+    }
+}
+
+impl<T: Copy> Consumer<T> {
+    /// Pops the oldest record, if there is one.
+    pub fn pop(&mut self) -> Option<T> {
+        let ring = &*self.ring;
+        let read = ring.read.load(Ordering::Relaxed);
+
+        if read == ring.write.load(Ordering::Acquire) {
+            return None;
+        }
+
+        let record = unsafe { (*ring.slots[read & ring.mask].get()).assume_init() };
+
+        ring.read.store(read.wrapping_add(1), Ordering::Release);
+
+        Some(record)
+    }
+
+    /// Returns the number of records in the ring.
+    pub fn len(&self) -> usize {
+        let write = self.ring.write.load(Ordering::Acquire);
+
+        write.wrapping_sub(self.ring.read.load(Ordering::Relaxed))
+    }
+
+    /// Returns `true` if there is no record in the ring.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of records dropped because the ring was full.
+    pub fn get_overflows(&self) -> usize {
+        self.ring.overflows.load(Ordering::Relaxed)
+    }
+
+    /// Returns `true` if the producer has been dropped, so that no record
+    /// will be pushed anymore.
+    pub fn is_abandoned(&self) -> bool {
+        Arc::strong_count(&self.ring) == 1
+    }
+}
+
+#[cfg(test)]
+mod ring_test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_overflow() {
+        let (mut producer, mut consumer) = ring_buffer(3);
+
+        for i in 0..5 {
+            producer.push(i);
+        }
+
+        // The capacity is rounded up to 4
+        assert_eq!(consumer.len(), 4);
+        assert_eq!(consumer.get_overflows(), 1);
+        assert_eq!(producer.get_overflows(), 1);
+        assert_eq!(consumer.pop(), Some(0));
+        assert!(producer.push(5));
+        assert_eq!(
+            (0..5).map(|_| consumer.pop()).collect::<Vec<_>>(),
+            vec![Some(1), Some(2), Some(3), Some(5), None]
+        );
+        assert!(consumer.is_empty());
+        assert!(!consumer.is_abandoned());
+
+        drop(producer);
+
+        assert!(consumer.is_abandoned());
+    }
+
+    #[test]
+    fn test_threads() {
+        let (mut producer, mut consumer) = ring_buffer(64);
+
+        let handle = thread::spawn(move || {
+            for i in 0..100_000u64 {
+                while !producer.push(i) {
+                    thread::yield_now();
+                }
+            }
+
+            producer.get_overflows()
+        });
+
+        let mut expected = 0;
+
+        while expected < 100_000 {
+            match consumer.pop() {
+                Some(record) => {
+                    assert_eq!(record, expected);
+                    expected += 1;
+                }
+                None => thread::yield_now(),
+            }
+        }
+
+        // Every failed push is counted
+        assert_eq!(handle.join().unwrap(), consumer.get_overflows());
+        assert!(consumer.is_empty());
+    }
+}
@@ -0,0 +1,230 @@
+//! This module aggregates the measurements of the audio cycles of a run, and
+//! writes a summary of them in CSV and in JSON at the end of the run.
+
+use std::fmt::Write;
+use std::fs;
+use std::io;
+
+use measure::MeasureRecord;
+
+/// The width of the buckets of the histogram of the cycle durations, in µs.
+pub const BUCKET_WIDTH: u64 = 50;
+
+/// The columns of a summary in CSV.
+pub const CSV_HEADER: &str =
+    "cycles,mean_us,median_us,p99_us,p999_us,max_us,deadline_misses,xruns,lost";
+
+#[derive(Clone, Debug, Default, PartialEq)]
+/// The measurements of the cycles of a run.
+pub struct CycleStats {
+    durations: Vec<u64>, // µs
+    nb_deadline_misses: usize,
+    nb_xruns: usize,
+    nb_lost: usize, // The cycles whose measurements were lost
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// The statistics of the cycles of a run, the durations being in µs.
+pub struct StatsSummary {
+    pub nb_cycles: usize,
+    pub mean: f64,
+    pub median: u64,
+    pub p99: u64,
+    pub p999: u64,
+    pub max: u64,
+    pub nb_deadline_misses: usize,
+    pub nb_xruns: usize,
+    pub nb_lost: usize,
+    pub histogram: Vec<usize>, // The number of cycles in each bucket of `BUCKET_WIDTH` µs
+}
+
+impl CycleStats {
+    /// Creates empty statistics.
+    pub fn new() -> CycleStats {
+        CycleStats::default()
+    }
+
+    /// Adds the measurements of a cycle.
+    ///
+    /// # Arguments
+    /// * `record` - The measurements
+    pub fn add_record(&mut self, record: &MeasureRecord) {
+        self.durations.push(record.duration);
+
+        if record.time_left < 0 {
+            self.nb_deadline_misses += 1;
+        }
+    }
+
+    /// Counts an xrun reported by JACK.
+    pub fn add_xrun(&mut self) {
+        self.nb_xruns += 1;
+    }
+
+    /// Counts cycles whose measurements were lost.
+    ///
+    /// # Arguments
+    /// * `nb_lost` - The number of cycles
+    pub fn add_lost(&mut self, nb_lost: usize) {
+        self.nb_lost += nb_lost;
+    }
+
+    /// Returns the statistics of the cycles added.
+    pub fn get_summary(&self) -> StatsSummary {
+        let mut durations = self.durations.clone();
+        durations.sort_unstable();
+
+        let nb_cycles = durations.len();
+        let max = durations.last().cloned().unwrap_or(0);
+        let mut histogram = vec![0; (max / BUCKET_WIDTH) as usize + 1];
+
+        for &duration in &durations {
+            histogram[(duration / BUCKET_WIDTH) as usize] += 1;
+        }
+
+        StatsSummary {
+            nb_cycles,
+            mean: if nb_cycles > 0 {
+                durations.iter().sum::<u64>() as f64 / nb_cycles as f64
+            } else {
+                0.0
+            },
+            median: get_percentile(&durations, 0.5),
+            p99: get_percentile(&durations, 0.99),
+            p999: get_percentile(&durations, 0.999),
+            max,
+            nb_deadline_misses: self.nb_deadline_misses,
+            nb_xruns: self.nb_xruns,
+            nb_lost: self.nb_lost,
+            histogram,
+        }
+    }
+}
+
+// Returns the nearest-rank percentile of sorted values, or 0 without values
+fn get_percentile(sorted: &[u64], percentile: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+
+    let rank = (percentile * sorted.len() as f64).ceil() as usize;
+
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+impl StatsSummary {
+    /// Returns the summary in CSV, a header and a line of values, so that the
+    /// summaries of several runs can be concatenated.
+    pub fn write_csv(&self) -> String {
+        format!("{}\n{}\n", CSV_HEADER, self.write_csv_values())
+    }
+
+    /// Returns the values of the summary in CSV, in the order of
+    /// `CSV_HEADER`.
+    pub fn write_csv_values(&self) -> String {
+        format!(
+            "{},{:.3},{},{},{},{},{},{},{}",
+            self.nb_cycles,
+            self.mean,
+            self.median,
+            self.p99,
+            self.p999,
+            self.max,
+            self.nb_deadline_misses,
+            self.nb_xruns,
+            self.nb_lost
+        )
+    }
+
+    /// Returns the histogram in CSV, a line per bucket of `BUCKET_WIDTH` µs.
+    pub fn write_histogram_csv(&self) -> String {
+        let mut csv = String::from("start_us,end_us,cycles\n");
+
+        for (i, count) in self.histogram.iter().enumerate() {
+            let start = i as u64 * BUCKET_WIDTH;
+
+            writeln!(csv, "{},{},{}", start, start + BUCKET_WIDTH, count).unwrap();
+        }
+
+        csv
+    }
+
+    /// Returns the summary and its histogram in JSON.
+    pub fn write_json(&self) -> String {
+        let counts: Vec<String> = self.histogram.iter().map(|c| c.to_string()).collect();
+
+        format!(
+            "{{\n  \"cycles\": {},\n  \"mean_us\": {:.3},\n  \"median_us\": {},\n  \
+             \"p99_us\": {},\n  \"p999_us\": {},\n  \"max_us\": {},\n  \
+             \"deadline_misses\": {},\n  \"xruns\": {},\n  \"lost\": {},\n  \
+             \"histogram\": {{\"bucket_width_us\": {}, \"counts\": [{}]}}\n}}\n",
+            self.nb_cycles,
+            self.mean,
+            self.median,
+            self.p99,
+            self.p999,
+            self.max,
+            self.nb_deadline_misses,
+            self.nb_xruns,
+            self.nb_lost,
+            BUCKET_WIDTH,
+            counts.join(", ")
+        )
+    }
+
+    /// Saves the summary in `<prefix>_stats.csv` and `<prefix>_stats.json`,
+    /// and the histogram in `<prefix>_hist.csv`.
+    ///
+    /// # Arguments
+    /// * `prefix` - The path of the files, without their suffix
+    pub fn save(&self, prefix: &str) -> io::Result<()> {
+        fs::write(format!("{}_stats.csv", prefix), self.write_csv())?;
+        fs::write(format!("{}_hist.csv", prefix), self.write_histogram_csv())?;
+        fs::write(format!("{}_stats.json", prefix), self.write_json())
+    }
+}
+
+#[cfg(test)]
+mod stats_test {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let mut stats = CycleStats::new();
+
+        assert_eq!(stats.get_summary().nb_cycles, 0);
+
+        for duration in 1..=1000 {
+            stats.add_record(&MeasureRecord {
+                start: 0,
+                duration,
+                time_left: 900 - duration as i64,
+            });
+        }
+
+        stats.add_xrun();
+        stats.add_lost(3);
+
+        let summary = stats.get_summary();
+
+        assert_eq!(summary.nb_cycles, 1000);
+        assert_eq!(summary.mean, 500.5);
+        assert_eq!(summary.median, 500);
+        assert_eq!(summary.p99, 990);
+        assert_eq!(summary.p999, 999);
+        assert_eq!(summary.max, 1000);
+        assert_eq!(summary.nb_deadline_misses, 100);
+        assert_eq!(summary.nb_xruns, 1);
+        assert_eq!(summary.nb_lost, 3);
+        assert_eq!(summary.histogram.len(), 21);
+        assert_eq!(summary.histogram[0], 49);
+        assert_eq!(summary.histogram[20], 1);
+        assert_eq!(summary.histogram.iter().sum::<usize>(), 1000);
+
+        assert!(summary
+            .write_csv()
+            .ends_with("\n1000,500.500,500,990,999,1000,100,1,3\n"));
+        assert!(summary.write_histogram_csv().contains("\n50,100,50\n"));
+        assert!(summary.write_json().contains("\"p999_us\": 999,"));
+    }
+}
@@ -0,0 +1,410 @@
+//! This module records what the executors do during the audio cycles, as
+//! structured events pushed by each thread in its own lock-free ring, and
+//! writes them in the trace event format of Chrome (`chrome://tracing`,
+//! Perfetto), next to the static `Schedule` the cycles were supposed to
+//! follow. The tracing is disabled unless a run asks for it.
+
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use parser::json::writer::quote;
+use static_scheduling::schedule::Schedule;
+use task_graph::graph::TaskGraph;
+
+use super::ring::{ring_buffer, Consumer, Producer};
+
+/// The number of most recent events kept for each thread.
+pub const TRACE_CAPACITY: usize = 1 << 16;
+
+/// The number of events a thread can record before they are collected.
+pub const TRACE_RING_CAPACITY: usize = 1 << 12;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// What happened during a `TraceEvent`.
+pub enum TraceEventKind {
+    Cycle,                                // An audio cycle, on the JACK thread
+    Task(usize),                          // The execution of a node
+    Wait(usize),                          // Waiting for the predecessors of a node
+    Steal { node: usize, victim: usize }, // A node stolen from another worker
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// An event of a thread, its times being measured from the creation of the
+/// `Trace`.
+pub struct TraceEvent {
+    pub kind: TraceEventKind,
+    pub thread: usize,
+    pub cycle: usize,
+    pub start: Duration,
+    pub end: Duration,
+}
+
+/// The events of the workers of an executor and of its JACK thread, whose
+/// index is the number of workers.
+pub struct Trace {
+    origin: Instant,
+    cycle: Arc<AtomicUsize>,
+    nb_workers: usize,
+    enabled: bool,
+    // The rings of the `TraceWriter`s and the events collected from them for
+    // each thread, only locked out of the audio threads
+    rings: Mutex<Vec<Consumer<TraceEvent>>>,
+    threads: Mutex<Vec<VecDeque<TraceEvent>>>,
+}
+
+/// The end of a `Trace` through which a thread records its events, without
+/// blocking nor allocating.
+pub struct TraceWriter {
+    thread: usize,
+    origin: Instant,
+    cycle: Arc<AtomicUsize>,
+    events: Option<Producer<TraceEvent>>, // `None` if the trace is disabled
+}
+
+impl Trace {
+    /// Creates an empty `Trace`.
+    ///
+    /// # Arguments
+    /// * `nb_workers` - The number of threads executing the nodes
+    /// * `enabled` - Whether the events are recorded, the `TraceWriter`s
+    ///   only measuring times otherwise
+    pub fn new(nb_workers: usize, enabled: bool) -> Trace {
+        Trace {
+            origin: Instant::now(),
+            cycle: Arc::new(AtomicUsize::new(0)),
+            nb_workers,
+            enabled,
+            rings: Mutex::new(Vec::new()),
+            threads: Mutex::new((0..=nb_workers).map(|_| VecDeque::new()).collect()),
+        }
+    }
+
+    /// Returns `true` if the events are recorded.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Returns the index of the JACK thread.
+    pub fn get_jack_thread(&self) -> usize {
+        self.nb_workers
+    }
+
+    /// Returns the time elapsed since the creation of the trace.
+    pub fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+
+    /// Starts a new audio cycle, whose number is given to the next events,
+    /// and returns its start time.
+    pub fn start_cycle(&self) -> Duration {
+        self.cycle.fetch_add(1, Ordering::Relaxed);
+        self.now()
+    }
+
+    /// Returns a `TraceWriter` recording the events of a thread in a new
+    /// ring, which is allocated here, before the thread runs.
+    ///
+    /// # Arguments
+    /// * `thread` - The index of the thread
+    pub fn get_writer(&self, thread: usize) -> TraceWriter {
+        let events = if self.enabled {
+            let (producer, consumer) = ring_buffer(TRACE_RING_CAPACITY);
+            self.rings.lock().unwrap().push(consumer);
+
+            Some(producer)
+        } else {
+            None
+        };
+
+        TraceWriter {
+            thread,
+            origin: self.origin,
+            cycle: self.cycle.clone(),
+            events,
+        }
+    }
+
+    /// Moves the events of the rings to the buffers of their threads,
+    /// dropping the oldest events of a full buffer and the rings of the
+    /// threads which have stopped.
+    pub fn collect(&self) {
+        let mut rings = self.rings.lock().unwrap();
+        let mut threads = self.threads.lock().unwrap();
+
+        for ring in rings.iter_mut() {
+            while let Some(event) = ring.pop() {
+                if let Some(events) = threads.get_mut(event.thread) {
+                    if events.len() == TRACE_CAPACITY {
+                        events.pop_front();
+                    }
+
+                    events.push_back(event);
+                }
+            }
+        }
+
+        rings.retain(|ring| !ring.is_abandoned());
+    }
+
+    /// Returns the events of all the threads, sorted by start time.
+    pub fn get_events(&self) -> Vec<TraceEvent> {
+        self.collect();
+
+        let mut events: Vec<TraceEvent> = self
+            .threads
+            .lock()
+            .unwrap()
+            .iter()
+            .flat_map(|events| events.iter().cloned())
+            .collect();
+
+        events.sort_by_key(|event| event.start);
+
+        events
+    }
+
+    /// Forgets the recorded events.
+    pub fn clear(&self) {
+        self.collect();
+
+        for events in self.threads.lock().unwrap().iter_mut() {
+            events.clear();
+        }
+    }
+}
+
+impl TraceWriter {
+    /// Returns the time elapsed since the creation of the trace.
+    pub fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+
+    /// Pushes an event of the thread ending now in its ring, the event being
+    /// dropped if the ring is full.
+    ///
+    /// # Arguments
+    /// * `kind` - What happened
+    /// * `start` - When it started, from `now`
+    pub fn record(&mut self, kind: TraceEventKind, start: Duration) {
+        if let Some(ref mut events) = self.events {
+            events.push(TraceEvent {
+                kind,
+                thread: self.thread,
+                cycle: self.cycle.load(Ordering::Relaxed),
+                start,
+                end: self.origin.elapsed(),
+            });
+        }
+    }
+}
+
+// Returns a duration in µs, the time unit of the trace event format
+fn to_micros(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1_000_000.0
+}
+
+// Returns a complete event ("X") of the trace event format
+fn complete_event(name: &str, pid: usize, tid: usize, ts: f64, dur: f64, args: &str) -> String {
+    format!(
+        "{{\"name\": {}, \"ph\": \"X\", \"pid\": {}, \"tid\": {}, \"ts\": {:.3}, \"dur\": {:.3}, \"args\": {{{}}}}}",
+        quote(name),
+        pid,
+        tid,
+        ts,
+        dur,
+        args
+    )
+}
+
+// Returns a metadata event ("M") naming a process or a thread
+fn name_event(kind: &str, pid: usize, tid: usize, name: &str) -> String {
+    format!(
+        "{{\"name\": \"{}\", \"ph\": \"M\", \"pid\": {}, \"tid\": {}, \"args\": {{\"name\": {}}}}}",
+        kind,
+        pid,
+        tid,
+        quote(name)
+    )
+}
+
+/// Returns events in the trace event format of Chrome. The threads of the
+/// execution are in a first process and, with a `Schedule`, the time slots it
+/// planned for each cycle are in a second process, a thread per `Processor`.
+///
+/// # Arguments
+/// * `events` - The events recorded
+/// * `graph` - The graph executed, for the names of the nodes
+/// * `schedule` - The static `Schedule` followed, if any
+pub fn write_chrome_trace(
+    events: &[TraceEvent],
+    graph: &TaskGraph,
+    schedule: Option<&Schedule>,
+) -> String {
+    let mut trace_events = vec![name_event("process_name", 0, 0, "Execution")];
+    let mut threads: Vec<usize> = events.iter().map(|event| event.thread).collect();
+
+    threads.sort();
+    threads.dedup();
+
+    let jack_thread = events
+        .iter()
+        .find(|event| event.kind == TraceEventKind::Cycle)
+        .map(|event| event.thread);
+
+    for thread in threads {
+        let name = if Some(thread) == jack_thread {
+            String::from("JACK")
+        } else {
+            format!("Worker {}", thread)
+        };
+
+        trace_events.push(name_event("thread_name", 0, thread, &name));
+    }
+
+    if let Some(schedule) = schedule {
+        trace_events.push(name_event("process_name", 1, 0, "Static schedule"));
+
+        for proc_index in 0..schedule.get_nb_processor() {
+            trace_events.push(name_event(
+                "thread_name",
+                1,
+                proc_index,
+                &format!("P{}", proc_index),
+            ));
+        }
+    }
+
+    for event in events {
+        let ts = to_micros(event.start);
+        let dur = to_micros(event.end - event.start);
+        let mut args = format!("\"cycle\": {}", event.cycle);
+
+        let name = match event.kind {
+            TraceEventKind::Cycle => format!("Cycle {}", event.cycle),
+            TraceEventKind::Task(node) => graph.get_node_name(node),
+            TraceEventKind::Wait(node) => format!("Wait {}", graph.get_node_name(node)),
+            TraceEventKind::Steal { node, victim } => {
+                write!(args, ", \"victim\": {}", victim).unwrap();
+                format!("Steal {}", graph.get_node_name(node))
+            }
+        };
+
+        trace_events.push(complete_event(&name, 0, event.thread, ts, dur, &args));
+
+        // The time slots planned for the cycle, from its start
+        if let (TraceEventKind::Cycle, Some(schedule)) = (event.kind, schedule) {
+            for (proc_index, processor) in schedule.processors.iter().enumerate() {
+                for time_slot in &processor.time_slots {
+                    trace_events.push(complete_event(
+                        &graph.get_node_name(time_slot.get_node()),
+                        1,
+                        proc_index,
+                        ts + time_slot.get_start_time() * 1_000_000.0,
+                        (time_slot.get_completion_time() - time_slot.get_start_time())
+                            * 1_000_000.0,
+                        &format!("\"cycle\": {}", event.cycle),
+                    ));
+                }
+            }
+        }
+    }
+
+    format!(
+        "{{\"traceEvents\": [\n  {}\n], \"displayTimeUnit\": \"ns\"}}\n",
+        trace_events.join(",\n  ")
+    )
+}
+
+/// Saves the events of a `Trace` in a file in the trace event format of
+/// Chrome.
+///
+/// # Arguments
+/// * `path` - The path of the file
+/// * `trace` - The events recorded
+/// * `graph` - The graph executed
+/// * `schedule` - The static `Schedule` followed, if any
+pub fn save_chrome_trace<P: AsRef<Path>>(
+    path: P,
+    trace: &Trace,
+    graph: &TaskGraph,
+    schedule: Option<&Schedule>,
+) -> io::Result<()> {
+    fs::write(
+        path,
+        write_chrome_trace(&trace.get_events(), graph, schedule),
+    )
+}
+
+#[cfg(test)]
+mod trace_test {
+    use super::*;
+    use task_graph::task::Task;
+
+    #[test]
+    fn test_chrome_trace() {
+        let mut g = TaskGraph::new(2, 1);
+
+        g.add_task(Task::Constant(0.001));
+        g.add_task(Task::Constant(0.001));
+        g.add_edge(0, 1);
+
+        let trace = Trace::new(2, true);
+
+        assert_eq!(trace.get_jack_thread(), 2);
+
+        let mut workers = vec![trace.get_writer(0), trace.get_writer(1)];
+        let mut jack = trace.get_writer(trace.get_jack_thread());
+
+        let cycle_start = trace.start_cycle();
+        let start = trace.now();
+        workers[1].record(TraceEventKind::Wait(1), start);
+        workers[0].record(TraceEventKind::Task(0), start);
+        let start = workers[1].now();
+        workers[1].record(TraceEventKind::Task(1), start);
+        jack.record(TraceEventKind::Cycle, cycle_start);
+
+        let events = trace.get_events();
+
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0].kind, TraceEventKind::Cycle);
+        assert!(events.iter().all(|event| event.cycle == 1));
+        assert!(events.iter().all(|event| event.start <= event.end));
+
+        let mut sche = Schedule::new();
+
+        sche.add_processor();
+        sche.processors[0].add_timeslot(0, 0.0, 0.001);
+        sche.processors[0].add_timeslot(1, 0.001, 0.002);
+
+        let json = write_chrome_trace(&events, &g, Some(&sche));
+
+        assert!(json.starts_with("{\"traceEvents\": ["));
+        assert!(json.contains("\"args\": {\"name\": \"JACK\"}"));
+        assert!(json.contains("{\"name\": \"Wait 1\", \"ph\": \"X\", \"pid\": 0, \"tid\": 1,"));
+        assert!(json.contains("\"dur\": 1000.000, \"args\": {\"cycle\": 1}}"));
+        assert_eq!(json.matches("\"pid\": 1, \"tid\": 0, \"ts\"").count(), 2);
+
+        trace.clear();
+
+        assert!(trace.get_events().is_empty());
+
+        // The rings of the stopped threads are dropped
+        drop(workers);
+        trace.collect();
+
+        assert_eq!(trace.rings.lock().unwrap().len(), 1);
+
+        // Nothing is recorded by a disabled trace
+        let trace = Trace::new(0, false);
+        let mut writer = trace.get_writer(0);
+        writer.record(TraceEventKind::Cycle, writer.now());
+
+        assert!(trace.get_events().is_empty());
+    }
+}
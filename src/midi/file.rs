@@ -0,0 +1,163 @@
+//! Read the standard MIDI files played by the offline runs, the times of the
+//! events being converted to frames with the tempo changes of the file.
+
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use midly::{MetaMessage, Smf, Timing, TrackEventKind};
+
+use super::MidiMessage;
+
+// The tempo of a file without a tempo change, in microseconds per beat
+const DEFAULT_TEMPO: f64 = 500_000.0;
+
+/// Decodes the content of a MIDI file. Returns its messages with the frame
+/// they are played at, sorted by frame, the events of all the tracks being
+/// merged.
+///
+/// # Arguments
+/// * `bytes` - The content of the file
+/// * `sample_rate` - The sample rate the file is played at
+pub fn parse_midi_file(bytes: &[u8], sample_rate: usize) -> Result<Vec<(usize, MidiMessage)>> {
+    let smf = Smf::parse(bytes).map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))?;
+
+    // The events of all the tracks, with their time in ticks
+    let mut events = Vec::new();
+
+    for track in smf.tracks.iter() {
+        let mut tick = 0u64;
+
+        for event in track.iter() {
+            tick += u64::from(event.delta.as_int());
+            events.push((tick, event.kind));
+        }
+    }
+
+    // The sort is stable: the events of a tick stay in the order of the tracks
+    events.sort_by_key(|&(tick, _)| tick);
+
+    let mut messages = Vec::new();
+    let mut tempo = DEFAULT_TEMPO;
+    let mut last_tick = 0;
+    let mut seconds = 0.0;
+
+    for (tick, kind) in events {
+        seconds += match smf.header.timing {
+            Timing::Metrical(ticks_per_beat) => {
+                (tick - last_tick) as f64 * tempo / 1e6 / f64::from(ticks_per_beat.as_int())
+            }
+            Timing::Timecode(fps, subframes) => {
+                (tick - last_tick) as f64 / (f64::from(fps.as_f32()) * f64::from(subframes))
+            }
+        };
+        last_tick = tick;
+
+        match kind {
+            TrackEventKind::Meta(MetaMessage::Tempo(microseconds)) => {
+                tempo = f64::from(microseconds.as_int());
+            }
+            TrackEventKind::Midi { channel, message } => {
+                if let Some(message) = MidiMessage::from_midly(channel, message) {
+                    messages.push(((seconds * sample_rate as f64).round() as usize, message));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(messages)
+}
+
+/// Reads a MIDI file.
+///
+/// # Arguments
+/// * `path` - The path of the file
+/// * `sample_rate` - The sample rate the file is played at
+pub fn read_midi_file<P: AsRef<Path>>(
+    path: P,
+    sample_rate: usize,
+) -> Result<Vec<(usize, MidiMessage)>> {
+    parse_midi_file(&fs::read(path)?, sample_rate)
+}
+
+#[cfg(test)]
+mod file_test {
+    use super::*;
+    use midly::num::{u15, u24, u28, u4, u7};
+    use midly::{Format, Header, MidiMessage as MidlyMessage, TrackEvent};
+
+    // Returns a track event
+    fn event(delta: u32, kind: TrackEventKind) -> TrackEvent {
+        TrackEvent {
+            delta: u28::new(delta),
+            kind,
+        }
+    }
+
+    // Returns a note on event
+    fn note_on(delta: u32, key: u8) -> TrackEvent<'static> {
+        event(
+            delta,
+            TrackEventKind::Midi {
+                channel: u4::new(0),
+                message: MidlyMessage::NoteOn {
+                    key: u7::new(key),
+                    vel: u7::new(100),
+                },
+            },
+        )
+    }
+
+    #[test]
+    fn test_parse_midi_file() {
+        let mut smf = Smf::new(Header::new(
+            Format::Parallel,
+            Timing::Metrical(u15::new(480)),
+        ));
+
+        // A beat at 120 BPM, then the tempo halves and a beat at 60 BPM
+        smf.tracks.push(vec![
+            note_on(0, 60),
+            note_on(480, 62),
+            event(
+                0,
+                TrackEventKind::Meta(MetaMessage::Tempo(u24::new(1_000_000))),
+            ),
+            note_on(480, 64),
+            event(0, TrackEventKind::Meta(MetaMessage::EndOfTrack)),
+        ]);
+        // A controller in another track, between the first two notes
+        smf.tracks.push(vec![
+            event(
+                240,
+                TrackEventKind::Midi {
+                    channel: u4::new(2),
+                    message: MidlyMessage::Controller {
+                        controller: u7::new(7),
+                        value: u7::new(64),
+                    },
+                },
+            ),
+            event(0, TrackEventKind::Meta(MetaMessage::EndOfTrack)),
+        ]);
+
+        let mut bytes = Vec::new();
+        smf.write_std(&mut bytes).unwrap();
+
+        let messages = parse_midi_file(&bytes, 48000).unwrap();
+        let frames: Vec<usize> = messages.iter().map(|&(frame, _)| frame).collect();
+
+        assert_eq!(frames, vec![0, 12000, 24000, 72000]);
+        assert_eq!(
+            messages[1].1,
+            MidiMessage::ControlChange {
+                channel: 2,
+                controller: 7,
+                value: 64
+            }
+        );
+
+        assert!(parse_midi_file(b"MThd", 48000).is_err());
+    }
+}
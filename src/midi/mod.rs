@@ -0,0 +1,239 @@
+//! Drive the parameters of the DSPs with MIDI messages: the notes played set
+//! the frequency of an oscillator, a controller its volume... The mappings are
+//! declared in the .ag files and the messages are applied by the audio thread
+//! at the start of a cycle, before the graph is processed.
+
+pub mod file;
+
+use midly::live::LiveEvent;
+use midly::num::u4;
+use midly::MidiMessage as MidlyMessage;
+
+use crate::dsp::Parameter;
+use crate::task_graph::graph::TaskGraph;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// A MIDI message driving the parameters of the DSPs. The channels are
+/// numbered from 0.
+pub enum MidiMessage {
+    NoteOn {
+        channel: u8,
+        key: u8,
+        velocity: u8,
+    },
+    NoteOff {
+        channel: u8,
+        key: u8,
+    },
+    ControlChange {
+        channel: u8,
+        controller: u8,
+        value: u8,
+    },
+}
+
+impl MidiMessage {
+    /// Parses the raw bytes of a MIDI message, as received on a JACK MIDI
+    /// port. Returns `None` for the messages which do not drive parameters.
+    ///
+    /// # Arguments
+    /// * `bytes` - The bytes of the message
+    pub fn parse(bytes: &[u8]) -> Option<MidiMessage> {
+        match LiveEvent::parse(bytes) {
+            Ok(LiveEvent::Midi { channel, message }) => MidiMessage::from_midly(channel, message),
+            _ => None,
+        }
+    }
+
+    // Converts a message parsed by midly, a note on with a velocity of 0 being
+    // a note off
+    fn from_midly(channel: u4, message: MidlyMessage) -> Option<MidiMessage> {
+        let channel = channel.as_int();
+
+        match message {
+            MidlyMessage::NoteOn { key, vel } if vel.as_int() > 0 => Some(MidiMessage::NoteOn {
+                channel,
+                key: key.as_int(),
+                velocity: vel.as_int(),
+            }),
+            MidlyMessage::NoteOn { key, .. } | MidlyMessage::NoteOff { key, .. } => {
+                Some(MidiMessage::NoteOff {
+                    channel,
+                    key: key.as_int(),
+                })
+            }
+            MidlyMessage::Controller { controller, value } => Some(MidiMessage::ControlChange {
+                channel,
+                controller: controller.as_int(),
+                value: value.as_int(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the channel of the message.
+    pub fn get_channel(&self) -> u8 {
+        match *self {
+            MidiMessage::NoteOn { channel, .. }
+            | MidiMessage::NoteOff { channel, .. }
+            | MidiMessage::ControlChange { channel, .. } => channel,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// The MIDI messages a parameter follows.
+pub enum MidiSource {
+    /// The notes played: their key for a frequency, their velocity for a
+    /// volume
+    Note,
+    /// The value of a controller
+    ControlChange(u8),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// Routes MIDI messages to a parameter of a node
+/// (`midi cc 7 channel 1 -> osc.volume;` in the .ag files).
+pub struct MidiMapping {
+    pub source: MidiSource,
+    /// The channel of the messages, from 0, or `None` for all of them
+    pub channel: Option<u8>,
+    pub node_index: usize,
+    pub parameter: Parameter,
+}
+
+/// Returns the frequency of a MIDI note, the A4 (69) being at 440 Hz.
+///
+/// # Arguments
+/// * `key` - The number of the note
+pub fn note_frequency(key: u8) -> f32 {
+    440.0 * 2f32.powf((f32::from(key) - 69.0) / 12.0)
+}
+
+impl MidiMapping {
+    /// Returns the value the parameter takes after a message, or `None` if the
+    /// mapping does not follow it. A note off leaves the parameters as they
+    /// are, and a controller driving a frequency plays its value as a note.
+    ///
+    /// # Arguments
+    /// * `message` - The MIDI message
+    pub fn get_value(&self, message: &MidiMessage) -> Option<f32> {
+        if self.channel.is_some() && self.channel != Some(message.get_channel()) {
+            return None;
+        }
+
+        match (self.source, *message, self.parameter) {
+            (MidiSource::Note, MidiMessage::NoteOn { key, .. }, Parameter::Frequency) => {
+                Some(note_frequency(key))
+            }
+            (MidiSource::Note, MidiMessage::NoteOn { velocity, .. }, Parameter::Volume) => {
+                Some(f32::from(velocity) / 127.0)
+            }
+            (
+                MidiSource::ControlChange(number),
+                MidiMessage::ControlChange {
+                    controller, value, ..
+                },
+                parameter,
+            ) if number == controller => match parameter {
+                Parameter::Frequency => Some(note_frequency(value)),
+                Parameter::Volume => Some(f32::from(value) / 127.0),
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Applies a MIDI message to the parameters of the nodes of a graph, following
+/// its MIDI mappings.
+///
+/// # Arguments
+/// * `graph` - The graph
+/// * `message` - The MIDI message
+pub fn apply_midi_message(graph: &TaskGraph, message: &MidiMessage) {
+    for mapping in graph.get_midi_mappings() {
+        let value = match mapping.get_value(message) {
+            Some(value) => value,
+            None => continue,
+        };
+
+        let dsp = graph.get_dsp(mapping.node_index);
+        let dsp = &mut *dsp.lock().unwrap();
+
+        if let Some(dsp) = dsp {
+            dsp.dsp.set_parameter(mapping.parameter, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod midi_test {
+    use super::*;
+    use dsp::DspNode;
+    use task_graph::task::DspTask;
+
+    #[test]
+    fn test_apply_midi_message() {
+        let mut g = TaskGraph::new(2, 0);
+        let osc = g.add_dsp(DspTask::new_oscillator("a".to_string(), 440.0, 1.0));
+        let modulator = g.add_dsp(DspTask::new_modulator("b".to_string(), 110.0, 1.0));
+
+        g.add_midi_mapping(MidiMapping {
+            source: MidiSource::Note,
+            channel: None,
+            node_index: osc,
+            parameter: Parameter::Frequency,
+        });
+        g.add_midi_mapping(MidiMapping {
+            source: MidiSource::ControlChange(7),
+            channel: Some(1),
+            node_index: modulator,
+            parameter: Parameter::Volume,
+        });
+
+        // Note on, channel 0, A5
+        let note = MidiMessage::parse(&[0x90, 81, 100]).unwrap();
+        assert_eq!(
+            note,
+            MidiMessage::NoteOn {
+                channel: 0,
+                key: 81,
+                velocity: 100
+            }
+        );
+        assert_eq!(
+            MidiMessage::parse(&[0x90, 81, 0]),
+            Some(MidiMessage::NoteOff {
+                channel: 0,
+                key: 81
+            })
+        );
+
+        apply_midi_message(&g, &note);
+        apply_midi_message(
+            &g,
+            &MidiMessage::NoteOff {
+                channel: 0,
+                key: 81,
+            },
+        );
+
+        // The controller is on another channel, then on the mapped one
+        apply_midi_message(&g, &MidiMessage::parse(&[0xb0, 7, 0]).unwrap());
+        apply_midi_message(&g, &MidiMessage::parse(&[0xb1, 7, 127]).unwrap());
+        apply_midi_message(&g, &MidiMessage::parse(&[0xb1, 7, 0]).unwrap());
+
+        match g.get_dsp(osc).lock().unwrap().as_ref().unwrap().dsp {
+            DspNode::Oscillator(ref o) => assert_eq!(o.get_frequency(), 880.0),
+            _ => panic!("not an oscillator"),
+        }
+
+        match g.get_dsp(modulator).lock().unwrap().as_ref().unwrap().dsp {
+            DspNode::Modulator(ref m) => {
+                assert_eq!(m.get_frequency(), 110.0);
+                assert_eq!(m.get_volume(), 0.0);
+            }
+            _ => panic!("not a modulator"),
+        }
+    }
+}
@@ -0,0 +1,323 @@
+//! Change the parameters of the nodes while a graph runs, and query the graph
+//! and the statistics of the run, with Open Sound Control messages received
+//! on UDP. This module decodes and encodes the OSC 1.0 packets, and hands the
+//! changes of the parameters to the audio thread.
+
+pub mod server;
+
+use std::io::{Error, ErrorKind, Result};
+
+use crate::dsp::Parameter;
+use crate::measure::ring::Consumer;
+use crate::task_graph::graph::TaskGraph;
+
+#[derive(Clone, Debug, PartialEq)]
+/// An argument of an OSC message.
+pub enum OscArgument {
+    Int(i32),
+    Float(f32),
+    String(String),
+    Blob(Vec<u8>),
+    Long(i64),
+    Double(f64),
+    Bool(bool),
+    Nil,
+}
+
+impl OscArgument {
+    /// Returns the value of a numeric argument.
+    pub fn as_f32(&self) -> Option<f32> {
+        match *self {
+            OscArgument::Int(value) => Some(value as f32),
+            OscArgument::Float(value) => Some(value),
+            OscArgument::Long(value) => Some(value as f32),
+            OscArgument::Double(value) => Some(value as f32),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// An OSC message: an address, such as `/node/osc/freq`, and arguments.
+pub struct OscMessage {
+    pub address: String,
+    pub arguments: Vec<OscArgument>,
+}
+
+// Returns an error about the content of a packet
+fn invalid_data(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+// Returns the size of some data padded to a multiple of four bytes
+fn padded(size: usize) -> usize {
+    (size + 3) & !3
+}
+
+// Reads the fields of a packet one after the other
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    // Returns the next bytes of the packet
+    fn take(&mut self, size: usize) -> Result<&'a [u8]> {
+        if self.offset + size > self.bytes.len() {
+            return Err(invalid_data("truncated packet"));
+        }
+
+        let bytes = &self.bytes[self.offset..self.offset + size];
+        self.offset += size;
+
+        Ok(bytes)
+    }
+
+    // Reads a null-terminated string, padded to four bytes
+    fn read_string(&mut self) -> Result<String> {
+        let rest = &self.bytes[self.offset.min(self.bytes.len())..];
+        let size = rest
+            .iter()
+            .position(|&byte| byte == 0)
+            .ok_or_else(|| invalid_data("unterminated string"))?;
+        let bytes = self.take(padded(size + 1))?;
+
+        String::from_utf8(bytes[..size].to_vec()).map_err(|_| invalid_data("invalid string"))
+    }
+
+    fn read_i32(&mut self) -> Result<i32> {
+        let bytes = self.take(4)?;
+
+        Ok(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_i64(&mut self) -> Result<i64> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+
+        Ok(i64::from_be_bytes(bytes))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.offset >= self.bytes.len()
+    }
+}
+
+// Decodes a message
+fn parse_message(bytes: &[u8]) -> Result<OscMessage> {
+    let mut reader = Reader { bytes, offset: 0 };
+    let address = reader.read_string()?;
+
+    if !address.starts_with('/') {
+        return Err(invalid_data("the address must start with /"));
+    }
+
+    // Old implementations may send messages without type tags
+    if reader.is_empty() {
+        return Ok(OscMessage::new(&address, Vec::new()));
+    }
+
+    let type_tags = reader.read_string()?;
+
+    if !type_tags.starts_with(',') {
+        return Err(invalid_data("missing type tags"));
+    }
+
+    let mut arguments = Vec::new();
+
+    for type_tag in type_tags[1..].chars() {
+        arguments.push(match type_tag {
+            'i' => OscArgument::Int(reader.read_i32()?),
+            'f' => OscArgument::Float(f32::from_bits(reader.read_i32()? as u32)),
+            's' | 'S' => OscArgument::String(reader.read_string()?),
+            'b' => {
+                let size = reader.read_i32()?;
+
+                if size < 0 {
+                    return Err(invalid_data("negative blob size"));
+                }
+
+                let blob = reader.take(padded(size as usize))?;
+
+                OscArgument::Blob(blob[..size as usize].to_vec())
+            }
+            'h' => OscArgument::Long(reader.read_i64()?),
+            'd' => OscArgument::Double(f64::from_bits(reader.read_i64()? as u64)),
+            'T' => OscArgument::Bool(true),
+            'F' => OscArgument::Bool(false),
+            'N' | 'I' => OscArgument::Nil,
+            _ => return Err(invalid_data("unsupported argument type")),
+        });
+    }
+
+    Ok(OscMessage { address, arguments })
+}
+
+/// Decodes an OSC packet. Returns its message, or the messages of a bundle
+/// and of the bundles it contains, their time tags being ignored.
+///
+/// # Arguments
+/// * `bytes` - The content of the packet
+pub fn parse_packet(bytes: &[u8]) -> Result<Vec<OscMessage>> {
+    if !bytes.starts_with(b"#bundle\0") {
+        return parse_message(bytes).map(|message| vec![message]);
+    }
+
+    // The bundle is followed by its time tag and by its sized elements
+    let mut reader = Reader { bytes, offset: 16 };
+    let mut messages = Vec::new();
+
+    while !reader.is_empty() {
+        let size = reader.read_i32()?;
+
+        if size < 0 {
+            return Err(invalid_data("negative element size"));
+        }
+
+        messages.extend(parse_packet(reader.take(size as usize)?)?);
+    }
+
+    Ok(messages)
+}
+
+// Writes a null-terminated string, padded to four bytes
+fn write_string(bytes: &mut Vec<u8>, string: &str) {
+    bytes.extend_from_slice(string.as_bytes());
+    bytes.resize(padded(bytes.len() + 1), 0);
+}
+
+impl OscMessage {
+    /// Creates a message.
+    ///
+    /// # Arguments
+    /// * `address` - The address of the message
+    /// * `arguments` - Its arguments
+    pub fn new(address: &str, arguments: Vec<OscArgument>) -> OscMessage {
+        OscMessage {
+            address: address.to_string(),
+            arguments,
+        }
+    }
+
+    /// Encodes the message in an OSC packet.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut type_tags = ",".to_string();
+
+        write_string(&mut bytes, &self.address);
+
+        for argument in &self.arguments {
+            type_tags.push(match argument {
+                OscArgument::Int(_) => 'i',
+                OscArgument::Float(_) => 'f',
+                OscArgument::String(_) => 's',
+                OscArgument::Blob(_) => 'b',
+                OscArgument::Long(_) => 'h',
+                OscArgument::Double(_) => 'd',
+                OscArgument::Bool(true) => 'T',
+                OscArgument::Bool(false) => 'F',
+                OscArgument::Nil => 'N',
+            });
+        }
+
+        write_string(&mut bytes, &type_tags);
+
+        for argument in &self.arguments {
+            match argument {
+                OscArgument::Int(value) => bytes.extend_from_slice(&value.to_be_bytes()),
+                OscArgument::Float(value) => {
+                    bytes.extend_from_slice(&value.to_bits().to_be_bytes())
+                }
+                OscArgument::String(value) => write_string(&mut bytes, value),
+                OscArgument::Blob(value) => {
+                    bytes.extend_from_slice(&(value.len() as i32).to_be_bytes());
+                    bytes.extend_from_slice(value);
+                    bytes.resize(padded(bytes.len()), 0);
+                }
+                OscArgument::Long(value) => bytes.extend_from_slice(&value.to_be_bytes()),
+                OscArgument::Double(value) => {
+                    bytes.extend_from_slice(&value.to_bits().to_be_bytes())
+                }
+                OscArgument::Bool(_) | OscArgument::Nil => {}
+            }
+        }
+
+        bytes
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// A change of a parameter of a node, handed to the audio thread.
+pub struct ParameterUpdate {
+    pub node_index: usize,
+    pub parameter: Parameter,
+    pub value: f32,
+}
+
+/// Applies the changes of parameters received since the last cycle, before
+/// the graph is processed. The audio thread neither blocks nor allocates to
+/// receive them.
+///
+/// # Arguments
+/// * `graph` - The audio graph of the run
+/// * `updates` - The ring the changes are received from
+pub fn apply_parameter_updates(graph: &TaskGraph, updates: &mut Consumer<ParameterUpdate>) {
+    while let Some(update) = updates.pop() {
+        let dsp = graph.get_dsp(update.node_index);
+        let dsp = &mut *dsp.lock().unwrap();
+
+        if let Some(dsp) = dsp {
+            dsp.dsp.set_parameter(update.parameter, update.value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod osc_test {
+    use super::*;
+
+    #[test]
+    fn test_parse_packet() {
+        // `/node/osc/freq 440.0`, as sent by `oscsend`
+        let mut packet = b"/node/osc/freq\0\0,f\0\0".to_vec();
+        packet.extend_from_slice(&440f32.to_bits().to_be_bytes());
+
+        let message = OscMessage::new("/node/osc/freq", vec![OscArgument::Float(440.0)]);
+
+        assert_eq!(parse_packet(&packet).unwrap(), vec![message.clone()]);
+        assert_eq!(message.to_bytes(), packet);
+
+        // Every type survives a round trip
+        let message = OscMessage::new(
+            "/all",
+            vec![
+                OscArgument::Int(-3),
+                OscArgument::String("abcd".to_string()),
+                OscArgument::Blob(vec![1, 2, 3, 4, 5]),
+                OscArgument::Long(1 << 40),
+                OscArgument::Double(0.25),
+                OscArgument::Bool(true),
+                OscArgument::Bool(false),
+                OscArgument::Nil,
+            ],
+        );
+        let bytes = message.to_bytes();
+
+        assert_eq!(bytes.len() % 4, 0);
+        assert_eq!(parse_packet(&bytes).unwrap(), vec![message.clone()]);
+
+        // A bundle of two messages
+        let mut bundle = b"#bundle\0\0\0\0\0\0\0\0\x01".to_vec();
+
+        for element in &[&packet, &bytes] {
+            bundle.extend_from_slice(&(element.len() as i32).to_be_bytes());
+            bundle.extend_from_slice(element);
+        }
+
+        assert_eq!(parse_packet(&bundle).unwrap().len(), 2);
+
+        assert!(parse_packet(b"node\0\0\0\0").is_err());
+        assert!(parse_packet(b"/node\0\0\0,f\0\0").is_err());
+        assert!(parse_packet(b"/node").is_err());
+    }
+}
@@ -0,0 +1,401 @@
+//! A UDP server receiving the OSC messages of a run on its own thread:
+//!
+//! - `/node/<id>/<param> <value>` changes the `freq` or the `volume` of an
+//!   oscillator or a modulator
+//! - `/nodes` is answered with `/nodes`, followed by the id and the kind of
+//!   each node
+//! - `/stats` is answered with `/stats` and the statistics of the cycles so
+//!   far: cycles, mean, median, 99th percentile and worst durations in µs,
+//!   deadline misses, xruns and lost measurements
+//!
+//! The other messages are answered with `/error <message>`. The statistics
+//! are answered once the measure thread gives them, without blocking the
+//! other messages. The ids of the nodes cannot contain `/`, since it separates
+//! the parts of the addresses.
+
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result};
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crossbeam::channel::{bounded, Receiver, Sender};
+
+use crate::dsp::Parameter;
+use crate::measure::ring::Producer;
+use crate::measure::stats::StatsSummary;
+use crate::measure::MeasureDestination;
+use crate::task_graph::dot::get_kind;
+use crate::task_graph::graph::TaskGraph;
+
+use super::{parse_packet, OscArgument, OscMessage, ParameterUpdate};
+
+/// The number of changes of parameters the audio thread can receive between
+/// two cycles.
+pub const UPDATES_CAPACITY: usize = 1024;
+
+// How often the server checks whether it must stop
+const POLL_PERIOD: Duration = Duration::from_millis(100);
+
+// How often the server checks whether the measure thread gave the statistics
+// it was asked
+const STATS_POLL_PERIOD: Duration = Duration::from_millis(5);
+
+// How long the server waits for the statistics of the measure thread
+const STATS_TIMEOUT: Duration = Duration::from_secs(1);
+
+// The size of the largest UDP packet
+const MAX_PACKET_SIZE: usize = 65536;
+
+// Returns an error message
+fn error(message: String) -> OscMessage {
+    OscMessage::new("/error", vec![OscArgument::String(message)])
+}
+
+// Answers the messages received by the server
+struct Handler {
+    nodes: Vec<(String, String)>, // The id and the kind of each node
+    node_indices: HashMap<String, usize>,
+    updates: Producer<ParameterUpdate>,
+    tx: Sender<MeasureDestination>,
+    log_path: String,
+    // The statistics asked to the measure thread, the sender they are
+    // answered to and when they were asked
+    pending_stats: Vec<(Receiver<StatsSummary>, SocketAddr, Instant)>,
+}
+
+impl Handler {
+    // Returns the answer to a message of a sender, if it has one now
+    fn handle(&mut self, message: &OscMessage, sender: SocketAddr) -> Option<OscMessage> {
+        let fields: Vec<&str> = message.address[1..].split('/').collect();
+
+        match fields.as_slice() {
+            ["nodes"] => Some(OscMessage::new(
+                "/nodes",
+                self.nodes
+                    .iter()
+                    .flat_map(|(id, kind)| {
+                        vec![
+                            OscArgument::String(id.clone()),
+                            OscArgument::String(kind.clone()),
+                        ]
+                    })
+                    .collect(),
+            )),
+            ["stats"] => self.ask_stats(sender).err().map(error),
+            ["node", node_id, parameter] => self
+                .update(node_id, parameter, &message.arguments)
+                .err()
+                .map(error),
+            _ => Some(error(format!("unknown address {}", message.address))),
+        }
+    }
+
+    // Hands the change of a parameter to the audio thread
+    fn update(
+        &mut self,
+        node_id: &str,
+        parameter_name: &str,
+        arguments: &[OscArgument],
+    ) -> std::result::Result<(), String> {
+        let node_index = *self
+            .node_indices
+            .get(node_id)
+            .ok_or_else(|| format!("unknown node {}", node_id))?;
+        let parameter = match self.nodes[node_index].1.as_str() {
+            "osc" | "mod" => Parameter::from_name(parameter_name),
+            _ => None,
+        }
+        .ok_or_else(|| format!("{} has no parameter {}", node_id, parameter_name))?;
+        let value = match arguments {
+            [argument] => argument.as_f32(),
+            _ => None,
+        }
+        .ok_or_else(|| format!("expected a number for {}.{}", node_id, parameter_name))?;
+
+        if self.updates.push(ParameterUpdate {
+            node_index,
+            parameter,
+            value,
+        }) {
+            Ok(())
+        } else {
+            Err("too many updates".to_string())
+        }
+    }
+
+    // Asks the statistics of the run to the measure thread, which are answered
+    // by get_stats_answers
+    fn ask_stats(&mut self, sender: SocketAddr) -> std::result::Result<(), String> {
+        let (reply_tx, reply_rx) = bounded(1);
+
+        self.tx
+            .send(MeasureDestination::Stats(self.log_path.clone(), reply_tx))
+            .map_err(|_| "no measure thread".to_string())?;
+        self.pending_stats.push((reply_rx, sender, Instant::now()));
+
+        Ok(())
+    }
+
+    // Returns the statistics given by the measure thread since the last call
+    // and the senders they are answered to, or an error for those it did not
+    // give in time
+    fn get_stats_answers(&mut self) -> Vec<(OscMessage, SocketAddr)> {
+        let mut answers = Vec::new();
+
+        self.pending_stats.retain(|(reply_rx, sender, asked)| {
+            let answer = match reply_rx.try_recv() {
+                Ok(summary) => OscMessage::new(
+                    "/stats",
+                    vec![
+                        OscArgument::Int(summary.nb_cycles as i32),
+                        OscArgument::Float(summary.mean as f32),
+                        OscArgument::Int(summary.median as i32),
+                        OscArgument::Int(summary.p99 as i32),
+                        OscArgument::Int(summary.max as i32),
+                        OscArgument::Int(summary.nb_deadline_misses as i32),
+                        OscArgument::Int(summary.nb_xruns as i32),
+                        OscArgument::Int(summary.nb_lost as i32),
+                    ],
+                ),
+                Err(_) if asked.elapsed() >= STATS_TIMEOUT => error("no statistics".to_string()),
+                Err(_) => return true,
+            };
+
+            answers.push((answer, *sender));
+            false
+        });
+
+        answers
+    }
+}
+
+/// A server receiving OSC messages on UDP, stopped when it is dropped.
+pub struct OscServer {
+    address: SocketAddr,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl OscServer {
+    /// Starts a server changing the parameters of the nodes of a graph.
+    ///
+    /// # Arguments
+    /// * `address` - The address the server listens on, such as
+    ///   `127.0.0.1:9000`
+    /// * `graph` - The audio graph of the run
+    /// * `updates` - The ring the changes of parameters are sent on
+    /// * `tx` - The channel of the measure thread, which gives the statistics
+    /// * `log_path` - The log of the run, whose statistics are queried
+    pub fn start(
+        address: &str,
+        graph: &TaskGraph,
+        updates: Producer<ParameterUpdate>,
+        tx: Sender<MeasureDestination>,
+        log_path: &str,
+    ) -> Result<OscServer> {
+        let socket = UdpSocket::bind(address)?;
+        socket.set_read_timeout(Some(POLL_PERIOD))?;
+
+        let nodes: Vec<_> = (0..graph.get_nb_node())
+            .map(|node_index| (graph.get_node_name(node_index), get_kind(graph, node_index)))
+            .collect();
+
+        if let Some((id, _)) = nodes.iter().find(|(id, _)| id.contains('/')) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "the node id {} contains a /, which OSC addresses cannot contain",
+                    id
+                ),
+            ));
+        }
+
+        let node_indices = nodes
+            .iter()
+            .enumerate()
+            .map(|(node_index, (id, _))| (id.clone(), node_index))
+            .collect();
+
+        let mut handler = Handler {
+            nodes,
+            node_indices,
+            updates,
+            tx,
+            log_path: log_path.to_string(),
+            pending_stats: Vec::new(),
+        };
+        let address = socket.local_addr()?;
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = running.clone();
+
+        let thread = thread::spawn(move || {
+            let mut buffer = vec![0; MAX_PACKET_SIZE];
+
+            while thread_running.load(Ordering::Relaxed) {
+                for (answer, sender) in handler.get_stats_answers() {
+                    let _ignored = socket.send_to(&answer.to_bytes(), sender);
+                }
+
+                // The statistics asked are answered without waiting for the
+                // next message
+                let period = if handler.pending_stats.is_empty() {
+                    POLL_PERIOD
+                } else {
+                    STATS_POLL_PERIOD
+                };
+
+                if let Err(e) = socket.set_read_timeout(Some(period)) {
+                    eprintln!("OSC server: {}", e);
+                    break;
+                }
+
+                let (size, sender) = match socket.recv_from(&mut buffer) {
+                    Ok(received) => received,
+                    // The timeouts let the server stop, and the senders which
+                    // closed their port are ignored
+                    Err(ref e)
+                        if e.kind() == ErrorKind::WouldBlock
+                            || e.kind() == ErrorKind::TimedOut
+                            || e.kind() == ErrorKind::ConnectionReset =>
+                    {
+                        continue
+                    }
+                    Err(e) => {
+                        eprintln!("OSC server: {}", e);
+                        break;
+                    }
+                };
+
+                let answers = match parse_packet(&buffer[..size]) {
+                    Ok(messages) => messages
+                        .iter()
+                        .filter_map(|message| handler.handle(message, sender))
+                        .collect(),
+                    Err(e) => vec![error(e.to_string())],
+                };
+
+                for answer in answers {
+                    let _ignored = socket.send_to(&answer.to_bytes(), sender);
+                }
+            }
+        });
+
+        Ok(OscServer {
+            address,
+            running,
+            thread: Some(thread),
+        })
+    }
+
+    /// Returns the address the server listens on.
+    pub fn get_address(&self) -> SocketAddr {
+        self.address
+    }
+}
+
+impl Drop for OscServer {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+
+        if let Some(thread) = self.thread.take() {
+            let _ignored = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod server_test {
+    use super::*;
+    use crossbeam::channel::unbounded;
+    use dsp::DspNode;
+    use measure::ring::ring_buffer;
+    use measure::Measure;
+    use osc::apply_parameter_updates;
+    use task_graph::task::DspTask;
+
+    #[test]
+    fn test_osc_server() {
+        let mut g = TaskGraph::new(2, 1);
+
+        g.add_dsp(DspTask::new_oscillator("a".to_string(), 440.0, 1.0));
+        g.add_dsp(DspTask::new_sink("s".to_string(), 1));
+        g.add_edge(0, 1);
+
+        let (tx, rx) = unbounded();
+        let measure = thread::spawn(move || Measure::new(rx).receive());
+        let (updates, mut updates_consumer) = ring_buffer(UPDATES_CAPACITY);
+        let server = OscServer::start("127.0.0.1:0", &g, updates, tx, "osc_test_log.txt").unwrap();
+
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+
+        // Sends a message and returns the answer
+        let query = |message: OscMessage| {
+            client
+                .send_to(&message.to_bytes(), server.get_address())
+                .unwrap();
+
+            let mut buffer = vec![0; MAX_PACKET_SIZE];
+            let size = client.recv(&mut buffer).unwrap();
+
+            parse_packet(&buffer[..size]).unwrap().remove(0)
+        };
+
+        let string = |value: &str| OscArgument::String(value.to_string());
+
+        assert_eq!(
+            query(OscMessage::new("/nodes", Vec::new())).arguments,
+            vec![string("a"), string("osc"), string("s"), string("sink")]
+        );
+
+        let stats = query(OscMessage::new("/stats", Vec::new()));
+        assert_eq!(stats.address, "/stats");
+        assert_eq!(stats.arguments[0], OscArgument::Int(0));
+
+        for wrong in &["/node/b/freq", "/node/s/volume", "/node/a/cutoff", "/play"] {
+            assert_eq!(
+                query(OscMessage::new(wrong, vec![OscArgument::Float(1.0)])).address,
+                "/error"
+            );
+        }
+
+        // An update has no answer: the next query follows it
+        client
+            .send_to(
+                &OscMessage::new("/node/a/freq", vec![OscArgument::Int(220)]).to_bytes(),
+                server.get_address(),
+            )
+            .unwrap();
+        query(OscMessage::new("/nodes", Vec::new()));
+
+        apply_parameter_updates(&g, &mut updates_consumer);
+
+        match g.get_dsp(0).lock().unwrap().as_ref().unwrap().dsp {
+            DspNode::Oscillator(ref o) => assert_eq!(o.get_frequency(), 220.0),
+            _ => panic!("not an oscillator"),
+        }
+
+        drop(server);
+        measure.join().unwrap();
+    }
+
+    #[test]
+    fn test_node_id_with_slash() {
+        let mut g = TaskGraph::new(1, 1);
+
+        g.add_dsp(DspTask::new_sink("out/left".to_string(), 1));
+
+        let (tx, _rx) = unbounded();
+        let (updates, _updates_consumer) = ring_buffer(UPDATES_CAPACITY);
+
+        match OscServer::start("127.0.0.1:0", &g, updates, tx, "osc_test_log.txt") {
+            Err(e) => assert_eq!(e.kind(), ErrorKind::InvalidInput),
+            Ok(_) => panic!("the node id contains a /"),
+        }
+    }
+}
@@ -6,10 +6,12 @@ use std::io::prelude::*;
 use std::path::Path;
 use std::vec::IntoIter;
 
-use pest::error::Error as ParseError;
+use pest::error::{Error as ParseError, ErrorVariant};
 use pest::iterators::*;
 use pest::Parser;
 
+use dsp::Parameter;
+use midi::{MidiMapping, MidiSource};
 use task_graph::graph;
 use task_graph::task::DspTask;
 use task_graph::task::Task;
@@ -20,6 +22,7 @@ pub struct Edge {
     src_port: u32,
     dst_id: String,
     dst_port: u32,
+    cost: Option<f64>,
 }
 
 #[derive(Parser)]
@@ -70,7 +73,16 @@ fn parse_node(pair: Pair<Rule>) -> Task {
     }
 }
 
-fn parse_dsp_node(pair: Pair<Rule>) -> DspTask {
+// The DSP of a node, with the JACK ports the port of a sink or an input is
+// connected to (`connect: "system:playback_1,system:playback_2",`) and the
+// WAV file an input plays offline (`file: "guitar.wav",`)
+struct DspAttributes {
+    dsp: DspTask,
+    connections: Vec<String>,
+    file: Option<String>,
+}
+
+fn parse_dsp_node(pair: Pair<Rule>) -> DspAttributes {
     let mut inner_rules = pair.into_inner();
     let id: String = inner_rules.next().unwrap().as_str().to_string();
     let mut nb_inlets: usize = 0;
@@ -78,6 +90,8 @@ fn parse_dsp_node(pair: Pair<Rule>) -> DspTask {
     let mut class_name: String = String::default();
     let mut more: HashMap<String, String> = HashMap::new();
     let mut volume: f32 = 0.0;
+    let mut connections = Vec::new();
+    let mut file = None;
 
     //Attributes
     for attribute in inner_rules {
@@ -89,38 +103,120 @@ fn parse_dsp_node(pair: Pair<Rule>) -> DspTask {
             "out" => nb_outlets = v.parse().unwrap(),
             "kind" => class_name = v.to_string(),
             "volume" => volume = v.parse().unwrap(),
+            "connect" => {
+                connections = v
+                    .split(',')
+                    .map(|port| port.trim().to_string())
+                    .filter(|port| !port.is_empty())
+                    .collect()
+            }
+            "file" => file = Some(v.to_string()),
             _ => {
                 more.insert(token.to_string(), v.to_string());
             }
         }
     }
 
-    match class_name.as_str() {
+    let dsp = match class_name.as_str() {
         "osc" => DspTask::new_oscillator(
             id,
-            more["freq"].parse().expect("frq must be an integer"),
+            more["freq"].parse().expect("frq must be a number"),
             volume,
         ),
         "mod" => DspTask::new_modulator(
             id,
-            more["freq"].parse().expect("frq must be an integer"),
+            more["freq"].parse().expect("frq must be a number"),
             volume,
         ),
         "mix" => DspTask::new_io_adaptor(id, nb_inlets, nb_outlets),
         "sink" => DspTask::new_sink(id, 1),
+        "input" => DspTask::new_input(id, 1),
         // Return default DSPs if the class name is unknown
         _ => {
             if nb_inlets == 0 && nb_outlets == 1 {
-                DspTask::new_oscillator(id, 440, 1.0)
+                DspTask::new_oscillator(id, 440.0, 1.0)
             } else if nb_inlets == 1 && nb_outlets == 0 {
                 DspTask::new_sink(id, 1)
             } else if nb_inlets == 1 && nb_outlets == 1 {
-                DspTask::new_modulator(id, 110, 1.0)
+                DspTask::new_modulator(id, 110.0, 1.0)
             } else {
                 DspTask::new_io_adaptor(id, nb_inlets, nb_outlets)
             }
         }
+    };
+
+    DspAttributes {
+        dsp,
+        connections,
+        file,
+    }
+}
+
+// Returns an error located on a token of the file
+fn custom_error(pair: &Pair<Rule>, message: String) -> ParseError<Rule> {
+    ParseError::new_from_span(ErrorVariant::CustomError { message }, pair.as_span())
+}
+
+// Parses a MIDI mapping (`midi cc 7 channel 1 -> osc.volume;`), the channels
+// being numbered from 1 in the file
+fn parse_midi_map(
+    pair: Pair<Rule>,
+    node_indices: &HashMap<String, usize>,
+) -> Result<MidiMapping, ParseError<Rule>> {
+    let mut source = MidiSource::Note;
+    let mut channel = None;
+    let mut node_index = 0;
+    let mut parameter = Parameter::Frequency;
+
+    for inner_rule in pair.into_inner() {
+        match inner_rule.as_rule() {
+            Rule::cc => {
+                let number = inner_rule.into_inner().next().unwrap();
+
+                match number.as_str().parse() {
+                    Ok(controller) if controller < 128 => {
+                        source = MidiSource::ControlChange(controller)
+                    }
+                    _ => {
+                        return Err(custom_error(
+                            &number,
+                            "the controller must be between 0 and 127".to_string(),
+                        ))
+                    }
+                }
+            }
+            Rule::channel => {
+                let number = inner_rule.into_inner().next().unwrap();
+
+                match number.as_str().parse::<u8>() {
+                    Ok(number) if (1..=16).contains(&number) => channel = Some(number - 1),
+                    _ => {
+                        return Err(custom_error(
+                            &number,
+                            "the channel must be between 1 and 16".to_string(),
+                        ))
+                    }
+                }
+            }
+            Rule::param_ident => {
+                let mut param_ident = inner_rule.into_inner();
+                let id = param_ident.next().unwrap();
+
+                node_index = *node_indices
+                    .get(id.as_str())
+                    .ok_or_else(|| custom_error(&id, format!("unknown node {}", id.as_str())))?;
+                parameter = Parameter::from_name(param_ident.next().unwrap().as_str()).unwrap();
+            }
+            _ => {}
+        }
     }
+
+    Ok(MidiMapping {
+        source,
+        channel,
+        node_index,
+        parameter,
+    })
 }
 
 fn parse_edge(pair: Pair<Rule>) -> IntoIter<Edge> {
@@ -132,15 +228,22 @@ fn parse_edge(pair: Pair<Rule>) -> IntoIter<Edge> {
     let mut edges = Vec::new();
 
     for inner_rule in inner_rules {
-        port_ident = inner_rule.into_inner().next().unwrap().into_inner();
+        let mut edge_rules = inner_rule.into_inner();
+        port_ident = edge_rules.next().unwrap().into_inner();
         let dst_id = port_ident.next().unwrap().as_str().to_string();
         let dst_port = port_ident.next().unwrap().as_str().parse().unwrap();
 
+        // The optional communication cost of the edge
+        let cost = edge_rules
+            .next()
+            .map(|cost| cost.into_inner().next().unwrap().as_str().parse().unwrap());
+
         edges.push(Edge {
             src_id,
             src_port,
             dst_id: dst_id.clone(),
             dst_port,
+            cost,
         });
 
         src_id = dst_id;
@@ -160,7 +263,7 @@ pub fn parse_audiograph(audiograph: &str) -> Result<graph::TaskGraph, ParseError
     let (nodes, edges): (Vec<_>, Vec<_>) = audiograph
         .into_inner()
         .flat_map(|r| r.into_inner())
-        .filter(|ref r| r.as_rule() != Rule::deadline)
+        .filter(|ref r| r.as_rule() == Rule::node || r.as_rule() == Rule::edges)
         // .inspect(|x| println!("Statement: {:?}.", x))
         .partition(|ref r| r.as_rule() == Rule::node);
 
@@ -188,6 +291,11 @@ pub fn parse_audiograph(audiograph: &str) -> Result<graph::TaskGraph, ParseError
         let dst_node = node_indices[&edge.dst_id];
 
         taskgraph.add_edge(src_node, dst_node);
+        taskgraph.set_ports(src_node, dst_node, edge.src_port, edge.dst_port);
+
+        if let Some(cost) = edge.cost {
+            taskgraph.set_communication_cost(src_node, dst_node, cost);
+        }
     }
 
     Ok(taskgraph)
@@ -198,10 +306,14 @@ pub fn parse_dsp_audiograph(audiograph: &str) -> Result<graph::TaskGraph, ParseE
         .next()
         .unwrap();
 
-    let (nodes, edges): (Vec<_>, Vec<_>) = audiograph
+    let statements = audiograph
         .into_inner()
         .flat_map(|r| r.into_inner())
-        .filter(|ref r| r.as_rule() != Rule::deadline)
+        .collect::<Vec<_>>();
+    let (nodes, edges): (Vec<_>, Vec<_>) = statements
+        .iter()
+        .filter(|r| r.as_rule() == Rule::node || r.as_rule() == Rule::edges)
+        .cloned()
         .partition(|ref r| r.as_rule() == Rule::node);
 
     let nodes = nodes.into_iter().map(parse_dsp_node).collect::<Vec<_>>();
@@ -210,14 +322,22 @@ pub fn parse_dsp_audiograph(audiograph: &str) -> Result<graph::TaskGraph, ParseE
 
     let mut taskgraph = graph::TaskGraph::new(nodes.len(), edges.len());
 
-    for task in nodes.into_iter() {
+    for node in nodes.into_iter() {
         let task_id;
 
-        match &task {
+        match &node.dsp {
             DspTask { id, .. } => task_id = id.clone(),
         }
 
-        let node_index = taskgraph.add_dsp(task);
+        let node_index = taskgraph.add_dsp(node.dsp);
+
+        if !node.connections.is_empty() {
+            taskgraph.set_connections(node_index, node.connections);
+        }
+
+        if let Some(file) = node.file {
+            taskgraph.set_input_file(node_index, &file);
+        }
 
         node_indices.insert(task_id, node_index);
     }
@@ -227,6 +347,30 @@ pub fn parse_dsp_audiograph(audiograph: &str) -> Result<graph::TaskGraph, ParseE
         let dst_node = node_indices[&edge.dst_id];
 
         taskgraph.add_edge(src_node, dst_node);
+        taskgraph.set_ports(src_node, dst_node, edge.src_port, edge.dst_port);
+
+        if let Some(cost) = edge.cost {
+            taskgraph.set_communication_cost(src_node, dst_node, cost);
+        }
+    }
+
+    for statement in statements {
+        match statement.as_rule() {
+            Rule::midi_map => {
+                taskgraph.add_midi_mapping(parse_midi_map(statement, &node_indices)?);
+            }
+            Rule::midi_file => {
+                let path = statement
+                    .into_inner()
+                    .next()
+                    .unwrap()
+                    .into_inner()
+                    .next()
+                    .unwrap();
+                taskgraph.set_midi_file(path.as_str());
+            }
+            _ => {}
+        }
     }
 
     Ok(taskgraph)
@@ -251,3 +395,44 @@ pub fn parse_audio_graph(path: &str) -> Result<graph::TaskGraph, ParseError<Rule
 
     parse_dsp_audiograph(&s)
 }
+
+#[cfg(test)]
+mod audiograph_parser_test {
+    use super::*;
+
+    #[test]
+    fn test_parse_connections() {
+        let mut graph = parse_dsp_audiograph(
+            "in = {kind: \"input\", file: \"guitar.wav\", connect: \"system:capture_1\",};\n\
+             left = {kind: \"sink\", in: 1, connect: \"system:playback_1\",};\n\
+             right = {kind: \"sink\", in: 1, connect: \"system:playback_2, recorder:in\",};\n\
+             mix = {kind: \"mix\", in: 1, out: 3,};\n\
+             monitor = {kind: \"sink\", in: 1,};\n\
+             in.0 -> mix.0;\n\
+             mix.0 -> left.0;\n\
+             mix.1 -> right.0;\n\
+             mix.2 -> monitor.0;",
+        )
+        .unwrap();
+
+        assert_eq!(graph.get_entry_nodes(), vec![0]);
+        assert_eq!(graph.get_connections(0), &["system:capture_1".to_string()]);
+        assert_eq!(graph.get_input_file(0), Some("guitar.wav"));
+        assert_eq!(graph.get_input_file(1), None);
+
+        let connections: Vec<_> = graph
+            .get_exit_nodes()
+            .into_iter()
+            .map(|node_index| graph.get_connections(node_index).to_vec())
+            .collect();
+
+        assert_eq!(
+            connections,
+            vec![
+                vec!["system:playback_1".to_string()],
+                vec!["system:playback_2".to_string(), "recorder:in".to_string()],
+                Vec::new(),
+            ]
+        );
+    }
+}
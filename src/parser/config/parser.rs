@@ -0,0 +1,351 @@
+//! Parse a run configuration in TOML. Every key is optional:
+//!
+//! ```text
+//! [backend]
+//! client_name = "audio_graph"
+//! start_server = false
+//!
+//! [executor]
+//! kind = "static"            # seq, ws or static
+//! threads = 4
+//! algorithm = "heft"
+//! schedule = "tmp/heft.sched"
+//! drift_threshold = 0.2
+//! cores = [0, 2, 4, 6]
+//!
+//! [wcet]
+//! pwcet = 1e-9               # The maximal execution times if missing
+//! database = "tmp/wcet_profiles.txt"
+//!
+//! [logging]
+//! directory = "tmp"
+//! name = "heft_4"
+//! trace = true               # Write a Chrome trace of the run
+//!
+//! [ports]
+//! prefix = "graph_"          # Followed by the ids of the exit and input nodes
+//! auto_connect = true        # To the playback and capture ports in turn
+//! playback = "system:playback_.*"
+//! capture = "system:capture_.*"
+//!
+//! [connections]              # The JACK ports of exit and input nodes, by id
+//! guitar = "system:capture_1"
+//! left = "system:playback_1"
+//! monitor = ["phones:in_1", "phones:in_2"]
+//!
+//! [osc]
+//! enabled = true
+//! address = "127.0.0.1:9000"
+//! ```
+
+use std::fmt::{Display, Error as FmtError, Formatter};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use pest::error::{Error as ParseError, ErrorVariant};
+use pest::iterators::Pair;
+use pest::Parser;
+
+use execution::config::{RunConfig, EXECUTOR_KINDS};
+use static_scheduling::algorithms::SchedulingAlgorithm;
+use wcet::profiler::WcetSource;
+
+// The tables of a run configuration
+const TABLES: &[&str] = &[
+    "backend",
+    "executor",
+    "wcet",
+    "logging",
+    "ports",
+    "connections",
+    "osc",
+];
+
+#[derive(Parser)]
+#[grammar = "parser/config/config.pest"]
+pub struct ConfigParser;
+
+#[derive(Debug)]
+/// The errors which can happen when loading a run configuration.
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(ParseError<Rule>),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), FmtError> {
+        match self {
+            ConfigError::Io(e) => write!(fmt, "{}", e),
+            ConfigError::Parse(e) => write!(fmt, "{}", e),
+        }
+    }
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(e: io::Error) -> ConfigError {
+        ConfigError::Io(e)
+    }
+}
+
+impl From<ParseError<Rule>> for ConfigError {
+    fn from(e: ParseError<Rule>) -> ConfigError {
+        ConfigError::Parse(e)
+    }
+}
+
+// Returns an error located on a token of the file
+fn custom_error(pair: &Pair<Rule>, message: &str) -> ParseError<Rule> {
+    ParseError::new_from_span(
+        ErrorVariant::CustomError {
+            message: message.to_string(),
+        },
+        pair.as_span(),
+    )
+}
+
+// Returns the content of a string, without its escape sequences
+fn get_string(value: &Pair<Rule>) -> Result<String, ParseError<Rule>> {
+    if value.as_rule() != Rule::string {
+        return Err(custom_error(value, "expected a string"));
+    }
+
+    let mut content = String::new();
+    let mut chars = value.clone().into_inner().next().unwrap().as_str().chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next().unwrap() {
+                'n' => content.push('\n'),
+                't' => content.push('\t'),
+                c => content.push(c),
+            },
+            c => content.push(c),
+        }
+    }
+
+    Ok(content)
+}
+
+// Returns the value of a number
+fn get_number(value: &Pair<Rule>) -> Result<f64, ParseError<Rule>> {
+    match value.as_rule() {
+        Rule::number => Ok(value.as_str().parse().unwrap()),
+        _ => Err(custom_error(value, "expected a number")),
+    }
+}
+
+// Returns the value of a natural number
+fn get_natural(value: &Pair<Rule>) -> Result<usize, ParseError<Rule>> {
+    value
+        .as_str()
+        .parse()
+        .map_err(|_| custom_error(value, "expected a natural number"))
+}
+
+// Returns the value of a boolean
+fn get_boolean(value: &Pair<Rule>) -> Result<bool, ParseError<Rule>> {
+    match value.as_rule() {
+        Rule::boolean => Ok(value.as_str() == "true"),
+        _ => Err(custom_error(value, "expected a boolean")),
+    }
+}
+
+// Returns the values of an array
+fn get_array<T, F>(value: &Pair<Rule>, get: F) -> Result<Vec<T>, ParseError<Rule>>
+where
+    F: Fn(&Pair<Rule>) -> Result<T, ParseError<Rule>>,
+{
+    if value.as_rule() != Rule::array {
+        return Err(custom_error(value, "expected an array"));
+    }
+
+    value
+        .clone()
+        .into_inner()
+        .map(|value| get(&value))
+        .collect()
+}
+
+// Sets a key of a table of the configuration
+fn set_key(
+    config: &mut RunConfig,
+    table: &str,
+    key: &Pair<Rule>,
+    value: &Pair<Rule>,
+) -> Result<(), ParseError<Rule>> {
+    match (table, key.as_str()) {
+        ("backend", "client_name") => config.backend.client_name = Some(get_string(value)?),
+        ("backend", "start_server") => config.backend.start_server = get_boolean(value)?,
+        ("executor", "kind") => {
+            let kind = get_string(value)?;
+
+            if !EXECUTOR_KINDS.contains(&kind.as_str()) {
+                return Err(custom_error(value, "expected seq, ws or static"));
+            }
+
+            config.executor.kind = kind;
+        }
+        ("executor", "threads") => match get_natural(value)? {
+            0 => return Err(custom_error(value, "expected at least one thread")),
+            threads => config.executor.threads = threads,
+        },
+        ("executor", "algorithm") => {
+            config.executor.algorithm = SchedulingAlgorithm::from_name(&get_string(value)?)
+                .ok_or_else(|| custom_error(value, "unknown scheduling algorithm"))?
+        }
+        ("executor", "schedule") => config.executor.schedule = Some(get_string(value)?),
+        ("executor", "drift_threshold") => {
+            config.executor.drift_threshold = Some(get_number(value)?)
+        }
+        ("executor", "cores") => config.executor.cores = get_array(value, get_natural)?,
+        ("wcet", "pwcet") => match get_number(value)? {
+            p if p > 0.0 && p < 1.0 => config.wcet.source = WcetSource::PWcet(p),
+            _ => {
+                return Err(custom_error(
+                    value,
+                    "the exceedance probability must be between 0 and 1",
+                ))
+            }
+        },
+        ("wcet", "database") => config.wcet.database = get_string(value)?,
+        ("logging", "directory") => config.logging.directory = get_string(value)?,
+        ("logging", "name") => config.logging.name = Some(get_string(value)?),
+        ("logging", "trace") => config.logging.trace = get_boolean(value)?,
+        ("ports", "prefix") => config.ports.prefix = get_string(value)?,
+        ("ports", "auto_connect") => config.ports.auto_connect = get_boolean(value)?,
+        ("ports", "playback") => config.ports.playback = get_string(value)?,
+        ("ports", "capture") => config.ports.capture = get_string(value)?,
+        ("osc", "enabled") => config.osc.enabled = get_boolean(value)?,
+        ("osc", "address") => config.osc.address = get_string(value)?,
+        ("connections", sink_id) => {
+            let destinations = match value.as_rule() {
+                Rule::array => get_array(value, get_string)?,
+                _ => vec![get_string(value)?],
+            };
+
+            config
+                .ports
+                .connections
+                .insert(sink_id.to_string(), destinations);
+        }
+        _ => return Err(custom_error(key, &format!("unknown key in [{}]", table))),
+    }
+
+    Ok(())
+}
+
+/// Parses a run configuration in TOML, the missing keys keeping their
+/// default value.
+///
+/// # Arguments
+/// * `toml` - The content of the TOML file
+pub fn parse_config(toml: &str) -> Result<RunConfig, ParseError<Rule>> {
+    let file = ConfigParser::parse(Rule::file, toml)?.next().unwrap();
+    let mut config = RunConfig::default();
+    let mut table = String::new();
+
+    for line in file.into_inner() {
+        match line.as_rule() {
+            Rule::table => {
+                let name = line.into_inner().next().unwrap();
+
+                if !TABLES.contains(&name.as_str()) {
+                    return Err(custom_error(&name, "unknown table"));
+                }
+
+                table = name.as_str().to_string();
+            }
+            Rule::pair => {
+                let mut fields = line.into_inner();
+                let key = fields.next().unwrap();
+                let value = fields.next().unwrap();
+
+                set_key(&mut config, &table, &key, &value)?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(config)
+}
+
+/// Loads a run configuration from a TOML file.
+///
+/// # Arguments
+/// * `path` - The path of the file
+pub fn load_config<P: AsRef<Path>>(path: P) -> Result<RunConfig, ConfigError> {
+    Ok(parse_config(&fs::read_to_string(path)?)?)
+}
+
+#[cfg(test)]
+mod config_parser_test {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = parse_config(
+            "# A static scheduling on the even CPUs\n\
+             [backend]\n\
+             client_name = \"agp\"\n\
+             \n\
+             [executor]\n\
+             kind = \"static\"   # With HEFT\n\
+             threads = 4\n\
+             algorithm = \"heft\"\n\
+             cores = [\n    0, 2,\n    4, 6,\n]\n\
+             \n\
+             [wcet]\n\
+             pwcet = 1e-9\n\
+             \n\
+             [logging]\n\
+             trace = true\n\
+             \n\
+             [ports]\n\
+             auto_connect = false\n\
+             \n\
+             [connections]\n\
+             left = \"system:playback_1\"\n\
+             monitor = [\"phones:in_1\", \"phones:in_2\"]\n\
+             \n\
+             [osc]\n\
+             enabled = true",
+        )
+        .unwrap();
+
+        assert_eq!(config.backend.client_name, Some("agp".to_string()));
+        assert!(!config.backend.start_server);
+        assert_eq!(config.executor.get_executor().get_name(), "heft");
+        assert_eq!(config.executor.threads, 4);
+        assert_eq!(config.executor.cores, vec![0, 2, 4, 6]);
+        assert_eq!(config.wcet.source, WcetSource::PWcet(1e-9));
+        assert_eq!(config.logging.directory, "tmp");
+        assert!(config.logging.trace);
+        assert_eq!(config.ports.prefix, "");
+        assert!(!config.ports.auto_connect);
+        assert_eq!(
+            config.ports.connections["left"],
+            vec!["system:playback_1".to_string()]
+        );
+        assert_eq!(config.ports.connections["monitor"].len(), 2);
+        assert!(config.osc.enabled);
+        assert_eq!(config.osc.address, "127.0.0.1:9000");
+
+        assert_eq!(parse_config("").unwrap(), RunConfig::default());
+
+        for wrong in &[
+            "[executor]\nthreads = 0",
+            "[executor]\nkind = \"fifo\"",
+            "[executor]\nthreads = \"4\"",
+            "[wcet]\npwcet = 2",
+            "[executor]\nthread = 4",
+            "[executors]",
+            "threads = 4",
+            "[connections]\nleft = [\"system:playback_1\"",
+            "[connections]\nleft = 1",
+            "[osc]\nenabled = 1",
+        ] {
+            assert!(parse_config(wrong).is_err(), "{}", wrong);
+        }
+    }
+}
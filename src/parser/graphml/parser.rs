@@ -0,0 +1,277 @@
+//! Parse a task graph in GraphML. The attributes of the nodes and of the
+//! edges are `data` elements, whose keys are declared with their name and
+//! type:
+//!
+//! ```text
+//! <graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+//!   <key id="kind" for="node" attr.name="kind" attr.type="string"/>
+//!   <key id="frequency" for="node" attr.name="frequency" attr.type="double"/>
+//!   <key id="cost" for="edge" attr.name="cost" attr.type="double"/>
+//!   <graph edgedefault="directed">
+//!     <node id="osc1"><data key="kind">osc</data><data key="frequency">440</data></node>
+//!     <node id="sink"><data key="kind">sink</data></node>
+//!     <edge source="osc1" target="sink"><data key="cost">0.5</data></edge>
+//!   </graph>
+//! </graphml>
+//! ```
+
+use std::collections::HashMap;
+
+use pest::error::{Error as ParseError, ErrorVariant};
+use pest::iterators::Pair;
+use pest::Parser;
+
+use parser::interchange::{build_graph, EdgeDescription, NodeDescription, Value};
+use parser::read_file;
+use task_graph::graph;
+
+#[derive(Parser)]
+#[grammar = "parser/graphml/graphml.pest"]
+pub struct GraphmlParser;
+
+// Returns an error located on a token of the file
+fn custom_error(pair: &Pair<Rule>, message: &str) -> ParseError<Rule> {
+    ParseError::new_from_span(
+        ErrorVariant::CustomError {
+            message: message.to_string(),
+        },
+        pair.as_span(),
+    )
+}
+
+// Replaces the predefined entities and the character references of XML
+fn unescape(text: &str) -> String {
+    let mut content = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        content.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let end = match rest.find(';') {
+            Some(end) => end,
+            None => break,
+        };
+
+        let entity = &rest[1..end];
+        let c = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ if entity.starts_with("#x") => u32::from_str_radix(&entity[2..], 16)
+                .ok()
+                .and_then(std::char::from_u32),
+            _ if entity.starts_with('#') => entity[1..].parse().ok().and_then(std::char::from_u32),
+            _ => None,
+        };
+
+        match c {
+            Some(c) => {
+                content.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                content.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    content.push_str(rest);
+
+    content
+}
+
+// Returns the name of an element
+fn get_name<'a>(element: &Pair<'a, Rule>) -> &'a str {
+    element.clone().into_inner().next().unwrap().as_str()
+}
+
+// Returns the value of an attribute of an element
+fn get_attribute(element: &Pair<Rule>, name: &str) -> Option<String> {
+    element
+        .clone()
+        .into_inner()
+        .filter(|pair| pair.as_rule() == Rule::attribute)
+        .map(|attribute| attribute.into_inner())
+        .find(|fields| fields.clone().next().unwrap().as_str() == name)
+        .map(|mut fields| {
+            let value = fields.nth(1).unwrap().into_inner().next().unwrap();
+            unescape(value.as_str())
+        })
+}
+
+// Returns the value of an attribute which is required
+fn require_attribute(element: &Pair<Rule>, name: &str) -> Result<String, ParseError<Rule>> {
+    get_attribute(element, name)
+        .ok_or_else(|| custom_error(element, &format!("missing attribute {}", name)))
+}
+
+// Returns the child elements of an element with a given name
+fn get_children<'a>(element: &Pair<'a, Rule>, name: &str) -> Vec<Pair<'a, Rule>> {
+    element
+        .clone()
+        .into_inner()
+        .filter(|pair| pair.as_rule() == Rule::element && get_name(pair) == name)
+        .collect()
+}
+
+// Returns the text of an element, without the surrounding whitespaces
+fn get_text(element: &Pair<Rule>) -> String {
+    let text: String = element
+        .clone()
+        .into_inner()
+        .filter(|pair| pair.as_rule() == Rule::text)
+        .map(|text| text.as_str())
+        .collect();
+
+    unescape(text.trim())
+}
+
+// Checks that each element is closed by a tag of the same name
+fn check_tags(element: &Pair<Rule>) -> Result<(), ParseError<Rule>> {
+    let names: Vec<Pair<Rule>> = element
+        .clone()
+        .into_inner()
+        .filter(|pair| pair.as_rule() == Rule::name)
+        .collect();
+
+    if names.len() == 2 && names[0].as_str() != names[1].as_str() {
+        return Err(custom_error(
+            &names[1],
+            &format!("expected </{}>", names[0].as_str()),
+        ));
+    }
+
+    for child in element.clone().into_inner() {
+        if child.as_rule() == Rule::element {
+            check_tags(&child)?;
+        }
+    }
+
+    Ok(())
+}
+
+// A key declared in the file: the name of the attribute and whether it is a
+// number
+type Key = (String, bool);
+
+// Returns the values of the data elements of a node or an edge
+fn get_data(
+    element: &Pair<Rule>,
+    keys: &HashMap<String, Key>,
+) -> Result<Vec<(String, Value)>, ParseError<Rule>> {
+    let mut attributes = Vec::new();
+
+    for data in get_children(element, "data") {
+        let key = require_attribute(&data, "key")?;
+        let (name, is_number) = keys
+            .get(&key)
+            .ok_or_else(|| custom_error(&data, &format!("undeclared key {}", key)))?;
+        let text = get_text(&data);
+
+        let value = if *is_number {
+            Value::Number(
+                text.parse()
+                    .map_err(|_| custom_error(&data, "expected a number"))?,
+            )
+        } else {
+            Value::Text(text)
+        };
+
+        attributes.push((name.clone(), value));
+    }
+
+    Ok(attributes)
+}
+
+// Parses an edge
+fn parse_edge(
+    element: &Pair<Rule>,
+    keys: &HashMap<String, Key>,
+) -> Result<EdgeDescription, ParseError<Rule>> {
+    let mut edge = EdgeDescription {
+        source: require_attribute(element, "source")?,
+        target: require_attribute(element, "target")?,
+        source_port: None,
+        target_port: None,
+        cost: None,
+    };
+
+    for (name, value) in get_data(element, keys)? {
+        let number = match value {
+            Value::Number(number) => number,
+            Value::Text(_) => continue,
+        };
+
+        match name.as_str() {
+            "source_port" => edge.source_port = Some(number as u32),
+            "target_port" => edge.target_port = Some(number as u32),
+            "cost" => edge.cost = Some(number),
+            _ => {}
+        }
+    }
+
+    Ok(edge)
+}
+
+/// Parses a task graph in GraphML. The nodes of the first graph of the file
+/// get their indices in the order of the file and the unknown data are
+/// ignored.
+///
+/// # Arguments
+/// * `graphml` - The content of the GraphML file
+pub fn parse_graphml(graphml: &str) -> Result<graph::TaskGraph, ParseError<Rule>> {
+    let root = GraphmlParser::parse(Rule::file, graphml)?.next().unwrap();
+
+    check_tags(&root)?;
+
+    if get_name(&root) != "graphml" {
+        return Err(custom_error(&root, "expected a graphml element"));
+    }
+
+    let mut keys = HashMap::new();
+
+    for key in get_children(&root, "key") {
+        let id = require_attribute(&key, "id")?;
+        let name = get_attribute(&key, "attr.name").unwrap_or_else(|| id.clone());
+        let is_number = matches!(
+            get_attribute(&key, "attr.type").as_deref(),
+            Some("int") | Some("long") | Some("float") | Some("double")
+        );
+
+        keys.insert(id, (name, is_number));
+    }
+
+    let graph = get_children(&root, "graph")
+        .into_iter()
+        .next()
+        .ok_or_else(|| custom_error(&root, "missing graph element"))?;
+
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+
+    for node in get_children(&graph, "node") {
+        nodes.push(NodeDescription {
+            id: require_attribute(&node, "id")?,
+            attributes: get_data(&node, &keys)?,
+        });
+    }
+
+    for edge in get_children(&graph, "edge") {
+        edges.push(parse_edge(&edge, &keys)?);
+    }
+
+    build_graph(&nodes, &edges).map_err(|message| {
+        ParseError::new_from_pos(
+            ErrorVariant::CustomError { message },
+            pest::Position::from_start(graphml),
+        )
+    })
+}
+
+pub fn parse(filename: &str) -> Result<graph::TaskGraph, ParseError<Rule>> {
+    parse_graphml(&read_file(filename)?)
+}
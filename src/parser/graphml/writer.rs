@@ -0,0 +1,119 @@
+//! Write a task graph in GraphML
+
+use std::fs;
+use std::io;
+
+use parser::interchange::{describe_graph, Value};
+use task_graph::graph::TaskGraph;
+
+// Escapes the characters which are not allowed in the text and the attribute
+// values of an XML file
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Returns the text of an attribute
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::Number(number) => format!("{}", number),
+        Value::Text(text) => escape(text),
+    }
+}
+
+/// Returns a task graph in GraphML, with the kind, the parameters and the
+/// known WCET of each node, and the ports and communication cost of each edge
+/// as `data` elements.
+///
+/// # Arguments
+/// * `graph` - The graph to write
+pub fn write_graphml(graph: &TaskGraph) -> String {
+    let (nodes, edges) = describe_graph(graph);
+
+    let mut graphml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+    );
+
+    // The keys of the node attributes, in the order they first appear
+    let mut node_keys: Vec<(&str, &str)> = Vec::new();
+
+    for node in &nodes {
+        for (key, value) in &node.attributes {
+            if node_keys.iter().all(|(name, _)| name != key) {
+                let key_type = match value {
+                    Value::Number(_) => "double",
+                    Value::Text(_) => "string",
+                };
+
+                node_keys.push((key, key_type));
+            }
+        }
+    }
+
+    for (name, key_type) in &node_keys {
+        graphml.push_str(&format!(
+            "  <key id=\"{0}\" for=\"node\" attr.name=\"{0}\" attr.type=\"{1}\"/>\n",
+            escape(name),
+            key_type
+        ));
+    }
+
+    graphml.push_str(
+        "  <key id=\"source_port\" for=\"edge\" attr.name=\"source_port\" attr.type=\"int\"/>\n\
+         \x20 <key id=\"target_port\" for=\"edge\" attr.name=\"target_port\" attr.type=\"int\"/>\n\
+         \x20 <key id=\"cost\" for=\"edge\" attr.name=\"cost\" attr.type=\"double\"/>\n\
+         \x20 <graph edgedefault=\"directed\">\n",
+    );
+
+    for node in &nodes {
+        graphml.push_str(&format!("    <node id=\"{}\">", escape(&node.id)));
+
+        for (key, value) in &node.attributes {
+            graphml.push_str(&format!(
+                "<data key=\"{}\">{}</data>",
+                escape(key),
+                format_value(value)
+            ));
+        }
+
+        graphml.push_str("</node>\n");
+    }
+
+    for edge in &edges {
+        graphml.push_str(&format!(
+            "    <edge source=\"{}\" target=\"{}\">",
+            escape(&edge.source),
+            escape(&edge.target)
+        ));
+
+        if let Some(source_port) = edge.source_port {
+            graphml.push_str(&format!("<data key=\"source_port\">{}</data>", source_port));
+        }
+
+        if let Some(target_port) = edge.target_port {
+            graphml.push_str(&format!("<data key=\"target_port\">{}</data>", target_port));
+        }
+
+        if let Some(cost) = edge.cost {
+            graphml.push_str(&format!("<data key=\"cost\">{}</data>", cost));
+        }
+
+        graphml.push_str("</edge>\n");
+    }
+
+    graphml.push_str("  </graph>\n</graphml>\n");
+
+    graphml
+}
+
+/// Writes a task graph in a GraphML file.
+///
+/// # Arguments
+/// * `graph` - The graph to write
+/// * `path` - The path of the file
+pub fn save_graphml(graph: &TaskGraph, path: &str) -> io::Result<()> {
+    fs::write(path, write_graphml(graph))
+}
@@ -0,0 +1,370 @@
+//! The description of a task graph shared by the interchange formats: a list
+//! of nodes with named attributes and a list of edges.
+//!
+//! The `kind` attribute of a node is the kind of its DSP in the `.ag` files
+//! (`osc`, `mod`, `mix`, `sink`, `input`) or the kind of its task (`constant`,
+//! `random`, `pd`, `ag`). The other attributes are the parameters of the DSP
+//! or of the task, and the WCET of the node when it is known.
+
+use std::collections::HashMap;
+
+use dsp::DspNode;
+use task_graph::graph::TaskGraph;
+use task_graph::task::{DspTask, Task};
+
+#[derive(Clone, Debug, PartialEq)]
+/// The value of an attribute.
+pub enum Value {
+    Number(f64),
+    Text(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A node of the graph and its attributes.
+pub struct NodeDescription {
+    pub id: String,
+    pub attributes: Vec<(String, Value)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// An edge of the graph, between the ids of two nodes.
+pub struct EdgeDescription {
+    pub source: String,
+    pub target: String,
+    pub source_port: Option<u32>,
+    pub target_port: Option<u32>,
+    pub cost: Option<f64>,
+}
+
+impl NodeDescription {
+    // Adds an attribute to the node
+    fn push(&mut self, key: &str, value: Value) {
+        self.attributes.push((key.to_string(), value));
+    }
+
+    /// Returns the value of an attribute if the node has it.
+    ///
+    /// # Arguments
+    /// * `key` - The name of the attribute
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.attributes
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value)
+    }
+
+    // Returns a numerical attribute which may be missing
+    fn get_number(&self, key: &str) -> Result<Option<f64>, String> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(Value::Number(number)) => Ok(Some(*number)),
+            Some(Value::Text(_)) => Err(format!("{}: {} must be a number", self.id, key)),
+        }
+    }
+
+    // Returns a numerical attribute which is required
+    fn require_number(&self, key: &str) -> Result<f64, String> {
+        self.get_number(key)?
+            .ok_or_else(|| format!("{}: missing {}", self.id, key))
+    }
+
+    // Returns a textual attribute which may be missing
+    fn get_text(&self, key: &str) -> Result<Option<String>, String> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(Value::Text(text)) => Ok(Some(text.clone())),
+            Some(Value::Number(_)) => Err(format!("{}: {} must be a string", self.id, key)),
+        }
+    }
+}
+
+// Describes a node of the graph
+fn describe_node(graph: &TaskGraph, node_index: usize) -> NodeDescription {
+    let mut node = NodeDescription {
+        id: graph.get_node_name(node_index),
+        attributes: Vec::new(),
+    };
+    let mut wcet = graph.get_known_wcet(node_index);
+
+    let dsp = graph.get_dsp(node_index);
+    let dsp = dsp.lock().unwrap();
+
+    match dsp.as_ref().map(|dsp| &dsp.dsp) {
+        Some(DspNode::Oscillator(osc)) => {
+            node.push("kind", Value::Text("osc".to_string()));
+            node.push("frequency", Value::Number(f64::from(osc.get_frequency())));
+            node.push("volume", Value::Number(f64::from(osc.get_volume())));
+        }
+        Some(DspNode::Modulator(modulator)) => {
+            node.push("kind", Value::Text("mod".to_string()));
+            node.push(
+                "frequency",
+                Value::Number(f64::from(modulator.get_frequency())),
+            );
+            node.push("volume", Value::Number(f64::from(modulator.get_volume())));
+        }
+        Some(DspNode::InputsOutputsAdaptor(adaptor)) => {
+            node.push("kind", Value::Text("mix".to_string()));
+            node.push("inputs", Value::Number(adaptor.get_nb_inputs() as f64));
+            node.push("outputs", Value::Number(adaptor.get_nb_outputs() as f64));
+        }
+        Some(DspNode::Sink(sink)) => {
+            node.push("kind", Value::Text("sink".to_string()));
+            node.push("channels", Value::Number(sink.get_nb_channels() as f64));
+        }
+        Some(DspNode::Input(input)) => {
+            node.push("kind", Value::Text("input".to_string()));
+            node.push("channels", Value::Number(input.get_nb_channels() as f64));
+        }
+        None => match graph.get_task(node_index).unwrap() {
+            Task::Constant(time) => {
+                node.push("kind", Value::Text("constant".to_string()));
+                wcet = Some(time);
+            }
+            Task::Random(min, max) => {
+                node.push("kind", Value::Text("random".to_string()));
+                node.push("min", Value::Number(min));
+                node.push("max", Value::Number(max));
+            }
+            Task::Puredata {
+                object_name,
+                xpos,
+                ypos,
+                args,
+            } => {
+                node.push("kind", Value::Text("pd".to_string()));
+                node.push("object", Value::Text(object_name));
+                node.push("x", Value::Number(xpos as f64));
+                node.push("y", Value::Number(ypos as f64));
+                node.push("args", Value::Text(args.join(" ")));
+            }
+            Task::Audiograph {
+                wcet: ag_wcet,
+                nb_inlets,
+                nb_outlets,
+                class_name,
+                text,
+                more,
+                volume,
+                ..
+            } => {
+                node.push("kind", Value::Text("ag".to_string()));
+                node.push("class", Value::Text(class_name));
+                node.push("inputs", Value::Number(f64::from(nb_inlets)));
+                node.push("outputs", Value::Number(f64::from(nb_outlets)));
+                node.push("volume", Value::Number(f64::from(volume)));
+
+                if let Some(text) = text {
+                    node.push("text", Value::Text(text));
+                }
+
+                let mut more: Vec<(String, String)> = more.into_iter().collect();
+                more.sort();
+
+                for (key, value) in more {
+                    node.push(&format!("more.{}", key), Value::Text(value));
+                }
+
+                // The WCET of the file is kept when the node has not been
+                // measured
+                wcet = wcet.or(ag_wcet);
+            }
+        },
+    }
+
+    let connections = graph.get_connections(node_index);
+
+    if !connections.is_empty() {
+        node.push("connect", Value::Text(connections.join(",")));
+    }
+
+    if let Some(path) = graph.get_input_file(node_index) {
+        node.push("file", Value::Text(path.to_string()));
+    }
+
+    if let Some(wcet) = wcet {
+        node.push("wcet", Value::Number(wcet));
+    }
+
+    node
+}
+
+/// Describes the nodes and the edges of a graph, the edges being sorted by
+/// source and destination.
+///
+/// # Arguments
+/// * `graph` - The graph to describe
+pub fn describe_graph(graph: &TaskGraph) -> (Vec<NodeDescription>, Vec<EdgeDescription>) {
+    let nodes = (0..graph.get_nb_node())
+        .map(|node_index| describe_node(graph, node_index))
+        .collect();
+
+    let mut edges: Vec<(usize, usize)> = graph.get_edges().keys().cloned().collect();
+    edges.sort();
+
+    let edges = edges
+        .into_iter()
+        .map(|(src, dst)| {
+            let ports = graph.get_ports(src, dst);
+
+            EdgeDescription {
+                source: graph.get_node_name(src),
+                target: graph.get_node_name(dst),
+                source_port: ports.map(|(src_port, _)| src_port),
+                target_port: ports.map(|(_, dst_port)| dst_port),
+                cost: graph.get_communication_cost(src, dst),
+            }
+        })
+        .collect();
+
+    (nodes, edges)
+}
+
+// Adds a described node to the graph and returns its index
+fn build_node(graph: &mut TaskGraph, node: &NodeDescription) -> Result<usize, String> {
+    let kind = node
+        .get_text("kind")?
+        .ok_or_else(|| format!("{}: missing kind", node.id))?;
+    let id = node.id.clone();
+
+    let node_index = match kind.as_str() {
+        "osc" => graph.add_dsp(DspTask::new_oscillator(
+            id,
+            node.require_number("frequency")? as f32,
+            node.get_number("volume")?.unwrap_or(1.0) as f32,
+        )),
+        "mod" => graph.add_dsp(DspTask::new_modulator(
+            id,
+            node.require_number("frequency")? as f32,
+            node.get_number("volume")?.unwrap_or(1.0) as f32,
+        )),
+        "mix" => {
+            let nb_inputs = node.require_number("inputs")? as usize;
+            let nb_outputs = node.require_number("outputs")? as usize;
+
+            if nb_inputs == 0
+                || nb_outputs == 0
+                || (nb_inputs % nb_outputs != 0 && nb_outputs % nb_inputs != 0)
+            {
+                return Err(format!("{}: bad number of inputs and outputs", node.id));
+            }
+
+            graph.add_dsp(DspTask::new_io_adaptor(id, nb_inputs, nb_outputs))
+        }
+        "sink" => graph.add_dsp(DspTask::new_sink(
+            id,
+            node.get_number("channels")?.unwrap_or(1.0) as usize,
+        )),
+        "input" => graph.add_dsp(DspTask::new_input(
+            id,
+            node.get_number("channels")?.unwrap_or(1.0) as usize,
+        )),
+        "constant" => graph.add_task(Task::Constant(node.require_number("wcet")?)),
+        "random" => graph.add_task(Task::Random(
+            node.require_number("min")?,
+            node.require_number("max")?,
+        )),
+        "pd" => graph.add_task(Task::Puredata {
+            object_name: node.get_text("object")?.unwrap_or_default(),
+            xpos: node.get_number("x")?.unwrap_or(-1.0) as i64,
+            ypos: node.get_number("y")?.unwrap_or(-1.0) as i64,
+            args: node
+                .get_text("args")?
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(|arg| arg.to_string())
+                .collect(),
+        }),
+        "ag" => {
+            let mut more = HashMap::new();
+
+            for (key, value) in &node.attributes {
+                if let (Some(key), Value::Text(value)) = (key.strip_prefix("more."), value) {
+                    more.insert(key.to_string(), value.clone());
+                }
+            }
+
+            graph.add_task(Task::Audiograph {
+                wcet: node.get_number("wcet")?,
+                id,
+                nb_inlets: node.get_number("inputs")?.unwrap_or(0.0) as u32,
+                nb_outlets: node.get_number("outputs")?.unwrap_or(0.0) as u32,
+                class_name: node.get_text("class")?.unwrap_or_default(),
+                text: node.get_text("text")?,
+                more,
+                volume: node.get_number("volume")?.unwrap_or(0.0) as f32,
+            })
+        }
+        _ => return Err(format!("{}: unknown kind {}", node.id, kind)),
+    };
+
+    // The WCET of a DSP or of a random task measured beforehand
+    if kind != "constant" && kind != "ag" {
+        if let Some(wcet) = node.get_number("wcet")? {
+            graph.set_wcet(node_index, wcet);
+        }
+    }
+
+    // The JACK ports of a sink or an input, and the file of an input
+    if let Some(connections) = node.get_text("connect")? {
+        graph.set_connections(
+            node_index,
+            connections
+                .split(',')
+                .map(|port| port.to_string())
+                .collect(),
+        );
+    }
+
+    if let Some(path) = node.get_text("file")? {
+        graph.set_input_file(node_index, &path);
+    }
+
+    Ok(node_index)
+}
+
+/// Builds a graph from the description of its nodes and edges.
+///
+/// # Arguments
+/// * `nodes` - The nodes of the graph, in the order of their indices
+/// * `edges` - The edges of the graph
+pub fn build_graph(
+    nodes: &[NodeDescription],
+    edges: &[EdgeDescription],
+) -> Result<TaskGraph, String> {
+    let mut graph = TaskGraph::new(nodes.len(), edges.len());
+    let mut node_indices: HashMap<&str, usize> = HashMap::new();
+
+    for node in nodes {
+        if node_indices.contains_key(node.id.as_str()) {
+            return Err(format!("{}: duplicated node", node.id));
+        }
+
+        let node_index = build_node(&mut graph, node)?;
+        node_indices.insert(&node.id, node_index);
+    }
+
+    for edge in edges {
+        let get_index = |id: &str| {
+            node_indices
+                .get(id)
+                .cloned()
+                .ok_or_else(|| format!("{}: no such node", id))
+        };
+
+        let src_node = get_index(&edge.source)?;
+        let dst_node = get_index(&edge.target)?;
+
+        graph.add_edge(src_node, dst_node);
+
+        if let (Some(src_port), Some(dst_port)) = (edge.source_port, edge.target_port) {
+            graph.set_ports(src_node, dst_node, src_port, dst_port);
+        }
+
+        if let Some(cost) = edge.cost {
+            graph.set_communication_cost(src_node, dst_node, cost);
+        }
+    }
+
+    Ok(graph)
+}
@@ -0,0 +1,210 @@
+//! Parse a task graph in JSON, as written by the Python analysis and
+//! visualisation tools:
+//!
+//! ```text
+//! {
+//!   "nodes": [
+//!     {"id": "osc1", "kind": "osc", "frequency": 440, "volume": 1},
+//!     {"id": "sink", "kind": "sink", "channels": 1, "wcet": 0.00001}
+//!   ],
+//!   "edges": [
+//!     {"source": "osc1", "target": "sink", "source_port": 0, "target_port": 0, "cost": 0.5}
+//!   ]
+//! }
+//! ```
+
+use pest::error::{Error as ParseError, ErrorVariant};
+use pest::iterators::{Pair, Pairs};
+use pest::Parser;
+
+use parser::interchange::{build_graph, EdgeDescription, NodeDescription, Value};
+use parser::read_file;
+use task_graph::graph;
+
+#[derive(Parser)]
+#[grammar = "parser/json/json.pest"]
+pub struct JsonParser;
+
+// Returns an error located on a token of the file
+fn custom_error(pair: &Pair<Rule>, message: &str) -> ParseError<Rule> {
+    ParseError::new_from_span(
+        ErrorVariant::CustomError {
+            message: message.to_string(),
+        },
+        pair.as_span(),
+    )
+}
+
+// Returns the content of a string, without its escape sequences
+fn unescape(string: Pair<Rule>) -> String {
+    let mut content = String::new();
+    let mut chars = string.into_inner().next().unwrap().as_str().chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            content.push(c);
+            continue;
+        }
+
+        match chars.next().unwrap() {
+            'b' => content.push('\u{8}'),
+            'f' => content.push('\u{c}'),
+            'n' => content.push('\n'),
+            'r' => content.push('\r'),
+            't' => content.push('\t'),
+            'u' => {
+                let code: String = chars.by_ref().take(4).collect();
+                let code = u32::from_str_radix(&code, 16).unwrap();
+                content.push(std::char::from_u32(code).unwrap_or('\u{fffd}'));
+            }
+            c => content.push(c),
+        }
+    }
+
+    content
+}
+
+// Returns the members of an object, as their key and value
+fn get_members(object: Pair<Rule>) -> Result<Vec<(String, Pair<Rule>)>, ParseError<Rule>> {
+    if object.as_rule() != Rule::object {
+        return Err(custom_error(&object, "expected an object"));
+    }
+
+    Ok(object
+        .into_inner()
+        .map(|member| {
+            let mut fields = member.into_inner();
+            let key = unescape(fields.next().unwrap());
+
+            (key, fields.next().unwrap())
+        })
+        .collect())
+}
+
+// Returns the values of an array
+fn get_values(array: Pair<Rule>) -> Result<Pairs<Rule>, ParseError<Rule>> {
+    if array.as_rule() != Rule::array {
+        return Err(custom_error(&array, "expected an array"));
+    }
+
+    Ok(array.into_inner())
+}
+
+// Returns the value of a number
+fn get_number(value: &Pair<Rule>) -> Result<f64, ParseError<Rule>> {
+    match value.as_rule() {
+        Rule::number => Ok(value.as_str().parse().unwrap()),
+        _ => Err(custom_error(value, "expected a number")),
+    }
+}
+
+// Returns the value of a port
+fn get_port(value: &Pair<Rule>) -> Result<u32, ParseError<Rule>> {
+    value
+        .as_str()
+        .parse()
+        .map_err(|_| custom_error(value, "expected a port"))
+}
+
+// Returns the id of a node, which is a string or a number
+fn get_id(value: Pair<Rule>) -> Result<String, ParseError<Rule>> {
+    match value.as_rule() {
+        Rule::string => Ok(unescape(value)),
+        Rule::number => Ok(value.as_str().to_string()),
+        _ => Err(custom_error(&value, "expected a node id")),
+    }
+}
+
+// Parses a node and its attributes
+fn parse_node(object: Pair<Rule>) -> Result<NodeDescription, ParseError<Rule>> {
+    let location = object.clone();
+    let mut id = None;
+    let mut attributes = Vec::new();
+
+    for (key, value) in get_members(object)? {
+        if key == "id" {
+            id = Some(get_id(value)?);
+            continue;
+        }
+
+        let value = match value.as_rule() {
+            Rule::number => Value::Number(get_number(&value)?),
+            Rule::string => Value::Text(unescape(value)),
+            _ => return Err(custom_error(&value, "expected a number or a string")),
+        };
+
+        attributes.push((key, value));
+    }
+
+    let id = id.ok_or_else(|| custom_error(&location, "missing node id"))?;
+
+    Ok(NodeDescription { id, attributes })
+}
+
+// Parses an edge
+fn parse_edge(object: Pair<Rule>) -> Result<EdgeDescription, ParseError<Rule>> {
+    let location = object.clone();
+    let mut source = None;
+    let mut target = None;
+    let mut source_port = None;
+    let mut target_port = None;
+    let mut cost = None;
+
+    for (key, value) in get_members(object)? {
+        match key.as_str() {
+            "source" => source = Some(get_id(value)?),
+            "target" => target = Some(get_id(value)?),
+            "source_port" => source_port = Some(get_port(&value)?),
+            "target_port" => target_port = Some(get_port(&value)?),
+            "cost" => cost = Some(get_number(&value)?),
+            _ => {}
+        }
+    }
+
+    Ok(EdgeDescription {
+        source: source.ok_or_else(|| custom_error(&location, "missing edge source"))?,
+        target: target.ok_or_else(|| custom_error(&location, "missing edge target"))?,
+        source_port,
+        target_port,
+        cost,
+    })
+}
+
+/// Parses a task graph in JSON. The nodes get their indices in the order of
+/// the `nodes` array and the other members of the document are ignored.
+///
+/// # Arguments
+/// * `json` - The content of the JSON file
+pub fn parse_json(json: &str) -> Result<graph::TaskGraph, ParseError<Rule>> {
+    let file = JsonParser::parse(Rule::file, json)?.next().unwrap();
+
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+
+    for (key, value) in get_members(file)? {
+        match key.as_str() {
+            "nodes" => {
+                for node in get_values(value)? {
+                    nodes.push(parse_node(node)?);
+                }
+            }
+            "edges" => {
+                for edge in get_values(value)? {
+                    edges.push(parse_edge(edge)?);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    build_graph(&nodes, &edges).map_err(|message| {
+        ParseError::new_from_pos(
+            ErrorVariant::CustomError { message },
+            pest::Position::from_start(json),
+        )
+    })
+}
+
+pub fn parse(filename: &str) -> Result<graph::TaskGraph, ParseError<Rule>> {
+    parse_json(&read_file(filename)?)
+}
@@ -0,0 +1,102 @@
+//! Write a task graph in JSON, with a node or an edge on each line
+
+use std::fs;
+use std::io;
+
+use parser::interchange::{describe_graph, Value};
+use task_graph::graph::TaskGraph;
+
+/// Returns a text as a JSON string, between quotes.
+///
+/// # Arguments
+/// * `text` - The text to quote
+pub fn quote(text: &str) -> String {
+    let mut string = String::with_capacity(text.len() + 2);
+
+    string.push('"');
+
+    for c in text.chars() {
+        match c {
+            '"' => string.push_str("\\\""),
+            '\\' => string.push_str("\\\\"),
+            '\n' => string.push_str("\\n"),
+            '\r' => string.push_str("\\r"),
+            '\t' => string.push_str("\\t"),
+            c if (c as u32) < 0x20 => string.push_str(&format!("\\u{:04x}", c as u32)),
+            c => string.push(c),
+        }
+    }
+
+    string.push('"');
+
+    string
+}
+
+// Returns the JSON value of an attribute
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::Number(number) => format!("{}", number),
+        Value::Text(text) => quote(text),
+    }
+}
+
+/// Returns a task graph in JSON, with the kind, the parameters and the known
+/// WCET of each node, and the ports and communication cost of each edge.
+///
+/// # Arguments
+/// * `graph` - The graph to write
+pub fn write_json(graph: &TaskGraph) -> String {
+    let (nodes, edges) = describe_graph(graph);
+
+    let nodes: Vec<String> = nodes
+        .iter()
+        .map(|node| {
+            let mut members = vec![format!("\"id\": {}", quote(&node.id))];
+
+            for (key, value) in &node.attributes {
+                members.push(format!("{}: {}", quote(key), format_value(value)));
+            }
+
+            format!("    {{{}}}", members.join(", "))
+        })
+        .collect();
+
+    let edges: Vec<String> = edges
+        .iter()
+        .map(|edge| {
+            let mut members = vec![
+                format!("\"source\": {}", quote(&edge.source)),
+                format!("\"target\": {}", quote(&edge.target)),
+            ];
+
+            if let Some(source_port) = edge.source_port {
+                members.push(format!("\"source_port\": {}", source_port));
+            }
+
+            if let Some(target_port) = edge.target_port {
+                members.push(format!("\"target_port\": {}", target_port));
+            }
+
+            if let Some(cost) = edge.cost {
+                members.push(format!("\"cost\": {}", cost));
+            }
+
+            format!("    {{{}}}", members.join(", "))
+        })
+        .collect();
+
+    format!(
+        "{{\n  \"nodes\": [\n{}\n  ],\n  \"edges\": [\n{}\n  ]\n}}\n",
+        nodes.join(",\n"),
+        edges.join(",\n")
+    )
+}
+
+/// Writes a task graph in a JSON file.
+///
+/// # Arguments
+/// * `graph` - The graph to write
+/// * `path` - The path of the file
+pub fn save_json(graph: &TaskGraph, path: &str) -> io::Result<()> {
+    fs::write(path, write_json(graph))
+}
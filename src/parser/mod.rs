@@ -1,21 +1,61 @@
 pub mod audiograph;
+pub mod config;
+pub mod graphml;
+pub mod interchange;
+pub mod json;
 pub mod puredata;
+pub mod stg;
+
+use std::fs;
+
+use pest::error::{Error as ParseError, ErrorVariant};
+use pest::{Position, RuleType};
 
 use task_graph::graph;
 
-pub fn parse(filename: &str) -> Option<graph::TaskGraph> {
-    if filename.ends_with(".pd") {
-        Some(self::puredata::parser::parse(filename).unwrap())
+/// Parses a task graph, its format depending on the extension of the file:
+/// `.pd`, `.ag`, `.stg`, `.json` or `.graphml`.
+///
+/// # Arguments
+/// * `filename` - The path of the file
+pub fn parse(filename: &str) -> Result<graph::TaskGraph, String> {
+    // The parsers have different grammars, hence different errors
+    let graph = if filename.ends_with(".pd") {
+        self::puredata::parser::parse(filename).map_err(|e| e.to_string())
     } else if filename.ends_with(".ag") {
-        Some(self::audiograph::parser::parse(filename).unwrap())
+        self::audiograph::parser::parse(filename).map_err(|e| e.to_string())
+    } else if filename.ends_with(".stg") {
+        self::stg::parser::parse(filename).map_err(|e| e.to_string())
+    } else if filename.ends_with(".json") {
+        self::json::parser::parse(filename).map_err(|e| e.to_string())
+    } else if filename.ends_with(".graphml") {
+        self::graphml::parser::parse(filename).map_err(|e| e.to_string())
     } else {
-        None
-    }
+        return Err(format!("{}: unknown graph format", filename));
+    };
+
+    graph.map_err(|e| format!("{}: {}", filename, e))
+}
+
+// Returns the content of a file, or an error of the parser reading it
+pub(crate) fn read_file<R: RuleType>(filename: &str) -> Result<String, ParseError<R>> {
+    fs::read_to_string(filename).map_err(|e| {
+        ParseError::new_from_pos(
+            ErrorVariant::CustomError {
+                message: format!("impossible to read the file: {}", e),
+            },
+            Position::from_start(""),
+        )
+    })
 }
 
 #[cfg(test)]
 mod parser_test {
     use super::*;
+    use dsp::Parameter;
+    use midi::{MidiMapping, MidiSource};
+    use task_graph::graph::TaskGraph;
+    use task_graph::task::Task;
 
     #[test]
     fn parse_audiograph() {
@@ -27,6 +67,227 @@ mod parser_test {
         assert_eq!(graph_2.get_topological_order().len(), 16);
     }
 
+    #[test]
+    fn parse_audiograph_communication_costs() {
+        let graph = self::audiograph::parser::parse_audiograph(
+            "a = { kind: \"osc\", freq: 440, };
+             b = { kind: \"mod\", freq: 110, };
+             c = { kind: \"sink\", };
+             a.0 -> b.0 [cost = 0.5] -> c.0;",
+        )
+        .unwrap();
+
+        assert_eq!(graph.get_communication_cost(0, 1), Some(0.5));
+        assert_eq!(graph.get_communication_cost(1, 2), None);
+    }
+
+    #[test]
+    fn parse_audiograph_midi() {
+        let graph = self::audiograph::parser::parse_dsp_audiograph(
+            "osc = { kind: \"osc\", freq: 440, volume: 1, };
+             out = { kind: \"sink\", };
+             osc.0 -> out.0;
+             midi note -> osc.freq;
+             midi cc 7 channel 2 -> osc.volume;
+             midi file \"tmp/melody.mid\";",
+        )
+        .unwrap();
+
+        assert_eq!(
+            graph.get_midi_mappings(),
+            &[
+                MidiMapping {
+                    source: MidiSource::Note,
+                    channel: None,
+                    node_index: 0,
+                    parameter: Parameter::Frequency,
+                },
+                MidiMapping {
+                    source: MidiSource::ControlChange(7),
+                    channel: Some(1),
+                    node_index: 0,
+                    parameter: Parameter::Volume,
+                },
+            ]
+        );
+        assert_eq!(graph.get_midi_file(), Some("tmp/melody.mid"));
+
+        let osc = "osc = { kind: \"osc\", freq: 440, };\n";
+        let parse = |midi: &str| {
+            self::audiograph::parser::parse_dsp_audiograph(&format!("{}{}", osc, midi))
+        };
+
+        assert!(parse("midi note -> lfo.freq;").is_err());
+        assert!(parse("midi cc 7 channel 17 -> osc.volume;").is_err());
+        assert!(parse("midi cc 128 -> osc.volume;").is_err());
+        assert!(parse("midi note -> osc.cutoff;").is_err());
+    }
+
+    #[test]
+    fn parse_stg() {
+        let mut graph = parse("Samples/STG/fork-join-8.stg").unwrap();
+
+        assert_eq!(graph.get_nb_node(), 10);
+        assert_eq!(graph.get_nb_edge(), 12);
+        assert_eq!(graph.get_wcet(4), Some(5.0));
+        assert_eq!(graph.get_predecessors(8), Some(vec![5, 7]));
+        assert_eq!(graph.get_entry_nodes(), vec![0]);
+        assert_eq!(graph.get_exit_nodes(), vec![9]);
+
+        assert!(self::stg::parser::parse_stg("2\n0 0 0\n1 3 1 0\n2 0 1 1\n").is_err());
+        assert!(self::stg::parser::parse_stg("1\n0 0 0\n2 3 1 0\n2 0 1 1\n").is_err());
+        assert!(self::stg::parser::parse_stg("1\n0 0 0\n1 3 2 0\n2 0 1 1\n").is_err());
+    }
+
+    #[test]
+    fn write_stg() {
+        let mut graph = parse("Samples/STG/fork-join-8.stg").unwrap();
+        let stg = self::stg::writer::write_stg(&mut graph);
+
+        assert_eq!(
+            stg,
+            std::fs::read_to_string("Samples/STG/fork-join-8.stg")
+                .unwrap()
+                .split('#')
+                .next()
+                .unwrap()
+        );
+
+        // Dummy tasks are added around a graph with several entry nodes
+        let mut graph = TaskGraph::new(4, 3);
+
+        for &wcet in &[0.0, 3.0, 0.0, 2.5] {
+            graph.add_task(Task::Constant(wcet));
+        }
+
+        graph.add_edge(0, 1);
+        graph.add_edge(1, 2);
+        graph.add_edge(3, 1);
+
+        assert_eq!(
+            self::stg::writer::write_stg(&mut graph),
+            "4\n0 0 0\n1 0 1 0\n2 2.5 1 0\n3 3 2 1 2\n4 0 1 3\n5 0 1 4\n"
+        );
+    }
+
+    // A graph with DSP nodes, ports, communication costs and tasks
+    fn get_interchange_graph() -> TaskGraph {
+        let mut graph = self::audiograph::parser::parse_dsp_audiograph(
+            "a = { kind: \"osc\", freq: 440, volume: 1.0, };
+             b = { kind: \"mod\", freq: 110, volume: 0.5, };
+             m = { kind: \"mix\", in: 2, out: 1, };
+             c = { kind: \"sink\", connect: \"system:playback_1\", };
+             i = { kind: \"input\", file: \"guitar.wav\", };
+             a.0 -> b.0 [cost = 0.5] -> m.1 -> c.0;
+             i.0 -> m.0;",
+        )
+        .unwrap();
+
+        graph.set_wcet(0, 0.00002);
+
+        graph
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let graph = get_interchange_graph();
+        let json = self::json::writer::write_json(&graph);
+        let read = self::json::parser::parse_json(&json).unwrap();
+
+        assert_eq!(self::json::writer::write_json(&read), json);
+        assert_eq!(read.get_nb_node(), 5);
+        assert_eq!(read.get_node_name(2), "m");
+        assert_eq!(read.get_ports(1, 2), Some((0, 1)));
+        assert_eq!(read.get_communication_cost(0, 1), Some(0.5));
+        assert_eq!(read.get_communication_cost(1, 2), None);
+        assert_eq!(read.get_known_wcet(0), Some(0.00002));
+        assert_eq!(read.get_known_wcet(1), None);
+        assert_eq!(read.get_connections(3), &["system:playback_1".to_string()]);
+        assert_eq!(read.get_input_file(4), Some("guitar.wav"));
+
+        let graph = self::json::parser::parse_json(
+            "{\"nodes\": [{\"id\": 0, \"kind\": \"constant\", \"wcet\": 2}, \
+             {\"id\": \"r\\u0031\", \"kind\": \"random\", \"min\": 1, \"max\": 3}], \
+             \"edges\": [{\"source\": 0, \"target\": \"r1\"}], \"version\": [1, true]}",
+        )
+        .unwrap();
+
+        assert_eq!(graph.get_task(0), Some(Task::Constant(2.0)));
+        assert_eq!(graph.get_task(1), Some(Task::Random(1.0, 3.0)));
+        assert_eq!(graph.get_predecessors(1), Some(vec![0]));
+
+        assert!(self::json::parser::parse_json("{\"nodes\": [{\"kind\": \"constant\"}]}").is_err());
+        assert!(
+            self::json::parser::parse_json("{\"nodes\": [{\"id\": 0, \"kind\": \"dsp\"}]}")
+                .is_err()
+        );
+        assert!(
+            self::json::parser::parse_json("{\"edges\": [{\"source\": 0, \"target\": 1}]}")
+                .is_err()
+        );
+        assert!(self::json::parser::parse_json("{\"nodes\": [}").is_err());
+    }
+
+    #[test]
+    fn graphml_round_trip() {
+        let graph = get_interchange_graph();
+        let graphml = self::graphml::writer::write_graphml(&graph);
+        let read = self::graphml::parser::parse_graphml(&graphml).unwrap();
+
+        assert_eq!(self::graphml::writer::write_graphml(&read), graphml);
+        assert_eq!(
+            self::json::writer::write_json(&read),
+            self::json::writer::write_json(&graph)
+        );
+
+        let read = self::graphml::parser::parse_graphml(
+            "<?xml version=\"1.0\"?>
+             <graphml>
+               <!-- A graph written by hand -->
+               <key id=\"d0\" for=\"node\" attr.name=\"kind\" attr.type=\"string\"/>
+               <key id=\"d1\" for=\"node\" attr.name=\"object\" attr.type=\"string\"/>
+               <key id=\"d2\" for=\"node\" attr.name=\"x\" attr.type=\"int\"/>
+               <graph edgedefault=\"directed\">
+                 <node id=\"n0\"><data key=\"d0\">pd</data><data key=\"d1\">osc&#126;</data></node>
+                 <node id=\"n1\"><data key=\"d0\">pd</data><data key=\"d2\">12</data></node>
+                 <edge source=\"n0\" target=\"n1\"/>
+               </graph>
+             </graphml>",
+        )
+        .unwrap();
+
+        match read.get_task(0) {
+            Some(Task::Puredata { object_name, .. }) => assert_eq!(object_name, "osc~"),
+            _ => panic!("the node is not a Puredata task"),
+        }
+
+        assert_eq!(read.get_successors(0), Some(vec![1]));
+
+        assert!(
+            self::graphml::parser::parse_graphml("<graphml><graph></graphm></graphml>").is_err()
+        );
+        assert!(self::graphml::parser::parse_graphml(
+            "<graphml><graph><node id=\"a\"><data key=\"k\">1</data></node></graph></graphml>"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse("Samples/STG/missing.stg").is_err());
+        assert!(parse("Samples/STG/missing.json").is_err());
+        assert!(parse("Cargo.toml").is_err());
+
+        let path = std::env::temp_dir().join("audio_graph_parser_test.graphml");
+
+        std::fs::write(&path, "<graphml><graph></graphm></graphml>").unwrap();
+
+        let result = parse(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(result.is_err());
+    }
+
     #[test]
     fn parse_puredata() {
         let graph_1 = parse("Samples/PD/aleatoire.pd").unwrap();
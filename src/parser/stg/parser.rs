@@ -0,0 +1,104 @@
+//! Parse the Standard Task Graph format, used by the benchmark suites of
+//! scheduling algorithms
+
+use pest::error::{Error as ParseError, ErrorVariant};
+use pest::iterators::Pair;
+use pest::Parser;
+
+use parser::read_file;
+use task_graph::graph;
+use task_graph::task::Task;
+
+#[derive(Parser)]
+#[grammar = "parser/stg/stg.pest"]
+pub struct StgParser;
+
+// Returns an error located on a token of the file
+fn custom_error(pair: &Pair<Rule>, message: String) -> ParseError<Rule> {
+    ParseError::new_from_span(ErrorVariant::CustomError { message }, pair.as_span())
+}
+
+/// Parses a task graph in the STG format. The task `i` of the file is the node
+/// `i` of the graph, with a constant WCET equal to its processing time.
+///
+/// # Arguments
+/// * `stg` - The content of the STG file
+pub fn parse_stg(stg: &str) -> Result<graph::TaskGraph, ParseError<Rule>> {
+    let file = StgParser::parse(Rule::file, stg)?.next().unwrap();
+
+    let mut nb_tasks = 0;
+    let mut tasks: Vec<(f64, Vec<usize>)> = Vec::new();
+
+    for pair in file.into_inner() {
+        match pair.as_rule() {
+            Rule::nb_tasks => nb_tasks = pair.as_str().parse::<usize>().unwrap(),
+            Rule::task => {
+                let mut fields = pair.clone().into_inner();
+
+                let index: usize = fields.next().unwrap().as_str().parse().unwrap();
+                let cost: f64 = fields.next().unwrap().as_str().parse().unwrap();
+                let nb_predecessors: usize = fields.next().unwrap().as_str().parse().unwrap();
+                let predecessors: Vec<usize> = fields
+                    .map(|predecessor| predecessor.as_str().parse().unwrap())
+                    .collect();
+
+                if index != tasks.len() {
+                    return Err(custom_error(
+                        &pair,
+                        format!("expected task {}, found task {}", tasks.len(), index),
+                    ));
+                }
+
+                if predecessors.len() != nb_predecessors {
+                    return Err(custom_error(
+                        &pair,
+                        format!(
+                            "expected {} predecessors, found {}",
+                            nb_predecessors,
+                            predecessors.len()
+                        ),
+                    ));
+                }
+
+                if let Some(&predecessor) = predecessors.iter().find(|&&pred| pred >= index) {
+                    return Err(custom_error(
+                        &pair,
+                        format!("the predecessor {} comes after the task", predecessor),
+                    ));
+                }
+
+                tasks.push((cost, predecessors));
+            }
+            _ => {}
+        }
+    }
+
+    // The dummy entry and exit tasks are not counted
+    if tasks.len() != nb_tasks + 2 {
+        return Err(ParseError::new_from_pos(
+            ErrorVariant::CustomError {
+                message: format!("expected {} tasks, found {}", nb_tasks + 2, tasks.len()),
+            },
+            pest::Position::from_start(stg),
+        ));
+    }
+
+    let nb_edges = tasks.iter().map(|(_, preds)| preds.len()).sum();
+    let mut taskgraph = graph::TaskGraph::new(tasks.len(), nb_edges);
+
+    for (cost, _) in tasks.iter() {
+        taskgraph.add_task(Task::Constant(*cost));
+    }
+
+    for (index, (_, predecessors)) in tasks.iter().enumerate() {
+        for &predecessor in predecessors {
+            taskgraph.add_edge(predecessor, index);
+        }
+    }
+
+    Ok(taskgraph)
+}
+
+pub fn parse(filename: &str) -> Result<graph::TaskGraph, ParseError<Rule>> {
+    parse_stg(&read_file(filename)?)
+}
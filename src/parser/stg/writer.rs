@@ -0,0 +1,134 @@
+//! Write a task graph in the Standard Task Graph format
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs;
+use std::io;
+
+use task_graph::graph::TaskGraph;
+
+// Formats a processing time, as an integer when it is one like in the
+// benchmark suites
+fn format_cost(cost: f64) -> String {
+    if cost.fract() == 0.0 {
+        format!("{}", cost as u64)
+    } else {
+        format!("{}", cost)
+    }
+}
+
+// Returns the nodes in a topological order, keeping the order of the indices
+// when possible so that a graph read from a STG file is written back as is
+fn get_task_order(graph: &TaskGraph) -> Vec<usize> {
+    let nb_nodes = graph.get_nb_node();
+    let mut nb_waiting: Vec<usize> = (0..nb_nodes)
+        .map(|node_index| graph.get_predecessors(node_index).unwrap().len())
+        .collect();
+    let mut ready: BinaryHeap<Reverse<usize>> = (0..nb_nodes)
+        .filter(|&node_index| nb_waiting[node_index] == 0)
+        .map(Reverse)
+        .collect();
+    let mut order = Vec::with_capacity(nb_nodes);
+
+    while let Some(Reverse(node_index)) = ready.pop() {
+        order.push(node_index);
+
+        for successor in graph.get_successors(node_index).unwrap() {
+            nb_waiting[successor] -= 1;
+
+            if nb_waiting[successor] == 0 {
+                ready.push(Reverse(successor));
+            }
+        }
+    }
+
+    order
+}
+
+/// Returns a task graph in the STG format, the WCETs of the nodes being their
+/// processing times and the tasks being numbered in the topological order.
+///
+/// A graph which does not start and end with a single node of WCET 0 gets
+/// dummy entry and exit tasks, as required by the format.
+///
+/// # Arguments
+/// * `graph` - The graph to write
+pub fn write_stg(graph: &mut TaskGraph) -> String {
+    let nb_nodes = graph.get_nb_node();
+    let entry_nodes = graph.get_entry_nodes();
+    let exit_nodes = graph.get_exit_nodes();
+
+    let has_dummies = nb_nodes >= 2
+        && entry_nodes.len() == 1
+        && exit_nodes.len() == 1
+        && graph.get_wcet(entry_nodes[0]) == Some(0.0)
+        && graph.get_wcet(exit_nodes[0]) == Some(0.0);
+
+    // The index of the task of each node
+    let offset = if has_dummies { 0 } else { 1 };
+    let top_ord = get_task_order(graph);
+    let mut task_indices = vec![0; nb_nodes];
+
+    for (position, &node_index) in top_ord.iter().enumerate() {
+        task_indices[node_index] = position + offset;
+    }
+
+    // The processing time and the predecessors of each task
+    let mut tasks: Vec<(f64, Vec<usize>)> = Vec::with_capacity(nb_nodes + 2);
+
+    if !has_dummies {
+        tasks.push((0.0, Vec::new()));
+    }
+
+    for &node_index in &top_ord {
+        let mut predecessors: Vec<usize> = graph
+            .get_predecessors(node_index)
+            .unwrap_or_default()
+            .iter()
+            .map(|&predecessor| task_indices[predecessor])
+            .collect();
+        predecessors.sort();
+
+        if predecessors.is_empty() && !has_dummies {
+            predecessors.push(0);
+        }
+
+        tasks.push((graph.get_wcet(node_index).unwrap_or(0.0), predecessors));
+    }
+
+    if !has_dummies {
+        let mut exit_predecessors: Vec<usize> =
+            exit_nodes.iter().map(|&exit| task_indices[exit]).collect();
+        exit_predecessors.sort();
+
+        tasks.push((0.0, exit_predecessors));
+    }
+
+    let mut stg = format!("{}\n", tasks.len() - 2);
+
+    for (index, (cost, predecessors)) in tasks.iter().enumerate() {
+        stg.push_str(&format!(
+            "{} {} {}",
+            index,
+            format_cost(*cost),
+            predecessors.len()
+        ));
+
+        for predecessor in predecessors {
+            stg.push_str(&format!(" {}", predecessor));
+        }
+
+        stg.push('\n');
+    }
+
+    stg
+}
+
+/// Writes a task graph in a file in the STG format.
+///
+/// # Arguments
+/// * `graph` - The graph to write
+/// * `path` - The path of the file
+pub fn save_stg(graph: &mut TaskGraph, path: &str) -> io::Result<()> {
+    fs::write(path, write_stg(graph))
+}
@@ -3,27 +3,95 @@ use std::collections::HashMap;
 
 use task_graph::{graph::TaskGraph, state::TaskState};
 
+use super::platform::Platform;
 use super::processor::Processor;
 use super::schedule::Schedule;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum SchedulingAlgorithm {
     Random,
     HLFET,
     ETF,
+    DSC,
+    LC,
+    HEFT,
     // CPFD, This one cannot be executed without further treatment
 }
 
+impl SchedulingAlgorithm {
+    /// Returns the name of the algorithm on the command line.
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            SchedulingAlgorithm::Random => "rand",
+            SchedulingAlgorithm::HLFET => "hlfet",
+            SchedulingAlgorithm::ETF => "etf",
+            SchedulingAlgorithm::DSC => "dsc",
+            SchedulingAlgorithm::LC => "lc",
+            SchedulingAlgorithm::HEFT => "heft",
+        }
+    }
+
+    /// Returns the algorithm with a name on the command line, if there is one.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the algorithm
+    pub fn from_name(name: &str) -> Option<SchedulingAlgorithm> {
+        match name {
+            "rand" => Some(SchedulingAlgorithm::Random),
+            "hlfet" => Some(SchedulingAlgorithm::HLFET),
+            "etf" => Some(SchedulingAlgorithm::ETF),
+            "dsc" => Some(SchedulingAlgorithm::DSC),
+            "lc" => Some(SchedulingAlgorithm::LC),
+            "heft" => Some(SchedulingAlgorithm::HEFT),
+            _ => None,
+        }
+    }
+}
+
 pub fn schedule(
     graph: &mut TaskGraph,
     nb_processors: usize,
     algo: SchedulingAlgorithm,
 ) -> Schedule {
-    match algo {
-        SchedulingAlgorithm::Random => random(graph, nb_processors),
-        SchedulingAlgorithm::HLFET => hlfet(graph, nb_processors),
-        SchedulingAlgorithm::ETF => etf(graph, nb_processors),
+    schedule_on(graph, &Platform::homogeneous(nb_processors), algo)
+}
+
+/// Schedules the `TaskGraph` on the processors of a `Platform`, pinning
+/// the `Processor`s of the `Schedule` on the same logical CPUs.
+///
+/// Every algorithm pays the communication costs between the processors of
+/// the `Platform`, but only ETF and HEFT choose the processors from their
+/// speeds.
+///
+/// # Arguments
+/// * `graph` - The `TaskGraph` to schedule
+/// * `platform` - The processors available
+/// * `algo` - The scheduling algorithm
+pub fn schedule_on(
+    graph: &mut TaskGraph,
+    platform: &Platform,
+    algo: SchedulingAlgorithm,
+) -> Schedule {
+    let mut out_schedule = match algo {
+        SchedulingAlgorithm::Random => random_on(graph, platform),
+        SchedulingAlgorithm::HLFET => hlfet_on(graph, platform),
+        SchedulingAlgorithm::ETF => etf_on(graph, platform),
+        SchedulingAlgorithm::DSC => dsc_on(graph, platform),
+        SchedulingAlgorithm::LC => linear_clustering_on(graph, platform),
+        SchedulingAlgorithm::HEFT => heft(graph, platform),
+    };
+
+    for (processor, spec) in out_schedule
+        .processors
+        .iter_mut()
+        .zip(platform.processors.iter())
+    {
+        processor.core_id = spec.core_id;
     }
+
+    out_schedule.algorithm = Some(algo.get_name().to_string());
+
+    out_schedule
 }
 
 // Returns the cpn dominant sequence
@@ -82,6 +150,79 @@ fn get_ready_time(node: usize, graph: &TaskGraph, sched: &Schedule) -> f64 {
         .get_completion_time()
 }
 
+// Returns the time when the data of all the predecessors of the node
+// will be available on the processor: the communication cost between the
+// processors of an edge is paid when the predecessor is on another processor,
+// and the copy of a predecessor whose data arrives first is used
+fn get_ready_time_on(
+    node: usize,
+    proc: usize,
+    graph: &TaskGraph,
+    sched: &Schedule,
+    platform: &Platform,
+) -> f64 {
+    let mut ready_time: f64 = 0.0;
+
+    for pred in graph.get_predecessors(node).unwrap() {
+        let mut arrival_time: Option<f64> = None;
+
+        for (pred_proc, processor) in sched.processors.iter().enumerate() {
+            for ts in processor
+                .time_slots
+                .iter()
+                .filter(|ts| ts.get_node() == pred)
+            {
+                let time = ts.get_completion_time()
+                    + platform.get_communication_cost(graph, pred, node, pred_proc, proc);
+
+                arrival_time = Some(arrival_time.map_or(time, |arrival| arrival.min(time)));
+            }
+        }
+
+        ready_time = ready_time.max(arrival_time.unwrap_or(0.0));
+    }
+
+    ready_time
+}
+
+// Returns the times the data of a node whose predecessors are all scheduled is
+// available on each processor
+fn get_ready_times(
+    node: usize,
+    graph: &TaskGraph,
+    sched: &Schedule,
+    platform: &Platform,
+) -> Vec<f64> {
+    (0..sched.processors.len())
+        .map(|proc| get_ready_time_on(node, proc, graph, sched, platform))
+        .collect()
+}
+
+// Returns the time when the messages of all the predecessors of the node
+// will have arrived, `default_cost` being used for the edges without
+// communication cost
+fn get_message_arrival_time(
+    node: usize,
+    graph: &TaskGraph,
+    sched: &Schedule,
+    default_cost: f64,
+) -> f64 {
+    let mut arrival_time: f64 = 0.0;
+
+    for pred in graph.get_predecessors(node).unwrap() {
+        if let Some(ts) = sched.get_time_slot(pred) {
+            arrival_time = arrival_time.max(
+                ts.get_completion_time()
+                    + graph
+                        .get_communication_cost(pred, node)
+                        .unwrap_or(default_cost),
+            );
+        }
+    }
+
+    arrival_time
+}
+
 // Sets the status of all reachable nodes from the entry
 // to TaskState::WaintingDependancies
 fn set_status_waiting(graph: &mut TaskGraph) {
@@ -116,7 +257,7 @@ fn optimal_proc(
     let predecessors = graph.get_predecessors(candidate).unwrap_or_default();
 
     if !predecessors.is_empty() && !duplicate_proc.contains_all_list_node(&predecessors) {
-        start_time = (get_ready_time(candidate, graph, schedule) + communication_cost)
+        start_time = get_message_arrival_time(candidate, graph, schedule, communication_cost)
             .max(control.get_completion_time());
 
         let mut last_pred = None;
@@ -127,7 +268,9 @@ fn optimal_proc(
                 .get_time_slot(not_in_proc_pred)
                 .unwrap()
                 .get_completion_time()
-                + communication_cost;
+                + graph
+                    .get_communication_cost(not_in_proc_pred, candidate)
+                    .unwrap_or(communication_cost);
 
             if last_pred.is_none() || last_pred_message < message_arrive {
                 last_pred = Some(not_in_proc_pred);
@@ -190,14 +333,20 @@ fn get_max_tie_misf(ready_list: &HashMap<usize, f64>, graph: &TaskGraph) -> usiz
 /// * `graph` - The `TaskGraph` to schedule
 /// * `nb_processors` - The number of processors available
 pub fn random(graph: &mut TaskGraph, nb_processors: usize) -> Schedule {
+    random_on(graph, &Platform::homogeneous(nb_processors))
+}
+
+/// Randomly schedules the `TaskGraph` on the processors of a `Platform`
+///
+/// # Arguments
+/// * `graph` - The `TaskGraph` to schedule
+/// * `platform` - The processors available
+pub fn random_on(graph: &mut TaskGraph, platform: &Platform) -> Schedule {
     println!("computing of the schedule");
 
     // Build the schedule
-    let mut out_schedule = Schedule::new();
-
-    for _ in 0..nb_processors {
-        out_schedule.add_processor();
-    }
+    let mut out_schedule = Schedule::with_platform(platform);
+    let nb_processors = platform.get_nb_processor();
 
     println!("reset the graph");
 
@@ -220,16 +369,21 @@ pub fn random(graph: &mut TaskGraph, nb_processors: usize) -> Schedule {
         let rand_proc_start_time = out_schedule.processors[rand_proc].get_completion_time();
 
         // The start time of the node will be the the maximum
-        // between the processor's start time and the time when all the node's
-        // parents will be completed (connexion time are overlooked).
-        let node_start_time =
-            rand_proc_start_time.max(get_ready_time(rand_node, &graph, &out_schedule));
+        // between the processor's start time and the time when the data of
+        // all the node's parents will be available on the processor.
+        let node_start_time = rand_proc_start_time.max(get_ready_time_on(
+            rand_node,
+            rand_proc,
+            &graph,
+            &out_schedule,
+            platform,
+        ));
 
         // Schedule the node
         out_schedule.processors[rand_proc].add_timeslot(
             rand_node,
             node_start_time,
-            node_start_time + graph.get_wcet(rand_node).unwrap(),
+            node_start_time + platform.get_execution_time(graph, rand_node, rand_proc),
         );
 
         graph.set_state(rand_node, TaskState::Scheduled);
@@ -245,7 +399,7 @@ pub fn random(graph: &mut TaskGraph, nb_processors: usize) -> Schedule {
         ready_list.remove(rand_indice);
     }
 
-    debug_assert!(graph.is_valid_schedule(&out_schedule));
+    debug_assert!(graph.is_valid_schedule_on(&out_schedule, platform));
 
     out_schedule
 }
@@ -256,16 +410,25 @@ pub fn random(graph: &mut TaskGraph, nb_processors: usize) -> Schedule {
 /// * `graph` - The `TaskGraph` to schedule
 /// * `nb_processors` - The number of processors available
 pub fn hlfet(graph: &mut TaskGraph, nb_processors: usize) -> Schedule {
-    // Build the schedule
-    let mut out_schedule = Schedule::new();
+    hlfet_on(graph, &Platform::homogeneous(nb_processors))
+}
 
-    for _ in 0..nb_processors {
-        out_schedule.add_processor();
-    }
+/// Schedules the `TaskGraph` with the HLFET algorithm on the processors of a
+/// `Platform`
+///
+/// # Arguments
+/// * `graph` - The `TaskGraph` to schedule
+/// * `platform` - The processors available
+pub fn hlfet_on(graph: &mut TaskGraph, platform: &Platform) -> Schedule {
+    // Build the schedule
+    let mut out_schedule = Schedule::with_platform(platform);
 
     // Reset the status of all reachable nodes to `WaitingDependencies`
     set_status_waiting(graph);
 
+    // The b-levels, computed once for the whole run
+    let b_levels = get_b_levels(graph);
+
     // The firsts nodes in the readylist
     let first_nodes = graph.get_entry_nodes();
 
@@ -273,7 +436,7 @@ pub fn hlfet(graph: &mut TaskGraph, nb_processors: usize) -> Schedule {
     let mut ready_list: HashMap<usize, f64> = HashMap::new();
 
     for node in first_nodes {
-        ready_list.insert(node, graph.get_b_level(node).unwrap());
+        ready_list.insert(node, b_levels[node]);
     }
 
     // Main Loop
@@ -296,23 +459,28 @@ pub fn hlfet(graph: &mut TaskGraph, nb_processors: usize) -> Schedule {
         }
 
         // The start time of the node will be the the maximum
-        // between the processor's start time and the time when all the node's
-        // parents will be completed (connexion time are overlooked).
-        let node_start_time =
-            chosen_proc_start_time.max(get_ready_time(first_node, &graph, &out_schedule));
+        // between the processor's start time and the time when the data of
+        // all the node's parents will be available on the processor.
+        let node_start_time = chosen_proc_start_time.max(get_ready_time_on(
+            first_node,
+            chosen_proc,
+            &graph,
+            &out_schedule,
+            platform,
+        ));
 
         // Schedule the node
         out_schedule.processors[chosen_proc].add_timeslot(
             first_node,
             node_start_time,
-            node_start_time + graph.get_wcet(first_node).unwrap(),
+            node_start_time + platform.get_execution_time(graph, first_node, chosen_proc),
         );
         graph.set_state(first_node, TaskState::Scheduled);
 
         // Add the successors if all theirs predecessors are scheduled
         for node in graph.get_successors(first_node).unwrap_or_default() {
             if !ready_list.contains_key(&node) && predecessors_scheduled(node, &graph) {
-                ready_list.insert(node, graph.get_b_level(node).unwrap());
+                ready_list.insert(node, b_levels[node]);
             }
         }
 
@@ -320,7 +488,7 @@ pub fn hlfet(graph: &mut TaskGraph, nb_processors: usize) -> Schedule {
         ready_list.remove(&first_node);
     }
 
-    debug_assert!(graph.is_valid_schedule(&out_schedule));
+    debug_assert!(graph.is_valid_schedule_on(&out_schedule, platform));
 
     out_schedule
 }
@@ -331,18 +499,32 @@ pub fn hlfet(graph: &mut TaskGraph, nb_processors: usize) -> Schedule {
 /// * `graph` - The `TaskGraph` to schedule
 /// * `nb_processors` - The number of processors available
 pub fn etf(graph: &mut TaskGraph, nb_processors: usize) -> Schedule {
-    // Build the schedule
-    let mut out_schedule = Schedule::new();
+    etf_on(graph, &Platform::homogeneous(nb_processors))
+}
 
-    for _ in 0..nb_processors {
-        out_schedule.add_processor();
-    }
+/// Schedules the `TaskGraph` with the EFT algorithm on processors which may
+/// run at different speeds
+///
+/// # Arguments
+/// * `graph` - The `TaskGraph` to schedule
+/// * `platform` - The processors available
+pub fn etf_on(graph: &mut TaskGraph, platform: &Platform) -> Schedule {
+    // Build the schedule
+    let mut out_schedule = Schedule::with_platform(platform);
 
     // Reset the status of all reachable nodes to `WaitingDependencies`
     set_status_waiting(graph);
 
-    // The firsts nodes in the readylist
+    // The b-levels, computed once for the whole run
+    let b_levels = get_b_levels(graph);
+
+    // The firsts nodes in the readylist, with the times their data is
+    // available on each processor, which do not change once they are ready
     let mut ready_list: Vec<usize> = graph.get_entry_nodes();
+    let mut ready_times: Vec<Vec<f64>> = ready_list
+        .iter()
+        .map(|&node| get_ready_times(node, graph, &out_schedule, platform))
+        .collect();
 
     // Main loop
     while !ready_list.is_empty() {
@@ -358,9 +540,8 @@ pub fn etf(graph: &mut TaskGraph, nb_processors: usize) -> Schedule {
 
             for j in 0..ready_list.len() {
                 let current_node = ready_list[j];
-                let current_blevel = graph.get_b_level(current_node).unwrap();
-                let current_start_time =
-                    proc_start_time.max(get_ready_time(current_node, &graph, &out_schedule));
+                let current_blevel = b_levels[current_node];
+                let current_start_time = proc_start_time.max(ready_times[j][i]);
 
                 if min_start_time.is_none() {
                     min_start_time = Some(current_start_time);
@@ -370,7 +551,7 @@ pub fn etf(graph: &mut TaskGraph, nb_processors: usize) -> Schedule {
                 }
                 // Not stric comparaison, but within error margin
                 if (current_start_time - min_start_time.unwrap()).abs() < std::f64::EPSILON
-                    && graph.get_b_level(min_node.unwrap()).unwrap() < current_blevel
+                    && b_levels[min_node.unwrap()] < current_blevel
                 {
                     min_start_time = Some(current_start_time);
                     min_node = Some(current_node);
@@ -386,7 +567,8 @@ pub fn etf(graph: &mut TaskGraph, nb_processors: usize) -> Schedule {
             }
         }
 
-        let end_time = min_start_time.unwrap() + graph.get_wcet(ready_list[node_index]).unwrap();
+        let end_time = min_start_time.unwrap()
+            + platform.get_execution_time(graph, ready_list[node_index], min_proc.unwrap());
 
         out_schedule.processors[min_proc.unwrap()].add_timeslot(
             min_node.unwrap(),
@@ -396,18 +578,80 @@ pub fn etf(graph: &mut TaskGraph, nb_processors: usize) -> Schedule {
 
         graph.set_state(min_node.unwrap(), TaskState::Scheduled);
 
+        ready_list.remove(node_index);
+        ready_times.remove(node_index);
+
         let successors = graph.get_successors(min_node.unwrap()).unwrap_or_default();
 
         for node in successors {
             if !ready_list.contains(&node) && predecessors_scheduled(node, &graph) {
                 ready_list.push(node);
+                ready_times.push(get_ready_times(node, graph, &out_schedule, platform));
             }
         }
+    }
 
-        ready_list.remove(node_index);
+    debug_assert!(graph.is_valid_schedule_on(&out_schedule, platform));
+
+    out_schedule
+}
+
+/// Schedules the `TaskGraph` with the HEFT algorithm, on processors which may
+/// run at different speeds
+///
+/// # Arguments
+/// * `graph` - The `TaskGraph` to schedule
+/// * `platform` - The processors available
+pub fn heft(graph: &mut TaskGraph, platform: &Platform) -> Schedule {
+    // Build the schedule
+    let mut out_schedule = Schedule::with_platform(platform);
+
+    // Compute the upward rank of each node, with its average execution time
+    // over the processors and the average communication costs between them
+    let mut ranks = vec![0.0; graph.get_nb_node()];
+
+    for node in graph.get_rev_topological_order() {
+        let mut max: f64 = 0.0;
+
+        for succ in graph.get_successors(node).unwrap_or_default() {
+            max = max.max(platform.get_average_communication_cost(graph, node, succ) + ranks[succ]);
+        }
+
+        ranks[node] = platform.get_average_execution_time(graph, node) + max;
     }
 
-    debug_assert!(graph.is_valid_schedule(&out_schedule));
+    // Sort the nodes by decreasing rank, the stable sort keeping the
+    // topological order between nodes of equal rank
+    let mut nodes = graph.get_topological_order();
+    nodes.sort_by(|&a, &b| ranks[b].partial_cmp(&ranks[a]).unwrap());
+
+    for node in nodes {
+        // Choose the processor on which the node finishes the earliest
+        let mut chosen_proc = 0;
+        let mut chosen_start_time = 0.0;
+        let mut chosen_finish_time = None;
+
+        for i in 0..out_schedule.processors.len() {
+            let start_time = out_schedule.processors[i]
+                .get_completion_time()
+                .max(get_ready_time_on(node, i, graph, &out_schedule, platform));
+            let finish_time = start_time + platform.get_execution_time(graph, node, i);
+
+            if chosen_finish_time.is_none() || finish_time < chosen_finish_time.unwrap() {
+                chosen_proc = i;
+                chosen_start_time = start_time;
+                chosen_finish_time = Some(finish_time);
+            }
+        }
+
+        out_schedule.processors[chosen_proc].add_timeslot(
+            node,
+            chosen_start_time,
+            chosen_finish_time.unwrap(),
+        );
+    }
+
+    debug_assert!(graph.is_valid_schedule_on(&out_schedule, platform));
 
     out_schedule
 }
@@ -416,7 +660,8 @@ pub fn etf(graph: &mut TaskGraph, nb_processors: usize) -> Schedule {
 ///
 /// # Arguments
 /// * `graph` - The `TaskGraph` to schedule
-/// * `communication_cost` - The communication time between processors
+/// * `communication_cost` - The communication time between processors for
+///   the edges without communication cost
 pub fn cpfd(graph: &mut TaskGraph, communication_cost: f64) -> Schedule {
     // Initialise the schedule
     let mut out_schedule = Schedule::new();
@@ -484,9 +729,354 @@ pub fn cpfd(graph: &mut TaskGraph, communication_cost: f64) -> Schedule {
     out_schedule
 }
 
+// Returns the b-levels of every node of the graph, communication costs included
+fn get_b_levels(graph: &mut TaskGraph) -> Vec<f64> {
+    graph.analyze().b_levels.clone()
+}
+
+// Returns the total execution time of the nodes of a cluster
+fn get_cluster_work(graph: &mut TaskGraph, cluster: &[usize]) -> f64 {
+    cluster
+        .iter()
+        .map(|&node| graph.get_wcet(node).unwrap())
+        .sum()
+}
+
+/// Groups the nodes of the `TaskGraph` in clusters with the Dominant Sequence
+/// Clustering algorithm. The nodes of each cluster are in execution order.
+///
+/// The clusters are not placed on processors yet, so an edge between two
+/// clusters costs its average communication cost on the `Platform`.
+///
+/// # Arguments
+/// * `graph` - The `TaskGraph` to cluster
+/// * `platform` - The processors the clusters will be mapped on
+pub fn dsc_clusters(graph: &mut TaskGraph, platform: &Platform) -> Vec<Vec<usize>> {
+    let nb_nodes = graph.get_nb_node();
+    let b_levels = get_b_levels(graph);
+
+    let mut clusters: Vec<Vec<usize>> = Vec::new();
+    let mut node_cluster: Vec<Option<usize>> = vec![None; nb_nodes];
+    let mut finish_times: Vec<f64> = vec![0.0; nb_nodes];
+
+    // Reset the status of all reachable nodes to `WaitingDependencies`
+    set_status_waiting(graph);
+
+    // The free list: the nodes whose predecessors have all been examined
+    let mut free_list = graph.get_entry_nodes();
+
+    while !free_list.is_empty() {
+        // The t-level of a free node only depends on its examined predecessors
+        let t_level = |node: usize, graph: &TaskGraph, finish_times: &[f64]| {
+            graph
+                .get_predecessors(node)
+                .unwrap_or_default()
+                .iter()
+                .map(|&pred| {
+                    finish_times[pred] + platform.get_average_communication_cost(graph, pred, node)
+                })
+                .fold(0.0, f64::max)
+        };
+
+        // Get the free node with the highest priority (t-level + b-level),
+        // which is the node on the dominant sequence
+        let mut chosen_index = 0;
+        let mut chosen_priority = None;
+
+        for (i, &node) in free_list.iter().enumerate() {
+            let priority = t_level(node, graph, &finish_times) + b_levels[node];
+
+            if chosen_priority.is_none() || priority > chosen_priority.unwrap() {
+                chosen_index = i;
+                chosen_priority = Some(priority);
+            }
+        }
+
+        let node = free_list.remove(chosen_index);
+        let predecessors = graph.get_predecessors(node).unwrap_or_default();
+
+        // First consider the node alone in a new cluster
+        let mut start_time = t_level(node, graph, &finish_times);
+        let mut chosen_cluster = None;
+
+        // Then try to merge it at the end of the cluster of one of its
+        // predecessors, zeroing the communication costs with this cluster
+        for &pred in &predecessors {
+            let cluster = node_cluster[pred].unwrap();
+            let cluster_ready_time = finish_times[*clusters[cluster].last().unwrap()];
+
+            let merged_start_time = predecessors
+                .iter()
+                .map(|&p| {
+                    if node_cluster[p] == Some(cluster) {
+                        finish_times[p]
+                    } else {
+                        finish_times[p] + platform.get_average_communication_cost(graph, p, node)
+                    }
+                })
+                .fold(cluster_ready_time, f64::max);
+
+            if merged_start_time < start_time {
+                start_time = merged_start_time;
+                chosen_cluster = Some(cluster);
+            }
+        }
+
+        let cluster = match chosen_cluster {
+            Some(cluster) => cluster,
+            None => {
+                clusters.push(Vec::new());
+                clusters.len() - 1
+            }
+        };
+
+        clusters[cluster].push(node);
+        node_cluster[node] = Some(cluster);
+        finish_times[node] = start_time + graph.get_wcet(node).unwrap();
+        graph.set_state(node, TaskState::Scheduled);
+
+        // Add the successors whose predecessors have all been examined
+        for succ in graph.get_successors(node).unwrap_or_default() {
+            if !free_list.contains(&succ) && predecessors_scheduled(succ, graph) {
+                free_list.push(succ);
+            }
+        }
+    }
+
+    clusters
+}
+
+/// Groups the nodes of the `TaskGraph` in clusters with the linear clustering
+/// algorithm: each cluster is the critical path of the nodes not clustered yet.
+///
+/// An edge costs its average communication cost on the `Platform`.
+///
+/// # Arguments
+/// * `graph` - The `TaskGraph` to cluster
+/// * `platform` - The processors the clusters will be mapped on
+pub fn linear_clusters(graph: &mut TaskGraph, platform: &Platform) -> Vec<Vec<usize>> {
+    let nb_nodes = graph.get_nb_node();
+    let rev_top_ord = graph.get_rev_topological_order();
+
+    let mut clusters = Vec::new();
+    let mut clustered = vec![false; nb_nodes];
+    let mut nb_clustered = 0;
+
+    while nb_clustered < nb_nodes {
+        // Compute the longest path from each node through the unclustered
+        // nodes, and the successor following it on this path
+        let mut path_lengths = vec![0.0; nb_nodes];
+        let mut next_nodes: Vec<Option<usize>> = vec![None; nb_nodes];
+
+        for &node in &rev_top_ord {
+            if clustered[node] {
+                continue;
+            }
+
+            let mut max = 0.0;
+
+            for succ in graph.get_successors(node).unwrap_or_default() {
+                if clustered[succ] {
+                    continue;
+                }
+
+                let length =
+                    platform.get_average_communication_cost(graph, node, succ) + path_lengths[succ];
+
+                if next_nodes[node].is_none() || length > max {
+                    max = length;
+                    next_nodes[node] = Some(succ);
+                }
+            }
+
+            path_lengths[node] = graph.get_wcet(node).unwrap() + max;
+        }
+
+        // The critical path starts at the unclustered node with the
+        // longest path
+        let mut current: Option<usize> = None;
+
+        for node in (0..nb_nodes).filter(|&node| !clustered[node]) {
+            if current.is_none() || path_lengths[node] > path_lengths[current.unwrap()] {
+                current = Some(node);
+            }
+        }
+
+        let mut cluster = Vec::new();
+
+        while let Some(node) = current {
+            cluster.push(node);
+            clustered[node] = true;
+            nb_clustered += 1;
+            current = next_nodes[node];
+        }
+
+        clusters.push(cluster);
+    }
+
+    clusters
+}
+
+/// Merges the clusters until there are at most as many of them as processors
+/// of the `Platform` and schedules their nodes on the `Processor` of their
+/// cluster.
+///
+/// The clusters are merged by decreasing amount of work on the least loaded
+/// `Processor`, then the nodes are ordered on each `Processor` by decreasing
+/// b-level.
+///
+/// # Arguments
+/// * `graph` - The `TaskGraph` to schedule
+/// * `clusters` - The clusters of nodes
+/// * `platform` - The processors available
+pub fn map_clusters(
+    graph: &mut TaskGraph,
+    clusters: &[Vec<usize>],
+    platform: &Platform,
+) -> Schedule {
+    let nb_nodes = graph.get_nb_node();
+    let nb_processors = platform.get_nb_processor();
+
+    // Build the schedule
+    let mut out_schedule = Schedule::with_platform(platform);
+
+    // Assign the clusters with the most work first to the least loaded processor
+    let mut works: Vec<(usize, f64)> = clusters
+        .iter()
+        .enumerate()
+        .map(|(i, cluster)| (i, get_cluster_work(graph, cluster)))
+        .collect();
+    works.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+
+    let mut loads = vec![0.0; nb_processors];
+    let mut node_proc = vec![0; nb_nodes];
+
+    for (cluster, work) in works {
+        let mut chosen_proc = 0;
+
+        for i in 1..nb_processors {
+            if loads[i] < loads[chosen_proc] {
+                chosen_proc = i;
+            }
+        }
+
+        loads[chosen_proc] += work;
+
+        for &node in &clusters[cluster] {
+            node_proc[node] = chosen_proc;
+        }
+    }
+
+    // Order the nodes on their processors
+    let b_levels = get_b_levels(graph);
+    let mut finish_times = vec![0.0; nb_nodes];
+
+    // Reset the status of all reachable nodes to `WaitingDependencies`
+    set_status_waiting(graph);
+
+    let mut ready_list = graph.get_entry_nodes();
+
+    while !ready_list.is_empty() {
+        // Get the ready node with the highest b-level
+        let mut node_index = 0;
+
+        for i in 1..ready_list.len() {
+            if b_levels[ready_list[i]] > b_levels[ready_list[node_index]] {
+                node_index = i;
+            }
+        }
+
+        let node = ready_list.remove(node_index);
+        let chosen_proc = node_proc[node];
+
+        // The node can start once its processor is free and the data from
+        // its predecessors on other processors has arrived
+        let start_time = graph
+            .get_predecessors(node)
+            .unwrap_or_default()
+            .iter()
+            .map(|&pred| {
+                finish_times[pred]
+                    + platform.get_communication_cost(
+                        graph,
+                        pred,
+                        node,
+                        node_proc[pred],
+                        chosen_proc,
+                    )
+            })
+            .fold(
+                out_schedule.processors[chosen_proc].get_completion_time(),
+                f64::max,
+            );
+
+        finish_times[node] = start_time + platform.get_execution_time(graph, node, chosen_proc);
+
+        out_schedule.processors[chosen_proc].add_timeslot(node, start_time, finish_times[node]);
+        graph.set_state(node, TaskState::Scheduled);
+
+        // Add the successors if all theirs predecessors are scheduled
+        for succ in graph.get_successors(node).unwrap_or_default() {
+            if !ready_list.contains(&succ) && predecessors_scheduled(succ, graph) {
+                ready_list.push(succ);
+            }
+        }
+    }
+
+    debug_assert!(graph.is_valid_schedule_on(&out_schedule, platform));
+
+    out_schedule
+}
+
+/// Schedules the `TaskGraph` with the DSC algorithm followed by the merging
+/// of the clusters
+///
+/// # Arguments
+/// * `graph` - The `TaskGraph` to schedule
+/// * `nb_processors` - The number of processors available
+pub fn dsc(graph: &mut TaskGraph, nb_processors: usize) -> Schedule {
+    dsc_on(graph, &Platform::homogeneous(nb_processors))
+}
+
+/// Schedules the `TaskGraph` with the DSC algorithm followed by the merging
+/// of the clusters on the processors of a `Platform`
+///
+/// # Arguments
+/// * `graph` - The `TaskGraph` to schedule
+/// * `platform` - The processors available
+pub fn dsc_on(graph: &mut TaskGraph, platform: &Platform) -> Schedule {
+    let clusters = dsc_clusters(graph, platform);
+
+    map_clusters(graph, &clusters, platform)
+}
+
+/// Schedules the `TaskGraph` with the linear clustering algorithm followed by
+/// the merging of the clusters
+///
+/// # Arguments
+/// * `graph` - The `TaskGraph` to schedule
+/// * `nb_processors` - The number of processors available
+pub fn linear_clustering(graph: &mut TaskGraph, nb_processors: usize) -> Schedule {
+    linear_clustering_on(graph, &Platform::homogeneous(nb_processors))
+}
+
+/// Schedules the `TaskGraph` with the linear clustering algorithm followed by
+/// the merging of the clusters on the processors of a `Platform`
+///
+/// # Arguments
+/// * `graph` - The `TaskGraph` to schedule
+/// * `platform` - The processors available
+pub fn linear_clustering_on(graph: &mut TaskGraph, platform: &Platform) -> Schedule {
+    let clusters = linear_clusters(graph, platform);
+
+    map_clusters(graph, &clusters, platform)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use parser::stg;
+    use static_scheduling::validation::{validate, validate_on};
     use task_graph::task::Task;
 
     #[test]
@@ -512,6 +1102,7 @@ mod tests {
         let sche_rand = random(&mut g, 2);
 
         println!("schedule {}", sche_hlfelt);
+        assert_eq!(validate(&mut g, &sche_hlfelt), Vec::new());
         assert_eq!(sche_hlfelt.get_completion_time(), 5.0);
         assert!(sche_hlfelt.get_completion_time() <= sche_rand.get_completion_time());
     }
@@ -539,10 +1130,127 @@ mod tests {
         let sche_rand = random(&mut g, 2);
 
         println!("schedule {}", sche_etf);
+        assert_eq!(validate(&mut g, &sche_etf), Vec::new());
         assert_eq!(sche_etf.get_completion_time(), 5.0);
         assert!(sche_etf.get_completion_time() <= sche_rand.get_completion_time());
     }
 
+    #[test]
+    fn test_etf_communication_costs() {
+        let mut g = TaskGraph::new(3, 2);
+
+        for _ in 0..3 {
+            g.add_task(Task::Constant(1.0));
+        }
+
+        g.add_edge(0, 1);
+        g.add_edge(0, 2);
+        g.set_communication_cost(0, 1, 10.0);
+        g.set_communication_cost(0, 2, 10.0);
+
+        // Sending the data to another processor costs more than running
+        // every node on the same one
+        let sche_etf = etf(&mut g, 2);
+
+        println!("schedule {}", sche_etf);
+        assert_eq!(validate(&mut g, &sche_etf), Vec::new());
+        assert_eq!(sche_etf.get_completion_time(), 3.0);
+    }
+
+    #[test]
+    fn test_processor_communication_costs() {
+        let mut g = TaskGraph::new(3, 2);
+
+        for _ in 0..3 {
+            g.add_task(Task::Constant(1.0));
+        }
+
+        g.add_edge(0, 1);
+        g.add_edge(0, 2);
+
+        // The processor 2 shares a cache with the processor 0, not the
+        // processor 1
+        let mut platform = Platform::homogeneous(3);
+
+        for &(src, dst, cost) in &[(0, 1, 10.0), (0, 2, 0.5), (1, 2, 10.0)] {
+            platform.set_communication_cost(src, dst, cost);
+            platform.set_communication_cost(dst, src, cost);
+        }
+
+        // Every algorithm pays the costs of the platform
+        for algo in &[
+            SchedulingAlgorithm::Random,
+            SchedulingAlgorithm::HLFET,
+            SchedulingAlgorithm::ETF,
+            SchedulingAlgorithm::DSC,
+            SchedulingAlgorithm::LC,
+            SchedulingAlgorithm::HEFT,
+        ] {
+            let sche = schedule_on(&mut g, &platform, *algo);
+
+            assert_eq!(validate_on(&mut g, &sche, &platform), Vec::new());
+        }
+
+        // The algorithms choosing the processor with the earliest start or
+        // finish time avoid the remote processor
+        for algo in &[SchedulingAlgorithm::ETF, SchedulingAlgorithm::HEFT] {
+            let sche = schedule_on(&mut g, &platform, *algo);
+
+            assert!(sche.processors[1].time_slots.is_empty());
+        }
+
+        let sche_etf = etf_on(&mut g, &platform);
+
+        assert_eq!(sche_etf.get_completion_time(), 2.5);
+        // Without the costs of the platform, the schedule is too optimistic
+        let sche_etf = etf(&mut g, 3);
+
+        assert!(!validate_on(&mut g, &sche_etf, &platform).is_empty());
+    }
+
+    #[test]
+    fn test_heft_heterogeneous() {
+        let mut g = TaskGraph::new(4, 4);
+
+        for _ in 0..4 {
+            g.add_task(Task::Constant(1.0));
+        }
+
+        g.add_edge(0, 1);
+        g.add_edge(0, 2);
+        g.add_edge(1, 3);
+        g.add_edge(2, 3);
+
+        // Without communication costs, the slow processor only helps for
+        // one of the two branches
+        let platform = Platform::with_speeds(&[0.25, 1.0]);
+
+        let sche_heft = heft(&mut g, &platform);
+        println!("schedule {}", sche_heft);
+        assert_eq!(validate_on(&mut g, &sche_heft, &platform), Vec::new());
+        assert_eq!(sche_heft.get_completion_time(), 4.0);
+        assert_eq!(sche_heft.processors[0].time_slots.len(), 0);
+
+        let platform = Platform::with_speeds(&[0.5, 1.0]);
+
+        let sche_heft = heft(&mut g, &platform);
+        println!("schedule {}", sche_heft);
+        assert_eq!(validate_on(&mut g, &sche_heft, &platform), Vec::new());
+        assert_eq!(sche_heft.get_completion_time(), 4.0);
+
+        // ETF picks the earliest start time, whatever the speed of the
+        // processor
+        let sche_etf = etf_on(&mut g, &platform);
+        println!("schedule {}", sche_etf);
+        assert_eq!(validate_on(&mut g, &sche_etf, &platform), Vec::new());
+        assert_eq!(sche_etf.get_completion_time(), 6.0);
+
+        // A homogeneous platform runs both branches in parallel
+        let sche_heft = heft(&mut g, &Platform::homogeneous(2));
+        assert_eq!(validate(&mut g, &sche_heft), Vec::new());
+        assert_eq!(sche_heft.get_completion_time(), 3.0);
+    }
+
     #[test]
     fn test_cpdf() {
         let mut g = TaskGraph::new(8, 9);
@@ -565,6 +1273,7 @@ mod tests {
         let sche_cpfd = cpfd(&mut g, 0.0);
 
         println!("schedule {}", sche_cpfd);
+        assert_eq!(validate(&mut g, &sche_cpfd), Vec::new());
 
         assert_eq!(sche_cpfd.get_completion_time(), 5.0);
     }
@@ -593,8 +1302,11 @@ mod tests {
         let mut sche_rand = random(&mut g, 2);
 
         println!("schedule {}", sche_etf);
+        assert_eq!(validate(&mut g, &sche_etf), Vec::new());
         println!("schedule {}", sche_hlfelt);
+        assert_eq!(validate(&mut g, &sche_hlfelt), Vec::new());
         println!("schedule {}", sche_rand);
+        assert_eq!(validate(&mut g, &sche_rand), Vec::new());
 
         assert!(sche_hlfelt.get_completion_time() <= sche_rand.get_completion_time());
         assert!(sche_etf.get_completion_time() <= sche_hlfelt.get_completion_time());
@@ -604,8 +1316,11 @@ mod tests {
         sche_rand = random(&mut g, 3);
 
         println!("schedule {}", sche_etf);
+        assert_eq!(validate(&mut g, &sche_etf), Vec::new());
         println!("schedule {}", sche_hlfelt);
+        assert_eq!(validate(&mut g, &sche_hlfelt), Vec::new());
         println!("schedule {}", sche_rand);
+        assert_eq!(validate(&mut g, &sche_rand), Vec::new());
 
         assert!(sche_hlfelt.get_completion_time() <= sche_rand.get_completion_time());
         assert!(sche_etf.get_completion_time() <= sche_hlfelt.get_completion_time());
@@ -615,8 +1330,11 @@ mod tests {
         sche_rand = random(&mut g, 4);
 
         println!("schedule {}", sche_etf);
+        assert_eq!(validate(&mut g, &sche_etf), Vec::new());
         println!("schedule {}", sche_hlfelt);
+        assert_eq!(validate(&mut g, &sche_hlfelt), Vec::new());
         println!("schedule {}", sche_rand);
+        assert_eq!(validate(&mut g, &sche_rand), Vec::new());
 
         assert!(sche_hlfelt.get_completion_time() <= sche_rand.get_completion_time());
         assert!(sche_etf.get_completion_time() <= sche_hlfelt.get_completion_time());
@@ -671,6 +1389,10 @@ mod tests {
         let mut sche_hlfelt = hlfet(&mut g, 2);
         let mut sche_rand = random(&mut g, 2);
 
+        assert_eq!(validate(&mut g, &sche_etf), Vec::new());
+        assert_eq!(validate(&mut g, &sche_hlfelt), Vec::new());
+        assert_eq!(validate(&mut g, &sche_rand), Vec::new());
+
         assert!(sche_hlfelt.get_completion_time() <= sche_rand.get_completion_time());
         assert!(sche_etf.get_completion_time() <= sche_hlfelt.get_completion_time());
 
@@ -678,6 +1400,10 @@ mod tests {
         sche_hlfelt = hlfet(&mut g, 3);
         sche_rand = random(&mut g, 3);
 
+        assert_eq!(validate(&mut g, &sche_etf), Vec::new());
+        assert_eq!(validate(&mut g, &sche_hlfelt), Vec::new());
+        assert_eq!(validate(&mut g, &sche_rand), Vec::new());
+
         assert!(sche_hlfelt.get_completion_time() <= sche_rand.get_completion_time());
         assert!(sche_etf.get_completion_time() <= sche_hlfelt.get_completion_time());
 
@@ -685,6 +1411,10 @@ mod tests {
         sche_hlfelt = hlfet(&mut g, 4);
         sche_rand = random(&mut g, 4);
 
+        assert_eq!(validate(&mut g, &sche_etf), Vec::new());
+        assert_eq!(validate(&mut g, &sche_hlfelt), Vec::new());
+        assert_eq!(validate(&mut g, &sche_rand), Vec::new());
+
         assert!(sche_hlfelt.get_completion_time() <= sche_rand.get_completion_time());
         assert!(sche_etf.get_completion_time() <= sche_hlfelt.get_completion_time());
     }
@@ -743,6 +1473,10 @@ mod tests {
         let mut sche_hlfelt = hlfet(&mut g, 3);
         let mut sche_rand = random(&mut g, 3);
 
+        assert_eq!(validate(&mut g, &sche_etf), Vec::new());
+        assert_eq!(validate(&mut g, &sche_hlfelt), Vec::new());
+        assert_eq!(validate(&mut g, &sche_rand), Vec::new());
+
         assert!(sche_hlfelt.get_completion_time() <= sche_rand.get_completion_time());
         assert!(sche_etf.get_completion_time() <= sche_hlfelt.get_completion_time());
 
@@ -750,6 +1484,10 @@ mod tests {
         sche_hlfelt = hlfet(&mut g, 4);
         sche_rand = random(&mut g, 4);
 
+        assert_eq!(validate(&mut g, &sche_etf), Vec::new());
+        assert_eq!(validate(&mut g, &sche_hlfelt), Vec::new());
+        assert_eq!(validate(&mut g, &sche_rand), Vec::new());
+
         assert!(sche_hlfelt.get_completion_time() <= sche_rand.get_completion_time());
         assert!(sche_etf.get_completion_time() <= sche_hlfelt.get_completion_time());
 
@@ -757,6 +1495,10 @@ mod tests {
         sche_hlfelt = hlfet(&mut g, 5);
         sche_rand = random(&mut g, 5);
 
+        assert_eq!(validate(&mut g, &sche_etf), Vec::new());
+        assert_eq!(validate(&mut g, &sche_hlfelt), Vec::new());
+        assert_eq!(validate(&mut g, &sche_rand), Vec::new());
+
         assert!(sche_hlfelt.get_completion_time() <= sche_rand.get_completion_time());
         assert!(sche_etf.get_completion_time() <= sche_hlfelt.get_completion_time());
 
@@ -764,8 +1506,138 @@ mod tests {
         sche_hlfelt = hlfet(&mut g, 6);
         sche_rand = random(&mut g, 56);
 
+        assert_eq!(validate(&mut g, &sche_etf), Vec::new());
+        assert_eq!(validate(&mut g, &sche_hlfelt), Vec::new());
+        assert_eq!(validate(&mut g, &sche_rand), Vec::new());
+
         assert!(sche_hlfelt.get_completion_time() <= sche_rand.get_completion_time());
         assert!(sche_etf.get_completion_time() <= sche_hlfelt.get_completion_time());
     }
 
+    #[test]
+    fn test_stg_dummy_tasks() {
+        // The dummy entry and exit tasks have a zero cost
+        let mut g = stg::parser::parse("Samples/STG/fork-join-8.stg").unwrap();
+
+        for nb_processors in 1..4 {
+            for sche in vec![
+                random(&mut g, nb_processors),
+                hlfet(&mut g, nb_processors),
+                etf(&mut g, nb_processors),
+                dsc(&mut g, nb_processors),
+                linear_clustering(&mut g, nb_processors),
+                heft(&mut g, &Platform::homogeneous(nb_processors)),
+            ] {
+                println!("schedule {}", sche);
+                assert_eq!(validate(&mut g, &sche), Vec::new());
+                assert!(sche.get_completion_time() >= 20.0);
+            }
+        }
+
+        let sche_cpfd = cpfd(&mut g, 0.0);
+        assert_eq!(validate(&mut g, &sche_cpfd), Vec::new());
+        assert_eq!(sche_cpfd.get_completion_time(), 20.0);
+    }
+
+    #[test]
+    fn test_dsc_chain() {
+        let mut g = TaskGraph::new(4, 3);
+
+        for _ in 0..4 {
+            g.add_task(Task::Constant(1.0));
+        }
+
+        g.add_edge(0, 1);
+        g.add_edge(1, 2);
+        g.add_edge(2, 3);
+
+        g.set_communication_cost(0, 1, 10.0);
+        g.set_communication_cost(1, 2, 10.0);
+        g.set_communication_cost(2, 3, 10.0);
+
+        assert_eq!(
+            dsc_clusters(&mut g, &Platform::homogeneous(1)),
+            vec![vec![0, 1, 2, 3]]
+        );
+
+        let sche_dsc = dsc(&mut g, 2);
+
+        println!("schedule {}", sche_dsc);
+        assert_eq!(validate(&mut g, &sche_dsc), Vec::new());
+        assert_eq!(sche_dsc.get_completion_time(), 4.0);
+    }
+
+    #[test]
+    fn test_clustering_fork_join() {
+        let mut g = TaskGraph::new(4, 4);
+
+        g.add_task(Task::Constant(1.0));
+        g.add_task(Task::Constant(2.0));
+        g.add_task(Task::Constant(1.0));
+        g.add_task(Task::Constant(1.0));
+
+        g.add_edge(0, 1);
+        g.add_edge(0, 2);
+        g.add_edge(1, 3);
+        g.add_edge(2, 3);
+
+        g.set_communication_cost(0, 1, 1.0);
+        g.set_communication_cost(0, 2, 1.0);
+        g.set_communication_cost(1, 3, 1.0);
+        g.set_communication_cost(2, 3, 1.0);
+
+        assert_eq!(
+            linear_clusters(&mut g, &Platform::homogeneous(1)),
+            vec![vec![0, 1, 3], vec![2]]
+        );
+        assert_eq!(
+            dsc_clusters(&mut g, &Platform::homogeneous(1)),
+            vec![vec![0, 1], vec![2], vec![3]]
+        );
+
+        let sche_lc = linear_clustering(&mut g, 2);
+        let sche_dsc = dsc(&mut g, 2);
+
+        println!("schedule {}", sche_lc);
+        assert_eq!(validate(&mut g, &sche_lc), Vec::new());
+        println!("schedule {}", sche_dsc);
+        assert_eq!(validate(&mut g, &sche_dsc), Vec::new());
+        assert_eq!(sche_lc.get_completion_time(), 5.0);
+        assert_eq!(sche_dsc.get_completion_time(), 5.0);
+    }
+
+    #[test]
+    fn test_clustering_graph_8_node() {
+        let mut g = TaskGraph::new(8, 9);
+
+        for _ in 0..8 {
+            g.add_task(Task::Constant(1.0));
+        }
+
+        g.add_edge(7, 5);
+        g.add_edge(7, 6);
+        g.add_edge(5, 2);
+        g.add_edge(5, 4);
+        g.add_edge(6, 4);
+        g.add_edge(6, 3);
+        g.add_edge(2, 1);
+        g.add_edge(3, 1);
+        g.add_edge(1, 0);
+
+        for nb_processors in 1..5 {
+            let sche_dsc = dsc(&mut g, nb_processors);
+            let sche_lc = linear_clustering(&mut g, nb_processors);
+
+            println!("schedule {}", sche_dsc);
+            assert_eq!(validate(&mut g, &sche_dsc), Vec::new());
+            println!("schedule {}", sche_lc);
+            assert_eq!(validate(&mut g, &sche_lc), Vec::new());
+
+            assert_eq!(sche_dsc.get_nb_processor(), nb_processors);
+            assert_eq!(sche_lc.get_nb_processor(), nb_processors);
+        }
+
+        assert_eq!(dsc(&mut g, 1).get_completion_time(), 8.0);
+        assert_eq!(linear_clustering(&mut g, 1).get_completion_time(), 8.0);
+    }
 }
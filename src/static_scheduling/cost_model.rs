@@ -0,0 +1,236 @@
+//! This module implements the model of the communication costs between the
+//! nodes of a `TaskGraph`, based on the size of the audio buffers and on where
+//! the producer and the consumer of a buffer are executed.
+
+use std::io::{Error, ErrorKind, Result};
+
+use static_scheduling::platform::Platform;
+use static_scheduling::topology::CpuTopology;
+use task_graph::graph::TaskGraph;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// How close two logical CPUs are in the memory hierarchy.
+pub enum Locality {
+    SameCore,
+    SharedCache(u32), // Level of the closest shared cache
+    Remote,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// The cost of moving data for a given `Locality`.
+pub struct TransferCost {
+    pub latency: f64,   // Fixed cost of a transfer, in seconds
+    pub bandwidth: f64, // In bytes per second
+}
+
+impl TransferCost {
+    /// Returns the time needed to move `bytes` bytes.
+    ///
+    /// # Arguments
+    /// * `bytes` - The amount of data to move
+    pub fn time(&self, bytes: usize) -> f64 {
+        self.latency + bytes as f64 / self.bandwidth
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Computes the communication cost of an edge from the size of the audio
+/// buffer it carries and the `Locality` of the logical CPUs executing its
+/// source and destination nodes. Two nodes on the same physical core share
+/// its caches, so their edge costs nothing.
+pub struct CostModel {
+    pub topology: CpuTopology,
+    pub l2_cache: TransferCost,
+    pub l3_cache: TransferCost,
+    pub remote: TransferCost,
+}
+
+impl CostModel {
+    /// Creates a `CostModel` with default transfer costs for a topology.
+    ///
+    /// # Arguments
+    /// * `topology` - The topology of the CPUs
+    pub fn new(topology: CpuTopology) -> CostModel {
+        CostModel {
+            topology,
+            l2_cache: TransferCost {
+                latency: 20e-9,
+                bandwidth: 50e9,
+            },
+            l3_cache: TransferCost {
+                latency: 50e-9,
+                bandwidth: 25e9,
+            },
+            remote: TransferCost {
+                latency: 150e-9,
+                bandwidth: 10e9,
+            },
+        }
+    }
+
+    /// Creates a `CostModel` with default transfer costs for the topology of
+    /// the machine.
+    pub fn from_sysfs() -> Result<CostModel> {
+        CpuTopology::from_sysfs().map(CostModel::new)
+    }
+
+    /// Returns the `Locality` of two logical CPUs.
+    ///
+    /// # Arguments
+    /// * `src_cpu` - The logical CPU producing the data
+    /// * `dst_cpu` - The logical CPU consuming the data
+    pub fn get_locality(&self, src_cpu: usize, dst_cpu: usize) -> Locality {
+        if self.topology.same_core(src_cpu, dst_cpu) {
+            Locality::SameCore
+        } else {
+            match self.topology.shared_cache_level(src_cpu, dst_cpu) {
+                Some(level) => Locality::SharedCache(level),
+                None => Locality::Remote,
+            }
+        }
+    }
+
+    /// Returns the time needed to send an audio buffer from a logical CPU to
+    /// another one.
+    ///
+    /// # Arguments
+    /// * `buffer_size` - The number of samples of the buffer
+    /// * `src_cpu` - The logical CPU producing the buffer
+    /// * `dst_cpu` - The logical CPU consuming the buffer
+    pub fn get_cost(&self, buffer_size: usize, src_cpu: usize, dst_cpu: usize) -> f64 {
+        let bytes = buffer_size * std::mem::size_of::<f32>();
+
+        let transfer = match self.get_locality(src_cpu, dst_cpu) {
+            Locality::SameCore => return 0.0,
+            Locality::SharedCache(level) if level <= 2 => &self.l2_cache,
+            Locality::SharedCache(_) => &self.l3_cache,
+            Locality::Remote => &self.remote,
+        };
+
+        transfer.time(bytes)
+    }
+
+    /// Sets the cost of sending an audio buffer of the graph between each
+    /// couple of processors of the `Platform` pinned on a logical CPU, so that
+    /// the schedulers pay the cost of where they place the producer and the
+    /// consumer of each edge. The costs set in the graph file are kept.
+    ///
+    /// Returns an error if the buffer size of the graph is not set.
+    ///
+    /// # Arguments
+    /// * `graph` - The graph whose buffers are sent
+    /// * `platform` - The processors used for the execution
+    pub fn apply(&self, graph: &TaskGraph, platform: &mut Platform) -> Result<()> {
+        let buffer_size = graph.get_buffer_size().ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "the buffer size of the graph is not set",
+            )
+        })?;
+        let cpus: Vec<Option<usize>> = platform
+            .processors
+            .iter()
+            .map(|processor| processor.core_id)
+            .collect();
+
+        for (src_proc, src_cpu) in cpus.iter().enumerate() {
+            for (dst_proc, dst_cpu) in cpus.iter().enumerate() {
+                if let (Some(src_cpu), Some(dst_cpu)) = (src_cpu, dst_cpu) {
+                    if src_proc != dst_proc {
+                        platform.set_communication_cost(
+                            src_proc,
+                            dst_proc,
+                            self.get_cost(buffer_size, *src_cpu, *dst_cpu),
+                        );
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod cost_model_test {
+    use super::*;
+    use static_scheduling::topology::{Cache, Cpu};
+    use task_graph::task::Task;
+
+    // Two physical cores with two hardware threads each, sharing the L3
+    fn get_topology() -> CpuTopology {
+        let cpus = (0..4)
+            .map(|id| Cpu {
+                id,
+                core_id: id / 2,
+                package_id: 0,
+                capacity: None,
+                caches: vec![
+                    Cache {
+                        level: 2,
+                        shared_cpus: if id < 2 { vec![0, 1] } else { vec![2, 3] },
+                    },
+                    Cache {
+                        level: 3,
+                        shared_cpus: vec![0, 1, 2, 3],
+                    },
+                ],
+            })
+            .collect();
+
+        CpuTopology { cpus }
+    }
+
+    #[test]
+    fn test_locality() {
+        let model = CostModel::new(get_topology());
+
+        assert_eq!(model.get_locality(0, 1), Locality::SameCore);
+        assert_eq!(model.get_locality(1, 2), Locality::SharedCache(3));
+        assert_eq!(model.get_locality(1, 8), Locality::Remote);
+    }
+
+    #[test]
+    fn test_cost() {
+        let model = CostModel::new(get_topology());
+
+        assert_eq!(model.get_cost(256, 0, 0), 0.0);
+        assert_eq!(model.get_cost(256, 0, 1), 0.0);
+        assert!(model.get_cost(256, 0, 2) < model.get_cost(256, 0, 8));
+        assert!(model.get_cost(128, 0, 2) < model.get_cost(256, 0, 2));
+    }
+
+    #[test]
+    fn test_apply() {
+        let model = CostModel::new(get_topology());
+        let mut g = TaskGraph::new(3, 2);
+
+        for _ in 0..3 {
+            g.add_task(Task::Constant(1.0));
+        }
+
+        g.add_edge(0, 1);
+        g.add_edge(1, 2);
+        g.set_communication_cost(1, 2, 0.5);
+
+        let mut platform = Platform::homogeneous(3);
+
+        for (processor, &cpu) in platform.processors.iter_mut().zip(&[0, 1, 2]) {
+            processor.core_id = Some(cpu);
+        }
+
+        assert!(model.apply(&g, &mut platform).is_err());
+
+        g.set_buffer_size(256);
+        model.apply(&g, &mut platform).unwrap();
+
+        // The cost depends on where the nodes are executed
+        assert_eq!(platform.get_communication_cost(&g, 0, 1, 0, 0), 0.0);
+        assert_eq!(platform.get_communication_cost(&g, 0, 1, 0, 1), 0.0);
+        assert_eq!(
+            platform.get_communication_cost(&g, 0, 1, 0, 2),
+            model.get_cost(256, 0, 2)
+        );
+        assert_eq!(platform.get_communication_cost(&g, 1, 2, 0, 2), 0.5);
+    }
+}
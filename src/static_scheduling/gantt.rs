@@ -0,0 +1,278 @@
+//! This module renders a static scheduling as a Gantt chart in the SVG format.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use static_scheduling::schedule::Schedule;
+use static_scheduling::timeslot::TimeSlot;
+use task_graph::graph::TaskGraph;
+
+// The dimensions of the chart, in pixels
+const LEFT_MARGIN: f64 = 60.0;
+const TOP_MARGIN: f64 = 30.0;
+const RIGHT_MARGIN: f64 = 20.0;
+const LANE_PADDING: f64 = 4.0;
+const NB_TICKS: usize = 10;
+
+const SLOT_COLOR: &str = "#9ecae1";
+const DUPLICATED_SLOT_COLOR: &str = "#fdae6b";
+const DEADLINE_COLOR: &str = "#e6550d";
+
+#[derive(Clone, Debug, PartialEq)]
+/// How to draw the Gantt chart of a `Schedule`.
+pub struct GanttOptions {
+    pub width: f64,       // Width of the time axis, in pixels
+    pub lane_height: f64, // Height of the lane of a processor, in pixels
+    pub dependencies: bool,
+    pub deadline: Option<f64>, // Time at which a vertical line is drawn
+}
+
+impl Default for GanttOptions {
+    fn default() -> GanttOptions {
+        GanttOptions {
+            width: 1000.0,
+            lane_height: 30.0,
+            dependencies: false,
+            deadline: None,
+        }
+    }
+}
+
+// Escapes the characters which are not allowed in the text of an SVG file
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Formats a time of a chart or of a graph, in µs for the durations of audio
+// cycles
+pub(crate) fn format_time(time: f64, max_time: f64) -> String {
+    if max_time < 1.0 {
+        format!("{:.1}µs", time * 1_000_000.0)
+    } else {
+        format!("{:.2}", time)
+    }
+}
+
+/// Returns the Gantt chart of a `Schedule`, in the SVG format, with a lane for
+/// each processor and the nodes named as in the `TaskGraph`.
+///
+/// The nodes which are scheduled on several processors, as CPFD duplicates
+/// them, are highlighted.
+///
+/// # Arguments
+/// * `graph` - The `TaskGraph` which was scheduled
+/// * `schedule` - The `Schedule` to draw
+/// * `options` - How to draw the chart
+pub fn render_svg(graph: &TaskGraph, schedule: &Schedule, options: &GanttOptions) -> String {
+    let max_time = schedule
+        .get_completion_time()
+        .max(options.deadline.unwrap_or(0.0));
+    let scale = if max_time > 0.0 {
+        options.width / max_time
+    } else {
+        0.0
+    };
+
+    let nb_lanes = schedule.get_nb_processor();
+    let width = LEFT_MARGIN + options.width + RIGHT_MARGIN;
+    let height = TOP_MARGIN + nb_lanes as f64 * options.lane_height + LANE_PADDING;
+
+    let x = |time: f64| LEFT_MARGIN + time * scale;
+    let lane_y = |proc_index: usize| TOP_MARGIN + proc_index as f64 * options.lane_height;
+
+    // The processors of the copies of each node
+    let mut copies: HashMap<usize, Vec<(usize, TimeSlot)>> = HashMap::new();
+
+    for (proc_index, processor) in schedule.processors.iter().enumerate() {
+        for time_slot in &processor.time_slots {
+            copies
+                .entry(time_slot.get_node())
+                .or_default()
+                .push((proc_index, *time_slot));
+        }
+    }
+
+    let mut svg = String::new();
+
+    writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+         font-family=\"sans-serif\" font-size=\"10\">",
+        width, height
+    )
+    .unwrap();
+
+    if options.dependencies {
+        svg.push_str(
+            "<defs><marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" \
+             markerWidth=\"6\" markerHeight=\"6\" orient=\"auto\">\
+             <path d=\"M 0 0 L 10 5 L 0 10 z\"/></marker></defs>\n",
+        );
+    }
+
+    // The time axis
+    for tick in 0..=NB_TICKS {
+        let time = max_time * tick as f64 / NB_TICKS as f64;
+
+        writeln!(
+            svg,
+            "<line x1=\"{0}\" y1=\"{1}\" x2=\"{0}\" y2=\"{2}\" stroke=\"#dddddd\"/>\
+             <text x=\"{0}\" y=\"{3}\" text-anchor=\"middle\">{4}</text>",
+            x(time),
+            TOP_MARGIN - 5.0,
+            height,
+            TOP_MARGIN - 10.0,
+            escape(&format_time(time, max_time))
+        )
+        .unwrap();
+    }
+
+    // The lanes and their time slots
+    for (proc_index, processor) in schedule.processors.iter().enumerate() {
+        let y = lane_y(proc_index);
+
+        writeln!(
+            svg,
+            "<text x=\"5\" y=\"{}\" dominant-baseline=\"middle\">P{}</text>",
+            y + options.lane_height / 2.0,
+            proc_index
+        )
+        .unwrap();
+
+        for time_slot in &processor.time_slots {
+            let node = time_slot.get_node();
+            let start = x(time_slot.get_start_time());
+            let slot_width = (time_slot.get_completion_time() - time_slot.get_start_time()) * scale;
+            let name = escape(&graph.get_node_name(node));
+            let color = if copies[&node].len() > 1 {
+                DUPLICATED_SLOT_COLOR
+            } else {
+                SLOT_COLOR
+            };
+
+            writeln!(
+                svg,
+                "<g><title>{} [{}, {}]</title>\
+                 <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"black\"/>\
+                 <text x=\"{}\" y=\"{}\" text-anchor=\"middle\" dominant-baseline=\"middle\">{}</text></g>",
+                name,
+                time_slot.get_start_time(),
+                time_slot.get_completion_time(),
+                start,
+                y + LANE_PADDING,
+                slot_width,
+                options.lane_height - 2.0 * LANE_PADDING,
+                color,
+                start + slot_width / 2.0,
+                y + options.lane_height / 2.0,
+                name
+            )
+            .unwrap();
+        }
+    }
+
+    // The dependencies, from the copy of each predecessor which completes the
+    // earliest, on the same processor if possible
+    if options.dependencies {
+        for (proc_index, processor) in schedule.processors.iter().enumerate() {
+            for time_slot in &processor.time_slots {
+                let node = time_slot.get_node();
+
+                for predecessor in graph.get_predecessors(node).unwrap_or_default() {
+                    let source = copies.get(&predecessor).and_then(|pred_copies| {
+                        pred_copies
+                            .iter()
+                            .find(|(pred_proc, _)| *pred_proc == proc_index)
+                            .or_else(|| {
+                                pred_copies.iter().min_by(|(_, a), (_, b)| {
+                                    a.get_completion_time()
+                                        .partial_cmp(&b.get_completion_time())
+                                        .unwrap()
+                                })
+                            })
+                    });
+
+                    if let Some((pred_proc, pred_slot)) = source {
+                        writeln!(
+                            svg,
+                            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#555555\" \
+                             marker-end=\"url(#arrow)\"/>",
+                            x(pred_slot.get_completion_time()),
+                            lane_y(*pred_proc) + options.lane_height / 2.0,
+                            x(time_slot.get_start_time()),
+                            lane_y(proc_index) + options.lane_height / 2.0
+                        )
+                        .unwrap();
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(deadline) = options.deadline {
+        writeln!(
+            svg,
+            "<line x1=\"{0}\" y1=\"{1}\" x2=\"{0}\" y2=\"{2}\" stroke=\"{3}\" \
+             stroke-width=\"2\" stroke-dasharray=\"4\"/>",
+            x(deadline),
+            TOP_MARGIN - 5.0,
+            height,
+            DEADLINE_COLOR
+        )
+        .unwrap();
+    }
+
+    svg.push_str("</svg>\n");
+
+    svg
+}
+
+#[cfg(test)]
+mod gantt_test {
+    use super::*;
+    use task_graph::task::Task;
+
+    #[test]
+    fn test_render_svg() {
+        let mut g = TaskGraph::new(3, 2);
+
+        for _ in 0..3 {
+            g.add_task(Task::Constant(1.0));
+        }
+
+        g.add_edge(0, 1);
+        g.add_edge(0, 2);
+
+        // The entry node is duplicated on both processors
+        let mut sche = Schedule::new();
+
+        sche.add_processor();
+        sche.add_processor();
+        sche.processors[0].add_timeslot(0, 0.0, 1.0);
+        sche.processors[0].add_timeslot(1, 1.0, 2.0);
+        sche.processors[1].add_timeslot(0, 0.0, 1.0);
+        sche.processors[1].add_timeslot(2, 1.0, 2.0);
+
+        let options = GanttOptions {
+            dependencies: true,
+            deadline: Some(4.0),
+            ..GanttOptions::default()
+        };
+
+        let svg = render_svg(&g, &sche, &options);
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<rect").count(), 4);
+        assert_eq!(svg.matches(DUPLICATED_SLOT_COLOR).count(), 2);
+        assert_eq!(svg.matches("marker-end").count(), 2);
+        assert_eq!(svg.matches(DEADLINE_COLOR).count(), 1);
+        assert!(svg.contains(">P1</text>"));
+
+        // The chart spans the deadline
+        assert!(svg.contains("<rect x=\"60\" y=\"34\" width=\"250\""));
+    }
+}
@@ -0,0 +1,212 @@
+//! This module computes the metrics used to compare static schedulings.
+
+use std::fmt::{Display, Error, Formatter};
+
+use static_scheduling::schedule::Schedule;
+use task_graph::graph::TaskGraph;
+
+/// The columns of the lines returned by `ScheduleMetrics::to_csv`.
+pub const CSV_HEADER: &str = "algorithm,nb_processors,makespan,sequential_time,critical_path,\
+                              speedup,efficiency,slr,idle_time,load_imbalance";
+
+#[derive(Clone, Debug, PartialEq)]
+/// The metrics of one `Processor` of a `Schedule`.
+pub struct ProcessorMetrics {
+    pub busy_time: f64,
+    pub utilisation: f64,           // Busy time over the makespan
+    pub idle_gaps: Vec<(f64, f64)>, // Start and end of the periods without task
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// The metrics of a `Schedule` of a `TaskGraph`.
+pub struct ScheduleMetrics {
+    pub makespan: f64,
+    pub sequential_time: f64, // Sum of the WCETs of the nodes
+    pub critical_path: f64,   // Longest path of WCETs, without communication costs
+    pub speedup: f64,
+    pub efficiency: f64,     // Speedup over the number of processors
+    pub slr: f64,            // Schedule length ratio, makespan over critical path
+    pub load_imbalance: f64, // Highest busy time over the average one
+    pub processors: Vec<ProcessorMetrics>,
+}
+
+// Returns `numerator / denominator`, or 0 for an empty schedule
+fn ratio(numerator: f64, denominator: f64) -> f64 {
+    if denominator > 0.0 {
+        numerator / denominator
+    } else {
+        0.0
+    }
+}
+
+impl ScheduleMetrics {
+    /// Computes the metrics of a `Schedule`.
+    ///
+    /// # Arguments
+    /// * `graph` - The `TaskGraph` which was scheduled
+    /// * `schedule` - The `Schedule` of the graph
+    pub fn new(graph: &mut TaskGraph, schedule: &Schedule) -> ScheduleMetrics {
+        let makespan = schedule.get_completion_time();
+
+        let mut sequential_time = 0.0;
+        let mut levels = vec![0.0; graph.get_nb_node()];
+        let mut critical_path: f64 = 0.0;
+
+        for node in graph.get_rev_topological_order() {
+            let wcet = graph.get_wcet(node).unwrap_or(0.0);
+            let mut max: f64 = 0.0;
+
+            for succ in graph.get_successors(node).unwrap_or_default() {
+                max = max.max(levels[succ]);
+            }
+
+            levels[node] = wcet + max;
+            sequential_time += wcet;
+            critical_path = critical_path.max(levels[node]);
+        }
+
+        let processors: Vec<ProcessorMetrics> = schedule
+            .processors
+            .iter()
+            .map(|processor| {
+                let mut busy_time = 0.0;
+                let mut idle_gaps = Vec::new();
+                let mut time = 0.0;
+
+                for time_slot in &processor.time_slots {
+                    if time_slot.get_start_time() > time {
+                        idle_gaps.push((time, time_slot.get_start_time()));
+                    }
+
+                    busy_time += time_slot.get_completion_time() - time_slot.get_start_time();
+                    time = time_slot.get_completion_time();
+                }
+
+                if makespan > time {
+                    idle_gaps.push((time, makespan));
+                }
+
+                ProcessorMetrics {
+                    busy_time,
+                    utilisation: ratio(busy_time, makespan),
+                    idle_gaps,
+                }
+            })
+            .collect();
+
+        let nb_processors = processors.len() as f64;
+        let total_busy_time: f64 = processors.iter().map(|p| p.busy_time).sum();
+        let max_busy_time = processors.iter().map(|p| p.busy_time).fold(0.0, f64::max);
+
+        let speedup = ratio(sequential_time, makespan);
+
+        ScheduleMetrics {
+            makespan,
+            sequential_time,
+            critical_path,
+            speedup,
+            efficiency: ratio(speedup, nb_processors),
+            slr: ratio(makespan, critical_path),
+            load_imbalance: ratio(max_busy_time, ratio(total_busy_time, nb_processors)),
+            processors,
+        }
+    }
+
+    /// Returns the time the processors spend without task before the end of
+    /// the `Schedule`.
+    pub fn get_idle_time(&self) -> f64 {
+        self.processors
+            .iter()
+            .flat_map(|p| p.idle_gaps.iter())
+            .map(|(start, end)| end - start)
+            .sum()
+    }
+
+    /// Returns the metrics as a CSV line, with the columns of `CSV_HEADER`.
+    ///
+    /// # Arguments
+    /// * `algorithm` - The name of the algorithm which made the `Schedule`
+    pub fn to_csv(&self, algorithm: &str) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{},{}",
+            algorithm,
+            self.processors.len(),
+            self.makespan,
+            self.sequential_time,
+            self.critical_path,
+            self.speedup,
+            self.efficiency,
+            self.slr,
+            self.get_idle_time(),
+            self.load_imbalance
+        )
+    }
+}
+
+impl Display for ScheduleMetrics {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        writeln!(fmt, "makespan: {:.6}", self.makespan)?;
+        writeln!(fmt, "sequential time: {:.6}", self.sequential_time)?;
+        writeln!(fmt, "critical path: {:.6}", self.critical_path)?;
+        writeln!(fmt, "speedup: {:.3}", self.speedup)?;
+        writeln!(fmt, "efficiency: {:.3}", self.efficiency)?;
+        writeln!(fmt, "SLR: {:.3}", self.slr)?;
+        writeln!(fmt, "load imbalance: {:.3}", self.load_imbalance)?;
+
+        for (i, processor) in self.processors.iter().enumerate() {
+            writeln!(
+                fmt,
+                "processor {}: utilisation {:.1}%, {} idle gaps",
+                i,
+                processor.utilisation * 100.0,
+                processor.idle_gaps.len()
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod metrics_test {
+    use super::*;
+    use task_graph::task::Task;
+
+    #[test]
+    fn test_metrics() {
+        let mut g = TaskGraph::new(4, 3);
+
+        for _ in 0..4 {
+            g.add_task(Task::Constant(1.0));
+        }
+
+        g.add_edge(0, 1);
+        g.add_edge(0, 2);
+        g.add_edge(1, 3);
+
+        let mut sche = Schedule::new();
+
+        sche.add_processor();
+        sche.add_processor();
+        sche.processors[0].add_timeslot(0, 0.0, 1.0);
+        sche.processors[0].add_timeslot(1, 1.0, 2.0);
+        sche.processors[0].add_timeslot(3, 2.0, 3.0);
+        sche.processors[1].add_timeslot(2, 1.5, 2.5);
+
+        let metrics = ScheduleMetrics::new(&mut g, &sche);
+
+        assert_eq!(metrics.makespan, 3.0);
+        assert_eq!(metrics.sequential_time, 4.0);
+        assert_eq!(metrics.critical_path, 3.0);
+        assert_eq!(metrics.slr, 1.0);
+        assert_eq!(metrics.efficiency, metrics.speedup / 2.0);
+        assert_eq!(metrics.load_imbalance, 1.5);
+        assert_eq!(metrics.processors[0].utilisation, 1.0);
+        assert_eq!(
+            metrics.processors[1].idle_gaps,
+            vec![(0.0, 1.5), (2.5, 3.0)]
+        );
+        assert_eq!(metrics.get_idle_time(), 2.0);
+        assert!(metrics.to_csv("test").starts_with("test,2,3,4,3,"));
+    }
+}
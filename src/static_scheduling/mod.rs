@@ -1,4 +1,11 @@
 pub mod algorithms;
+pub mod cost_model;
+pub mod gantt;
+pub mod metrics;
+pub mod platform;
 pub mod processor;
 pub mod schedule;
+pub mod schedule_file;
 pub mod timeslot;
+pub mod topology;
+pub mod validation;
@@ -0,0 +1,349 @@
+//! This module implements the model of the processors a `TaskGraph` is
+//! scheduled on, which may run at different speeds.
+
+use std::collections::HashMap;
+
+use static_scheduling::topology::CpuTopology;
+use task_graph::graph::TaskGraph;
+
+/// The default speed of a processor sharing its physical core with another
+/// processor of the platform, relative to a processor alone on its core.
+pub const SMT_SPEED: f64 = 0.6;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// A processor of the `Platform`.
+pub struct ProcessorSpec {
+    pub core_id: Option<usize>, // The logical CPU the processor is pinned on
+    pub speed: f64,             // Relative to the speed the WCETs are given for
+}
+
+#[derive(Clone, Debug, Default)]
+/// The processors available for a static scheduling, the execution time of
+/// each node on each of them and the cost of sending a buffer from one of
+/// them to another.
+pub struct Platform {
+    pub processors: Vec<ProcessorSpec>,
+    // Execution times given for a (node, processor) couple, replacing the
+    // WCET of the node divided by the speed of the processor
+    execution_times: HashMap<(usize, usize), f64>,
+    // Communication costs given for a (source processor, destination
+    // processor) couple, for the edges without a cost of their own
+    communication_costs: HashMap<(usize, usize), f64>,
+}
+
+impl Platform {
+    /// Creates a `Platform` of identical processors which are not pinned on
+    /// a given CPU.
+    ///
+    /// # Arguments
+    /// * `nb_processors` - The number of processors
+    pub fn homogeneous(nb_processors: usize) -> Platform {
+        Platform::with_speeds(&vec![1.0; nb_processors])
+    }
+
+    /// Creates a `Platform` of processors with the given speeds.
+    ///
+    /// # Arguments
+    /// * `speeds` - The speed of each processor
+    pub fn with_speeds(speeds: &[f64]) -> Platform {
+        debug_assert!(
+            speeds.iter().all(|&speed| speed > 0.0),
+            "Platform::with_speeds: violated pre-condition"
+        );
+
+        Platform {
+            processors: speeds
+                .iter()
+                .map(|&speed| ProcessorSpec {
+                    core_id: None,
+                    speed,
+                })
+                .collect(),
+            execution_times: HashMap::new(),
+            communication_costs: HashMap::new(),
+        }
+    }
+
+    /// Creates a `Platform` with a processor pinned on each of the given
+    /// logical CPUs.
+    ///
+    /// The speed of a CPU is its capacity relative to the fastest CPU of the
+    /// machine, multiplied by `smt_speed` if another CPU of the list is on the
+    /// same physical core.
+    ///
+    /// # Arguments
+    /// * `topology` - The topology of the machine
+    /// * `cpus` - The logical CPUs to use
+    /// * `smt_speed` - The relative speed of CPUs sharing a physical core
+    pub fn from_topology(topology: &CpuTopology, cpus: &[usize], smt_speed: f64) -> Platform {
+        let max_capacity = topology
+            .cpus
+            .iter()
+            .filter_map(|cpu| cpu.capacity)
+            .max()
+            .unwrap_or(0);
+
+        let processors = cpus
+            .iter()
+            .map(|&cpu_id| {
+                let mut speed = match topology.get_cpu(cpu_id).and_then(|cpu| cpu.capacity) {
+                    Some(capacity) if max_capacity > 0 => {
+                        f64::from(capacity) / f64::from(max_capacity)
+                    }
+                    _ => 1.0,
+                };
+
+                let shares_core = cpus
+                    .iter()
+                    .any(|&other| other != cpu_id && topology.same_core(cpu_id, other));
+
+                if shares_core {
+                    speed *= smt_speed;
+                }
+
+                ProcessorSpec {
+                    core_id: Some(cpu_id),
+                    speed,
+                }
+            })
+            .collect();
+
+        Platform {
+            processors,
+            execution_times: HashMap::new(),
+            communication_costs: HashMap::new(),
+        }
+    }
+
+    /// Detects the `Platform` made of the first `nb_processors` logical CPUs
+    /// of the machine.
+    ///
+    /// # Arguments
+    /// * `nb_processors` - The number of processors
+    pub fn detect(nb_processors: usize) -> std::io::Result<Platform> {
+        let topology = CpuTopology::from_sysfs()?;
+        let cpus: Vec<usize> = topology
+            .cpus
+            .iter()
+            .take(nb_processors)
+            .map(|cpu| cpu.id)
+            .collect();
+
+        Ok(Platform::from_topology(&topology, &cpus, SMT_SPEED))
+    }
+
+    /// Detects the `Platform` made of the given logical CPUs of the machine.
+    ///
+    /// # Arguments
+    /// * `cpus` - The logical CPUs the processors are pinned on
+    pub fn detect_on(cpus: &[usize]) -> std::io::Result<Platform> {
+        let topology = CpuTopology::from_sysfs()?;
+
+        Ok(Platform::from_topology(&topology, cpus, SMT_SPEED))
+    }
+
+    /// Returns the number of processors of the `Platform`.
+    pub fn get_nb_processor(&self) -> usize {
+        self.processors.len()
+    }
+
+    /// Returns the logical CPUs the processors are pinned on.
+    pub fn get_core_ids(&self) -> Vec<usize> {
+        self.processors
+            .iter()
+            .filter_map(|processor| processor.core_id)
+            .collect()
+    }
+
+    /// Sets the execution time of a node on a processor, replacing the one
+    /// computed from its WCET.
+    ///
+    /// # Arguments
+    /// * `node_index` - The index of the node
+    /// * `proc_index` - The index of the processor
+    /// * `time` - The execution time of the node on the processor
+    pub fn set_execution_time(&mut self, node_index: usize, proc_index: usize, time: f64) {
+        self.execution_times.insert((node_index, proc_index), time);
+    }
+
+    /// Returns the execution time of a node on a processor.
+    ///
+    /// # Arguments
+    /// * `graph` - The graph of the node
+    /// * `node_index` - The index of the node
+    /// * `proc_index` - The index of the processor
+    pub fn get_execution_time(
+        &self,
+        graph: &mut TaskGraph,
+        node_index: usize,
+        proc_index: usize,
+    ) -> f64 {
+        match self.execution_times.get(&(node_index, proc_index)) {
+            Some(&time) => time,
+            None => graph.get_wcet(node_index).unwrap() / self.processors[proc_index].speed,
+        }
+    }
+
+    /// Sets the cost of sending the data of an edge from a processor to
+    /// another one, for the edges without a communication cost in the graph.
+    ///
+    /// # Arguments
+    /// * `src_proc` - The index of the processor producing the data
+    /// * `dst_proc` - The index of the processor consuming the data
+    /// * `cost` - The communication cost
+    pub fn set_communication_cost(&mut self, src_proc: usize, dst_proc: usize, cost: f64) {
+        self.communication_costs.insert((src_proc, dst_proc), cost);
+    }
+
+    /// Returns the communication cost of an edge whose nodes are executed on
+    /// the given processors: nothing on the same processor, else the cost of
+    /// the edge in the graph if it has one, else the cost between the two
+    /// processors.
+    ///
+    /// # Arguments
+    /// * `graph` - The graph of the edge
+    /// * `src_node` - The source of the edge
+    /// * `dst_node` - The destination of the edge
+    /// * `src_proc` - The index of the processor of the source
+    /// * `dst_proc` - The index of the processor of the destination
+    pub fn get_communication_cost(
+        &self,
+        graph: &TaskGraph,
+        src_node: usize,
+        dst_node: usize,
+        src_proc: usize,
+        dst_proc: usize,
+    ) -> f64 {
+        if src_proc == dst_proc {
+            return 0.0;
+        }
+
+        graph
+            .get_communication_cost(src_node, dst_node)
+            .or_else(|| self.communication_costs.get(&(src_proc, dst_proc)).cloned())
+            .unwrap_or(0.0)
+    }
+
+    /// Returns the average communication cost of an edge whose nodes are
+    /// executed on two distinct processors.
+    ///
+    /// # Arguments
+    /// * `graph` - The graph of the edge
+    /// * `src_node` - The source of the edge
+    /// * `dst_node` - The destination of the edge
+    pub fn get_average_communication_cost(
+        &self,
+        graph: &TaskGraph,
+        src_node: usize,
+        dst_node: usize,
+    ) -> f64 {
+        let nb_processors = self.get_nb_processor();
+
+        if nb_processors < 2 {
+            return graph
+                .get_communication_cost(src_node, dst_node)
+                .unwrap_or(0.0);
+        }
+
+        let mut total = 0.0;
+
+        for src_proc in 0..nb_processors {
+            for dst_proc in (0..nb_processors).filter(|&dst_proc| dst_proc != src_proc) {
+                total += self.get_communication_cost(graph, src_node, dst_node, src_proc, dst_proc);
+            }
+        }
+
+        total / (nb_processors * (nb_processors - 1)) as f64
+    }
+
+    /// Returns the average execution time of a node over the processors.
+    ///
+    /// # Arguments
+    /// * `graph` - The graph of the node
+    /// * `node_index` - The index of the node
+    pub fn get_average_execution_time(&self, graph: &mut TaskGraph, node_index: usize) -> f64 {
+        let nb_processors = self.get_nb_processor();
+
+        (0..nb_processors)
+            .map(|proc_index| self.get_execution_time(graph, node_index, proc_index))
+            .sum::<f64>()
+            / nb_processors as f64
+    }
+}
+
+#[cfg(test)]
+mod platform_test {
+    use super::*;
+    use static_scheduling::topology::Cpu;
+    use task_graph::task::Task;
+
+    #[test]
+    fn test_execution_time() {
+        let mut g = TaskGraph::new(2, 0);
+
+        g.add_task(Task::Constant(2.0));
+        g.add_task(Task::Constant(1.0));
+
+        let mut platform = Platform::with_speeds(&[1.0, 0.5]);
+        platform.set_execution_time(1, 1, 3.0);
+
+        assert_eq!(platform.get_execution_time(&mut g, 0, 0), 2.0);
+        assert_eq!(platform.get_execution_time(&mut g, 0, 1), 4.0);
+        assert_eq!(platform.get_execution_time(&mut g, 1, 1), 3.0);
+        assert_eq!(platform.get_average_execution_time(&mut g, 0), 3.0);
+    }
+
+    #[test]
+    fn test_communication_cost() {
+        let mut g = TaskGraph::new(3, 2);
+
+        for _ in 0..3 {
+            g.add_task(Task::Constant(1.0));
+        }
+
+        g.add_edge(0, 1);
+        g.add_edge(1, 2);
+        g.set_communication_cost(1, 2, 0.5);
+
+        let mut platform = Platform::homogeneous(3);
+        platform.set_communication_cost(0, 1, 1.0);
+        platform.set_communication_cost(1, 0, 1.0);
+        platform.set_communication_cost(0, 2, 4.0);
+
+        assert_eq!(platform.get_communication_cost(&g, 0, 1, 0, 0), 0.0);
+        assert_eq!(platform.get_communication_cost(&g, 0, 1, 0, 1), 1.0);
+        assert_eq!(platform.get_communication_cost(&g, 0, 1, 0, 2), 4.0);
+        assert_eq!(platform.get_communication_cost(&g, 0, 1, 1, 2), 0.0);
+        // The cost given in the graph is kept
+        assert_eq!(platform.get_communication_cost(&g, 1, 2, 0, 2), 0.5);
+        assert_eq!(platform.get_average_communication_cost(&g, 0, 1), 1.0);
+        assert_eq!(platform.get_average_communication_cost(&g, 1, 2), 0.5);
+    }
+
+    #[test]
+    fn test_from_topology() {
+        // A big core with two hardware threads and two little cores
+        let cpus = vec![(0, 0, 1024), (1, 0, 1024), (2, 1, 512), (3, 2, 512)]
+            .into_iter()
+            .map(|(id, core_id, capacity)| Cpu {
+                id,
+                core_id,
+                package_id: 0,
+                capacity: Some(capacity),
+                caches: Vec::new(),
+            })
+            .collect();
+        let topology = CpuTopology { cpus };
+
+        let platform = Platform::from_topology(&topology, &[0, 2, 3], 0.5);
+
+        assert_eq!(platform.get_core_ids(), vec![0, 2, 3]);
+        assert_eq!(platform.processors[0].speed, 1.0);
+        assert_eq!(platform.processors[1].speed, 0.5);
+
+        let platform = Platform::from_topology(&topology, &[0, 1], 0.5);
+
+        assert_eq!(platform.processors[0].speed, 0.5);
+        assert_eq!(platform.processors[1].speed, 0.5);
+    }
+}
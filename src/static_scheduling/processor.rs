@@ -7,6 +7,7 @@ use std::fmt::{Display, Error, Formatter};
 /// Ordered list of the tasks which will be executed on one `Processor`.
 pub struct Processor {
     pub time_slots: Vec<TimeSlot>,
+    pub core_id: Option<usize>, // The logical CPU executing the `Processor`, if it is pinned
     completion_time: f64,
 }
 
@@ -5,13 +5,17 @@ use std::fs::File;
 use std::io::prelude::*;
 use std::path::Path;
 
+use static_scheduling::gantt::{render_svg, GanttOptions};
+use static_scheduling::platform::Platform;
 use static_scheduling::processor::Processor;
 use static_scheduling::timeslot::TimeSlot;
+use task_graph::graph::TaskGraph;
 
 #[derive(Clone, Default)]
 /// A list of the `Processor`s which will execute the tasks of the `Schedule`
 pub struct Schedule {
     pub processors: Vec<Processor>,
+    pub algorithm: Option<String>, // The name of the algorithm which made the `Schedule`
 }
 
 impl Schedule {
@@ -20,6 +24,22 @@ impl Schedule {
         Schedule::default()
     }
 
+    /// Returns a `Schedule` with an empty `Processor` for each processor of
+    /// the `Platform`, pinned on the same logical CPU.
+    ///
+    /// # Arguments
+    /// * `platform` - The processors to schedule on
+    pub fn with_platform(platform: &Platform) -> Schedule {
+        let mut schedule = Schedule::new();
+
+        for spec in &platform.processors {
+            let proc_index = schedule.add_processor();
+            schedule.processors[proc_index].core_id = spec.core_id;
+        }
+
+        schedule
+    }
+
     /// Adds an empty `Processor` and returns the previous number of `Processor`s in the `Schedule`.
     pub fn add_processor(&mut self) -> usize {
         self.processors.push(Processor::new());
@@ -92,6 +112,25 @@ impl Schedule {
         write!(file, "{}", out_file)
     }
 
+    /// Writes the Gantt chart of the `Schedule` in `tmp/<filename>.svg`.
+    ///
+    /// # Arguments
+    /// * `graph` - The `TaskGraph` which was scheduled, for the names of the nodes
+    /// * `filename` - The name of the file, without extension
+    /// * `options` - How to draw the chart
+    pub fn output_svg(
+        &self,
+        graph: &TaskGraph,
+        filename: &str,
+        options: &GanttOptions,
+    ) -> Result<(), std::io::Error> {
+        std::fs::create_dir_all("tmp")?;
+
+        let mut file = File::create(format!("tmp/{}.svg", filename))?;
+
+        write!(file, "{}", render_svg(graph, self, options))
+    }
+
     /// Returns the `TimeSlot`, containing one of the `predecessors`, with the latest completion time.
     ///
     /// # Arguments
@@ -0,0 +1,265 @@
+//! This module saves a static scheduling in a text file and loads it back, so
+//! that a schedule can be computed once, edited by hand and replayed.
+//!
+//! The file lists the processors and their time slots:
+//!
+//! ```text
+//! graph 84e2a1f0c3d5b697
+//! algorithm etf
+//! processor 2
+//! 0 0.0 0.0001 # osc1
+//! 3 0.0001 0.0002 # sink
+//! processor
+//! 1 0.0 0.00005 # mod1
+//! ```
+//!
+//! A `processor` line may give the logical CPU the processor is pinned on and
+//! each slot gives the index of a node, its start and completion times and,
+//! as a comment, the name of the node.
+
+use std::fmt::{Display, Error as FmtError, Formatter};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use static_scheduling::schedule::Schedule;
+use task_graph::graph::TaskGraph;
+
+/// The extension of the schedule files.
+pub const EXTENSION: &str = "sched";
+
+#[derive(Debug)]
+/// The errors which can happen when loading a schedule file.
+pub enum ScheduleFileError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+    GraphMismatch { expected: u64, found: u64 }, // The schedule is for another graph
+}
+
+impl Display for ScheduleFileError {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), FmtError> {
+        match self {
+            ScheduleFileError::Io(e) => write!(fmt, "{}", e),
+            ScheduleFileError::Parse { line, message } => write!(fmt, "line {}: {}", line, message),
+            ScheduleFileError::GraphMismatch { expected, found } => write!(
+                fmt,
+                "the schedule was made for the graph {:016x}, not for {:016x}",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for ScheduleFileError {
+    fn from(e: io::Error) -> ScheduleFileError {
+        ScheduleFileError::Io(e)
+    }
+}
+
+/// Returns the content of the schedule file of a `Schedule`.
+///
+/// # Arguments
+/// * `graph` - The `TaskGraph` which was scheduled
+/// * `schedule` - The `Schedule` to save
+pub fn write_schedule(graph: &TaskGraph, schedule: &Schedule) -> String {
+    let mut out_file = format!("graph {:016x}\n", graph.get_hash());
+
+    if let Some(ref algorithm) = schedule.algorithm {
+        out_file.push_str(&format!("algorithm {}\n", algorithm));
+    }
+
+    for processor in &schedule.processors {
+        match processor.core_id {
+            Some(core_id) => out_file.push_str(&format!("processor {}\n", core_id)),
+            None => out_file.push_str("processor\n"),
+        }
+
+        for slot in &processor.time_slots {
+            out_file.push_str(&format!(
+                "{} {:?} {:?} # {}\n",
+                slot.get_node(),
+                slot.get_start_time(),
+                slot.get_completion_time(),
+                graph.get_node_name(slot.get_node())
+            ));
+        }
+    }
+
+    out_file
+}
+
+/// Parses the content of a schedule file, refusing a `Schedule` made for
+/// another graph.
+///
+/// # Arguments
+/// * `graph` - The `TaskGraph` to execute with the `Schedule`
+/// * `content` - The content of the schedule file
+pub fn read_schedule(graph: &TaskGraph, content: &str) -> Result<Schedule, ScheduleFileError> {
+    let mut schedule = Schedule::new();
+    let mut hash = None;
+
+    for (i, line) in content.lines().enumerate() {
+        let line_number = i + 1;
+        let parse_error = |message: &str| ScheduleFileError::Parse {
+            line: line_number,
+            message: message.to_string(),
+        };
+
+        let line = line.split('#').next().unwrap().trim();
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        match words.as_slice() {
+            [] => {}
+            ["graph", value] => {
+                hash = Some(
+                    u64::from_str_radix(value, 16).map_err(|_| parse_error("bad graph hash"))?,
+                );
+            }
+            ["algorithm", name] => schedule.algorithm = Some(name.to_string()),
+            ["processor"] => {
+                schedule.add_processor();
+            }
+            ["processor", core_id] => {
+                let proc_index = schedule.add_processor();
+                schedule.processors[proc_index].core_id =
+                    Some(core_id.parse().map_err(|_| parse_error("bad core id"))?);
+            }
+            [node, start, completion] => {
+                let node: usize = node.parse().map_err(|_| parse_error("bad node index"))?;
+                let start: f64 = start.parse().map_err(|_| parse_error("bad start time"))?;
+                let completion: f64 = completion
+                    .parse()
+                    .map_err(|_| parse_error("bad completion time"))?;
+
+                if node >= graph.get_nb_node() {
+                    return Err(parse_error("no such node in the graph"));
+                }
+
+                if start < 0.0 || completion < start {
+                    return Err(parse_error("bad time slot"));
+                }
+
+                let processor = schedule
+                    .processors
+                    .last_mut()
+                    .ok_or_else(|| parse_error("time slot outside of a processor"))?;
+
+                if !processor.add_timeslot(node, start, completion) {
+                    return Err(parse_error("time slot before the end of the previous one"));
+                }
+            }
+            _ => return Err(parse_error("unexpected line")),
+        }
+    }
+
+    let expected = graph.get_hash();
+
+    match hash {
+        None => Err(ScheduleFileError::Parse {
+            line: 0,
+            message: "missing graph hash".to_string(),
+        }),
+        Some(found) if found != expected => {
+            Err(ScheduleFileError::GraphMismatch { expected, found })
+        }
+        Some(_) => Ok(schedule),
+    }
+}
+
+/// Saves a `Schedule` in a schedule file.
+///
+/// # Arguments
+/// * `graph` - The `TaskGraph` which was scheduled
+/// * `schedule` - The `Schedule` to save
+/// * `path` - The path of the file
+pub fn save_schedule<P: AsRef<Path>>(
+    graph: &TaskGraph,
+    schedule: &Schedule,
+    path: P,
+) -> io::Result<()> {
+    fs::write(path, write_schedule(graph, schedule))
+}
+
+/// Loads a `Schedule` from a schedule file, refusing a `Schedule` made for
+/// another graph.
+///
+/// # Arguments
+/// * `graph` - The `TaskGraph` to execute with the `Schedule`
+/// * `path` - The path of the file
+pub fn load_schedule<P: AsRef<Path>>(
+    graph: &TaskGraph,
+    path: P,
+) -> Result<Schedule, ScheduleFileError> {
+    read_schedule(graph, &fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod schedule_file_test {
+    use super::*;
+    use static_scheduling::algorithms::etf;
+    use task_graph::task::Task;
+
+    fn get_graph(nb_nodes: usize) -> TaskGraph {
+        let mut g = TaskGraph::new(nb_nodes, nb_nodes - 1);
+
+        for _ in 0..nb_nodes {
+            g.add_task(Task::Constant(1.0));
+        }
+
+        for i in 1..nb_nodes {
+            g.add_edge(0, i);
+        }
+
+        g
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut g = get_graph(4);
+
+        let mut sche = etf(&mut g, 2);
+        sche.algorithm = Some("etf".to_string());
+        sche.processors[1].core_id = Some(3);
+
+        let loaded = read_schedule(&g, &write_schedule(&g, &sche)).unwrap();
+
+        assert_eq!(loaded.algorithm, sche.algorithm);
+        assert_eq!(loaded.get_nb_processor(), 2);
+        assert_eq!(loaded.processors[0].core_id, None);
+        assert_eq!(loaded.processors[1].core_id, Some(3));
+
+        for (loaded_proc, proc) in loaded.processors.iter().zip(sche.processors.iter()) {
+            assert!(loaded_proc.time_slots == proc.time_slots);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let g = get_graph(4);
+        let other = get_graph(5);
+
+        let content = write_schedule(&other, &Schedule::new());
+
+        match read_schedule(&g, &content) {
+            Err(ScheduleFileError::GraphMismatch { .. }) => {}
+            _ => panic!("the graph hash is not checked"),
+        }
+
+        let content = format!("graph {:016x}\n0 0.0 1.0\n", g.get_hash());
+
+        match read_schedule(&g, &content) {
+            Err(ScheduleFileError::Parse { line: 2, .. }) => {}
+            _ => panic!("a time slot outside of a processor is accepted"),
+        }
+
+        let content = format!(
+            "graph {:016x}\nprocessor\n0 0.0 1.0\n1 0.5 1.5\n",
+            g.get_hash()
+        );
+
+        match read_schedule(&g, &content) {
+            Err(ScheduleFileError::Parse { line: 4, .. }) => {}
+            _ => panic!("overlapping time slots are accepted"),
+        }
+    }
+}
@@ -10,11 +10,12 @@ pub struct TimeSlot {
 }
 
 impl TimeSlot {
-    /// Creates a new `TimeSlot` for a task.
+    /// Creates a new `TimeSlot` for a task. The `TimeSlot` may be empty for
+    /// the dummy tasks of zero cost of the benchmark graphs.
     pub fn new(node: usize, start_time: f64, completion_time: f64) -> TimeSlot {
         // Check pre-condition
         debug_assert!(
-            start_time < completion_time,
+            start_time <= completion_time,
             "TimeSlot::new() : completions < start"
         );
 
@@ -0,0 +1,251 @@
+//! This module reads the topology of the CPUs of the machine: which logical CPUs
+//! belong to the same physical core and which caches they share.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The default location of the CPU topology on Linux.
+pub const SYSFS_CPU_PATH: &str = "/sys/devices/system/cpu";
+
+#[derive(Clone, Debug, PartialEq)]
+/// A cache of the CPU and the logical CPUs sharing it.
+pub struct Cache {
+    pub level: u32,
+    pub shared_cpus: Vec<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A logical CPU.
+pub struct Cpu {
+    pub id: usize,
+    pub core_id: usize,
+    pub package_id: usize,
+    pub capacity: Option<u32>, // Relative speed of the CPU on heterogeneous machines
+    pub caches: Vec<Cache>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+/// The list of the logical CPUs of the machine.
+pub struct CpuTopology {
+    pub cpus: Vec<Cpu>,
+}
+
+/// Parses a list of CPUs in the sysfs format, e.g. `0-3,8,10-11`.
+///
+/// # Arguments
+/// * `list` - The list to parse
+pub fn parse_cpu_list(list: &str) -> Option<Vec<usize>> {
+    let mut cpus = Vec::new();
+
+    for range in list.trim().split(',').filter(|range| !range.is_empty()) {
+        let mut bounds = range.splitn(2, '-');
+        let start: usize = bounds.next()?.trim().parse().ok()?;
+        let end: usize = match bounds.next() {
+            Some(end) => end.trim().parse().ok()?,
+            None => start,
+        };
+
+        cpus.extend(start..=end);
+    }
+
+    Some(cpus)
+}
+
+// Reads a file of the sysfs and returns its trimmed content
+fn read_value(path: &Path) -> io::Result<String> {
+    fs::read_to_string(path).map(|content| content.trim().to_string())
+}
+
+// Returns an error for a malformed sysfs file
+fn invalid_data(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("malformed topology file: {}", path.display()),
+    )
+}
+
+impl CpuTopology {
+    /// Reads the topology of the machine from the sysfs.
+    pub fn from_sysfs() -> io::Result<CpuTopology> {
+        CpuTopology::from_path(SYSFS_CPU_PATH)
+    }
+
+    /// Reads the topology from a directory organized like
+    /// `/sys/devices/system/cpu`.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the directory
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<CpuTopology> {
+        let path = path.as_ref();
+        let online_path = path.join("online");
+        let online =
+            parse_cpu_list(&read_value(&online_path)?).ok_or_else(|| invalid_data(&online_path))?;
+
+        let mut cpus = Vec::with_capacity(online.len());
+
+        for id in online {
+            let cpu_path = path.join(format!("cpu{}", id));
+            let topology_path = cpu_path.join("topology");
+
+            // Missing identifiers are not fatal, every CPU is then on its own
+            let core_id = read_value(&topology_path.join("core_id"))
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(id);
+            let package_id = read_value(&topology_path.join("physical_package_id"))
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(0);
+            let capacity = read_value(&cpu_path.join("cpu_capacity"))
+                .ok()
+                .and_then(|value| value.parse().ok());
+
+            let mut caches = Vec::new();
+
+            if let Ok(entries) = fs::read_dir(cpu_path.join("cache")) {
+                for entry in entries {
+                    let cache_path = entry?.path();
+
+                    let is_index = cache_path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .map(|name| name.starts_with("index"))
+                        .unwrap_or(false);
+
+                    if !is_index {
+                        continue;
+                    }
+
+                    let level_path = cache_path.join("level");
+                    let shared_path = cache_path.join("shared_cpu_list");
+
+                    let level = read_value(&level_path)?
+                        .parse()
+                        .map_err(|_| invalid_data(&level_path))?;
+                    let shared_cpus = parse_cpu_list(&read_value(&shared_path)?)
+                        .ok_or_else(|| invalid_data(&shared_path))?;
+
+                    caches.push(Cache { level, shared_cpus });
+                }
+            }
+
+            caches.sort_by_key(|cache| cache.level);
+
+            cpus.push(Cpu {
+                id,
+                core_id,
+                package_id,
+                capacity,
+                caches,
+            });
+        }
+
+        Ok(CpuTopology { cpus })
+    }
+
+    /// Returns the logical CPU with the given id if there is one.
+    ///
+    /// # Arguments
+    /// * `cpu_id` - The id of the logical CPU
+    pub fn get_cpu(&self, cpu_id: usize) -> Option<&Cpu> {
+        self.cpus.iter().find(|cpu| cpu.id == cpu_id)
+    }
+
+    /// Returns `true` if the two logical CPUs are on the same physical core.
+    ///
+    /// # Arguments
+    /// * `cpu_a` - The id of the first logical CPU
+    /// * `cpu_b` - The id of the second logical CPU
+    pub fn same_core(&self, cpu_a: usize, cpu_b: usize) -> bool {
+        if cpu_a == cpu_b {
+            return true;
+        }
+
+        match (self.get_cpu(cpu_a), self.get_cpu(cpu_b)) {
+            (Some(a), Some(b)) => a.core_id == b.core_id && a.package_id == b.package_id,
+            _ => false,
+        }
+    }
+
+    /// Returns the logical CPUs on the same physical core as the given one,
+    /// itself included.
+    ///
+    /// # Arguments
+    /// * `cpu_id` - The id of the logical CPU
+    pub fn get_siblings(&self, cpu_id: usize) -> Vec<usize> {
+        self.cpus
+            .iter()
+            .filter(|cpu| self.same_core(cpu_id, cpu.id))
+            .map(|cpu| cpu.id)
+            .collect()
+    }
+
+    /// Returns the level of the closest cache shared by the two logical CPUs
+    /// if there is one.
+    ///
+    /// # Arguments
+    /// * `cpu_a` - The id of the first logical CPU
+    /// * `cpu_b` - The id of the second logical CPU
+    pub fn shared_cache_level(&self, cpu_a: usize, cpu_b: usize) -> Option<u32> {
+        self.get_cpu(cpu_a)?
+            .caches
+            .iter()
+            .find(|cache| cache.shared_cpus.contains(&cpu_b))
+            .map(|cache| cache.level)
+    }
+}
+
+#[cfg(test)]
+mod topology_test {
+    use super::*;
+
+    fn write_file(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(parse_cpu_list("0"), Some(vec![0]));
+        assert_eq!(
+            parse_cpu_list("0-3,8,10-11\n"),
+            Some(vec![0, 1, 2, 3, 8, 10, 11])
+        );
+        assert_eq!(parse_cpu_list(""), Some(vec![]));
+        assert_eq!(parse_cpu_list("a-3"), None);
+    }
+
+    #[test]
+    fn test_from_path() {
+        let root = std::env::temp_dir().join(format!("agp_topology_{}", std::process::id()));
+
+        write_file(&root.join("online"), "0-3\n");
+
+        // Two physical cores with two hardware threads each, sharing the L3
+        for cpu in 0..4 {
+            let cpu_path = root.join(format!("cpu{}", cpu));
+            let siblings = if cpu < 2 { "0-1" } else { "2-3" };
+
+            write_file(&cpu_path.join("topology/core_id"), &format!("{}", cpu / 2));
+            write_file(&cpu_path.join("topology/physical_package_id"), "0");
+            write_file(&cpu_path.join("cpu_capacity"), "1024");
+            write_file(&cpu_path.join("cache/index0/level"), "1");
+            write_file(&cpu_path.join("cache/index0/shared_cpu_list"), siblings);
+            write_file(&cpu_path.join("cache/index3/level"), "3");
+            write_file(&cpu_path.join("cache/index3/shared_cpu_list"), "0-3");
+        }
+
+        let topology = CpuTopology::from_path(&root).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(topology.cpus.len(), 4);
+        assert!(topology.same_core(0, 1));
+        assert!(!topology.same_core(1, 2));
+        assert_eq!(topology.get_siblings(3), vec![2, 3]);
+        assert_eq!(topology.cpus[0].capacity, Some(1024));
+        assert_eq!(topology.shared_cache_level(0, 1), Some(1));
+        assert_eq!(topology.shared_cache_level(1, 2), Some(3));
+        assert_eq!(topology.shared_cache_level(1, 7), None);
+    }
+}
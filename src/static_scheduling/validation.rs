@@ -0,0 +1,387 @@
+//! This module checks a static scheduling against the `TaskGraph` it was
+//! computed for and lists what is wrong with it.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Error, Formatter};
+
+use static_scheduling::platform::Platform;
+use static_scheduling::schedule::Schedule;
+use task_graph::graph::TaskGraph;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// A reason for a `Schedule` not to be executable as is.
+pub enum Violation {
+    /// The node is not scheduled on any processor
+    Missing { node: usize },
+    /// The node is scheduled several times on the same processor
+    Duplicated { node: usize, processor: usize },
+    /// The time slot of the node ends before it starts
+    NegativeDuration { node: usize, processor: usize },
+    /// The time slot of the node is longer than the execution time of the
+    /// node on the processor
+    Overrun {
+        node: usize,
+        processor: usize,
+        duration: f64,
+        execution_time: f64,
+    },
+    /// The time slots of two nodes overlap on the processor
+    Overlap {
+        processor: usize,
+        first: usize,
+        second: usize,
+    },
+    /// The node starts before the data of one of its predecessors is
+    /// available on the processor
+    Precedence {
+        node: usize,
+        predecessor: usize,
+        processor: usize,
+    },
+}
+
+impl Display for Violation {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        match *self {
+            Violation::Missing { node } => write!(fmt, "node {} is not scheduled", node),
+            Violation::Duplicated { node, processor } => write!(
+                fmt,
+                "node {} is scheduled several times on processor {}",
+                node, processor
+            ),
+            Violation::NegativeDuration { node, processor } => write!(
+                fmt,
+                "node {} ends before it starts on processor {}",
+                node, processor
+            ),
+            Violation::Overrun {
+                node,
+                processor,
+                duration,
+                execution_time,
+            } => write!(
+                fmt,
+                "node {} lasts {} on processor {} instead of {}",
+                node, duration, processor, execution_time
+            ),
+            Violation::Overlap {
+                processor,
+                first,
+                second,
+            } => write!(
+                fmt,
+                "nodes {} and {} overlap on processor {}",
+                first, second, processor
+            ),
+            Violation::Precedence {
+                node,
+                predecessor,
+                processor,
+            } => write!(
+                fmt,
+                "node {} starts on processor {} before the data of node {} is available",
+                node, processor, predecessor
+            ),
+        }
+    }
+}
+
+// Returns the error allowed on the times of a `Schedule`, which are sums of
+// floats, relative to its makespan so that the WCETs of the DSPs, of a few µs,
+// are still checked
+fn get_tolerance(schedule: &Schedule) -> f64 {
+    1e-9 * schedule.get_completion_time().max(1.0)
+}
+
+/// Returns the violations of the `Schedule`, made for identical processors.
+///
+/// # Arguments
+/// * `graph` - The `TaskGraph` which was scheduled
+/// * `schedule` - The `Schedule` to check
+pub fn validate(graph: &mut TaskGraph, schedule: &Schedule) -> Vec<Violation> {
+    let platform = Platform::homogeneous(schedule.get_nb_processor());
+
+    validate_on(graph, schedule, &platform)
+}
+
+/// Returns the violations of the `Schedule`, the processors of the schedule
+/// running at the speeds of the processors of the `Platform`.
+///
+/// The communication cost of an edge between the processors of its nodes is
+/// paid when they are different, and a duplicated predecessor only needs one
+/// of its copies to send its data in time.
+///
+/// # Arguments
+/// * `graph` - The `TaskGraph` which was scheduled
+/// * `schedule` - The `Schedule` to check
+/// * `platform` - The processors the `TaskGraph` is scheduled on
+pub fn validate_on(
+    graph: &mut TaskGraph,
+    schedule: &Schedule,
+    platform: &Platform,
+) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let tolerance = get_tolerance(schedule);
+
+    // The processors and completion times of the copies of each node
+    let mut copies: HashMap<usize, Vec<(usize, f64)>> = HashMap::new();
+
+    for (proc_index, processor) in schedule.processors.iter().enumerate() {
+        for time_slot in &processor.time_slots {
+            copies
+                .entry(time_slot.get_node())
+                .or_default()
+                .push((proc_index, time_slot.get_completion_time()));
+        }
+    }
+
+    for node in graph.get_topological_order() {
+        if !copies.contains_key(&node) {
+            violations.push(Violation::Missing { node });
+        }
+    }
+
+    for (proc_index, processor) in schedule.processors.iter().enumerate() {
+        let mut time_slots = processor.time_slots.clone();
+        time_slots.sort_by(|a, b| a.get_start_time().partial_cmp(&b.get_start_time()).unwrap());
+
+        // The node of the earlier time slots which completes last, and when
+        let mut latest: Option<(usize, f64)> = None;
+
+        for (i, time_slot) in time_slots.iter().enumerate() {
+            let node = time_slot.get_node();
+            let start_time = time_slot.get_start_time();
+            let duration = time_slot.get_completion_time() - start_time;
+
+            if time_slots[..i].iter().any(|other| other.get_node() == node) {
+                violations.push(Violation::Duplicated {
+                    node,
+                    processor: proc_index,
+                });
+            }
+
+            if duration < 0.0 {
+                violations.push(Violation::NegativeDuration {
+                    node,
+                    processor: proc_index,
+                });
+            } else if graph.get_wcet(node).is_some() {
+                let execution_time = platform.get_execution_time(graph, node, proc_index);
+
+                if duration - execution_time > tolerance {
+                    violations.push(Violation::Overrun {
+                        node,
+                        processor: proc_index,
+                        duration,
+                        execution_time,
+                    });
+                }
+            }
+
+            if let Some((first, completion_time)) = latest {
+                if start_time < completion_time - tolerance {
+                    violations.push(Violation::Overlap {
+                        processor: proc_index,
+                        first,
+                        second: node,
+                    });
+                }
+            }
+
+            if latest.map_or(true, |(_, completion_time)| {
+                time_slot.get_completion_time() > completion_time
+            }) {
+                latest = Some((node, time_slot.get_completion_time()));
+            }
+
+            for predecessor in graph.get_predecessors(node).unwrap_or_default() {
+                // A missing predecessor is already reported
+                let ready_time = copies.get(&predecessor).and_then(|pred_copies| {
+                    pred_copies
+                        .iter()
+                        .map(|&(pred_proc, completion_time)| {
+                            completion_time
+                                + platform.get_communication_cost(
+                                    graph,
+                                    predecessor,
+                                    node,
+                                    pred_proc,
+                                    proc_index,
+                                )
+                        })
+                        .fold(None, |min: Option<f64>, time| {
+                            Some(min.map_or(time, |min| min.min(time)))
+                        })
+                });
+
+                if let Some(ready_time) = ready_time {
+                    if ready_time > start_time + tolerance {
+                        violations.push(Violation::Precedence {
+                            node,
+                            predecessor,
+                            processor: proc_index,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    violations
+}
+
+#[cfg(test)]
+mod validation_test {
+    use super::*;
+    use static_scheduling::timeslot::TimeSlot;
+    use task_graph::task::Task;
+
+    fn get_graph() -> TaskGraph {
+        // Room is left for a node added by a test
+        let mut g = TaskGraph::new(4, 2);
+
+        for _ in 0..3 {
+            g.add_task(Task::Constant(1.0));
+        }
+
+        g.add_edge(0, 1);
+        g.add_edge(0, 2);
+        g.set_communication_cost(0, 2, 0.5);
+
+        g
+    }
+
+    #[test]
+    fn test_valid() {
+        let mut g = get_graph();
+        let mut sche = Schedule::new();
+
+        sche.add_processor();
+        sche.add_processor();
+        sche.processors[0].add_timeslot(0, 0.0, 1.0);
+        sche.processors[0].add_timeslot(1, 1.0, 2.0);
+        sche.processors[1].add_timeslot(2, 1.5, 2.5);
+
+        assert_eq!(validate(&mut g, &sche), Vec::new());
+        assert!(g.is_valid_schedule(&sche));
+    }
+
+    #[test]
+    fn test_violations() {
+        let mut g = get_graph();
+        let mut sche = Schedule::new();
+
+        g.add_task(Task::Constant(3.0));
+
+        sche.add_processor();
+        sche.add_processor();
+        sche.add_processor();
+        sche.add_processor();
+        sche.processors[0].add_timeslot(0, 0.0, 1.0);
+        sche.processors[0].add_timeslot(0, 1.0, 2.0);
+        sche.processors[1].add_timeslot(2, 1.0, 2.0);
+
+        // Appending does not allow overlapping slots
+        sche.processors[2]
+            .time_slots
+            .push(TimeSlot::new(0, 0.0, 1.0));
+        sche.processors[2]
+            .time_slots
+            .push(TimeSlot::new(2, 0.5, 3.0));
+
+        // The last slot only overlaps the first one
+        sche.processors[3]
+            .time_slots
+            .push(TimeSlot::new(3, 0.0, 3.0));
+        sche.processors[3]
+            .time_slots
+            .push(TimeSlot::new(2, 1.5, 2.5));
+        sche.processors[3]
+            .time_slots
+            .push(TimeSlot::new(0, 2.5, 3.5));
+
+        assert_eq!(
+            validate(&mut g, &sche),
+            vec![
+                Violation::Missing { node: 1 },
+                Violation::Duplicated {
+                    node: 0,
+                    processor: 0,
+                },
+                Violation::Precedence {
+                    node: 2,
+                    predecessor: 0,
+                    processor: 1,
+                },
+                Violation::Overrun {
+                    node: 2,
+                    processor: 2,
+                    duration: 2.5,
+                    execution_time: 1.0,
+                },
+                Violation::Overlap {
+                    processor: 2,
+                    first: 0,
+                    second: 2,
+                },
+                Violation::Precedence {
+                    node: 2,
+                    predecessor: 0,
+                    processor: 2,
+                },
+                Violation::Overlap {
+                    processor: 3,
+                    first: 3,
+                    second: 2,
+                },
+                Violation::Overlap {
+                    processor: 3,
+                    first: 3,
+                    second: 0,
+                },
+            ]
+        );
+        assert!(!g.is_valid_schedule(&sche));
+    }
+
+    #[test]
+    fn test_tolerance() {
+        let mut g = TaskGraph::new(2, 1);
+        let mut sche = Schedule::new();
+
+        // The WCETs of DSPs
+        g.add_task(Task::Constant(1e-5));
+        g.add_task(Task::Constant(1e-5));
+        g.add_edge(0, 1);
+
+        sche.add_processor();
+        sche.processors[0]
+            .time_slots
+            .push(TimeSlot::new(0, 0.0, 1.5e-5));
+        sche.processors[0]
+            .time_slots
+            .push(TimeSlot::new(1, 1e-5, 2e-5));
+
+        assert_eq!(
+            validate(&mut g, &sche),
+            vec![
+                Violation::Overrun {
+                    node: 0,
+                    processor: 0,
+                    duration: 1.5e-5,
+                    execution_time: 1e-5,
+                },
+                Violation::Overlap {
+                    processor: 0,
+                    first: 0,
+                    second: 1,
+                },
+                Violation::Precedence {
+                    node: 1,
+                    predecessor: 0,
+                    processor: 0,
+                },
+            ]
+        );
+    }
+}
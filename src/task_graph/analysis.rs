@@ -0,0 +1,253 @@
+//! This module analyses the structure of a task graph in a single pass: the
+//! levels of its nodes, its critical path and how parallel it is.
+
+use task_graph::graph::TaskGraph;
+
+#[derive(Clone, Debug, PartialEq)]
+/// The levels of the nodes of a graph and the measures of its parallelism.
+pub struct GraphAnalysis {
+    pub t_levels: Vec<f64>,
+    pub b_levels: Vec<f64>,
+    pub static_levels: Vec<f64>,
+    // The nodes of a longest path, communication costs included, from an
+    // entry node to an exit node
+    pub critical_path: Vec<usize>,
+    pub critical_path_length: f64,
+    pub depth: usize,            // The number of nodes of the longest chain
+    pub width: usize,            // The size of the largest set of independent nodes
+    pub work: f64,               // The sum of the WCETs
+    pub span: f64,               // The length of the critical path without communications
+    pub level_sizes: Vec<usize>, // The number of nodes at each depth
+}
+
+impl GraphAnalysis {
+    /// Analyses a graph, the nodes whose WCET can not be estimated counting
+    /// as instantaneous.
+    ///
+    /// # Arguments
+    /// * `graph` - The graph to analyse
+    pub fn new(graph: &mut TaskGraph) -> GraphAnalysis {
+        let top_ord = graph.get_topological_order();
+        let nb_nodes = top_ord.len();
+
+        let wcets: Vec<f64> = (0..nb_nodes)
+            .map(|node_index| graph.get_wcet(node_index).unwrap_or(0.0))
+            .collect();
+        let predecessors: Vec<Vec<usize>> = (0..nb_nodes)
+            .map(|node_index| graph.get_predecessors(node_index).unwrap_or_default())
+            .collect();
+        let successors: Vec<Vec<usize>> = (0..nb_nodes)
+            .map(|node_index| graph.get_successors(node_index).unwrap_or_default())
+            .collect();
+        let cost = |src: usize, dst: usize| graph.get_communication_cost(src, dst).unwrap_or(0.0);
+
+        let mut t_levels = vec![0.0; nb_nodes];
+        let mut depths = vec![0; nb_nodes];
+
+        for &i in &top_ord {
+            for &x in &predecessors[i] {
+                t_levels[i] = f64::max(t_levels[i], t_levels[x] + wcets[x] + cost(x, i));
+                depths[i] = depths[i].max(depths[x] + 1);
+            }
+        }
+
+        let mut b_levels = vec![0.0; nb_nodes];
+        let mut static_levels = vec![0.0; nb_nodes];
+
+        for &i in top_ord.iter().rev() {
+            let mut max_b_level: f64 = 0.0;
+            let mut max_static_level: f64 = 0.0;
+
+            for &y in &successors[i] {
+                max_b_level = max_b_level.max(cost(i, y) + b_levels[y]);
+                max_static_level = max_static_level.max(static_levels[y]);
+            }
+
+            b_levels[i] = wcets[i] + max_b_level;
+            static_levels[i] = wcets[i] + max_static_level;
+        }
+
+        // The critical path starts at the entry node with the highest b-level
+        // and goes on through the successors on which it is reached
+        let mut critical_path = Vec::new();
+        let mut current = (0..nb_nodes).filter(|&i| predecessors[i].is_empty()).fold(
+            None,
+            |best: Option<usize>, i| match best {
+                Some(best) if b_levels[best] >= b_levels[i] => Some(best),
+                _ => Some(i),
+            },
+        );
+
+        while let Some(i) = current {
+            critical_path.push(i);
+
+            let remaining = b_levels[i] - wcets[i];
+
+            current = successors[i].iter().cloned().min_by(|&a, &b| {
+                let gap_a = (remaining - cost(i, a) - b_levels[a]).abs();
+                let gap_b = (remaining - cost(i, b) - b_levels[b]).abs();
+                gap_a.partial_cmp(&gap_b).unwrap()
+            });
+        }
+
+        let mut level_sizes = vec![0; depths.iter().max().map_or(0, |depth| depth + 1)];
+
+        for &depth in &depths {
+            level_sizes[depth] += 1;
+        }
+
+        GraphAnalysis {
+            critical_path_length: critical_path.first().map_or(0.0, |&i| b_levels[i]),
+            critical_path,
+            depth: level_sizes.len(),
+            width: get_width(&top_ord, &successors),
+            work: wcets.iter().sum(),
+            span: static_levels.iter().cloned().fold(0.0, f64::max),
+            t_levels,
+            b_levels,
+            static_levels,
+            level_sizes,
+        }
+    }
+
+    /// Returns the average parallelism of the graph: its work divided by its
+    /// span, or 0 if its span is null.
+    pub fn get_average_parallelism(&self) -> f64 {
+        if self.span > 0.0 {
+            self.work / self.span
+        } else {
+            0.0
+        }
+    }
+}
+
+// Returns the size of the largest antichain of a DAG, which is its number of
+// nodes minus a maximum matching of its transitive closure (Dilworth)
+fn get_width(top_ord: &[usize], successors: &[Vec<usize>]) -> usize {
+    let nb_nodes = top_ord.len();
+    let nb_words = (nb_nodes + 63) / 64;
+
+    // The descendants of each node, as bit sets
+    let mut reachable = vec![vec![0u64; nb_words]; nb_nodes];
+
+    for &i in top_ord.iter().rev() {
+        for &y in &successors[i] {
+            let descendants = reachable[y].clone();
+
+            for (bits, descendant_bits) in reachable[i].iter_mut().zip(descendants) {
+                *bits |= descendant_bits;
+            }
+
+            reachable[i][y / 64] |= 1 << (y % 64);
+        }
+    }
+
+    let descendants: Vec<Vec<usize>> = reachable
+        .iter()
+        .map(|bits| {
+            (0..nb_nodes)
+                .filter(|&y| bits[y / 64] & (1 << (y % 64)) != 0)
+                .collect()
+        })
+        .collect();
+
+    let mut matched: Vec<Option<usize>> = vec![None; nb_nodes];
+    let mut nb_matched = 0;
+
+    for i in 0..nb_nodes {
+        let mut visited = vec![false; nb_nodes];
+
+        if find_augmenting_path(i, &descendants, &mut matched, &mut visited) {
+            nb_matched += 1;
+        }
+    }
+
+    nb_nodes - nb_matched
+}
+
+// Looks for an augmenting path from a node in the bipartite graph of the
+// transitive closure (Kuhn's algorithm)
+fn find_augmenting_path(
+    node_index: usize,
+    descendants: &[Vec<usize>],
+    matched: &mut Vec<Option<usize>>,
+    visited: &mut Vec<bool>,
+) -> bool {
+    for &y in &descendants[node_index] {
+        if visited[y] {
+            continue;
+        }
+
+        visited[y] = true;
+
+        let is_free = match matched[y] {
+            None => true,
+            Some(other) => find_augmenting_path(other, descendants, matched, visited),
+        };
+
+        if is_free {
+            matched[y] = Some(node_index);
+            return true;
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod analysis_test {
+    use super::*;
+    use task_graph::task::Task;
+
+    #[test]
+    fn test_analysis() {
+        // 0 -> {1, 2, 3} -> 4, 5 alone
+        let mut g = TaskGraph::new(6, 6);
+
+        for &wcet in &[1.0, 3.0, 1.0, 1.0, 1.0, 2.0] {
+            g.add_task(Task::Constant(wcet));
+        }
+
+        g.add_edge(0, 1);
+        g.add_edge(0, 2);
+        g.add_edge(0, 3);
+        g.add_edge(1, 4);
+        g.add_edge(2, 4);
+        g.add_edge(3, 4);
+        g.set_communication_cost(0, 2, 4.0);
+
+        let analysis = GraphAnalysis::new(&mut g);
+
+        assert_eq!(analysis.t_levels, vec![0.0, 1.0, 5.0, 1.0, 6.0, 0.0]);
+        assert_eq!(analysis.b_levels, vec![7.0, 4.0, 2.0, 2.0, 1.0, 2.0]);
+        assert_eq!(analysis.static_levels, vec![5.0, 4.0, 2.0, 2.0, 1.0, 2.0]);
+        assert_eq!(analysis.critical_path, vec![0, 2, 4]);
+        assert_eq!(analysis.critical_path_length, 7.0);
+        assert_eq!(analysis.depth, 3);
+        assert_eq!(analysis.level_sizes, vec![2, 3, 1]);
+        assert_eq!(analysis.width, 4);
+        assert_eq!(analysis.work, 9.0);
+        assert_eq!(analysis.span, 5.0);
+        assert_eq!(analysis.get_average_parallelism(), 1.8);
+    }
+
+    #[test]
+    fn test_cache() {
+        let mut g = TaskGraph::new(2, 1);
+
+        g.add_task(Task::Constant(1.0));
+        g.add_task(Task::Constant(1.0));
+
+        assert_eq!(g.analyze().width, 2);
+
+        g.add_edge(0, 1);
+
+        assert_eq!(g.analyze().width, 1);
+        assert_eq!(g.get_b_level(0), Some(2.0));
+
+        g.set_wcet(1, 3.0);
+
+        assert_eq!(g.get_b_level(0), Some(4.0));
+        assert_eq!(g.get_t_level(1), Some(1.0));
+    }
+}
@@ -0,0 +1,266 @@
+//! This module renders a task graph in the DOT format, annotated with what the
+//! static scheduling algorithms look at.
+
+use std::fmt::Write;
+
+use dsp::DspNode;
+use static_scheduling::gantt::format_time;
+use static_scheduling::schedule::Schedule;
+use task_graph::graph::TaskGraph;
+use task_graph::task::Task;
+
+const CRITICAL_COLOR: &str = "#e6550d";
+
+// The fill colours of the nodes of each processor (ColorBrewer Set3)
+const PROCESSOR_COLORS: [&str; 12] = [
+    "#8dd3c7", "#ffffb3", "#bebada", "#fb8072", "#80b1d3", "#fdb462", "#b3de69", "#fccde5",
+    "#d9d9d9", "#bc80bd", "#ccebc5", "#ffed6f",
+];
+
+// Escapes the characters which are not allowed in a DOT string
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Returns the kind of the DSP or of the task of a node, as in the graph
+/// files.
+///
+/// # Arguments
+/// * `graph` - The graph
+/// * `node_index` - The index of the node
+pub fn get_kind(graph: &TaskGraph, node_index: usize) -> String {
+    let dsp = graph.get_dsp(node_index);
+    let dsp = dsp.lock().unwrap();
+
+    match dsp.as_ref().map(|dsp| &dsp.dsp) {
+        Some(DspNode::Oscillator(_)) => "osc".to_string(),
+        Some(DspNode::Modulator(_)) => "mod".to_string(),
+        Some(DspNode::InputsOutputsAdaptor(_)) => "mix".to_string(),
+        Some(DspNode::Sink(_)) => "sink".to_string(),
+        Some(DspNode::Input(_)) => "input".to_string(),
+        None => match graph.get_task(node_index).unwrap() {
+            Task::Constant(_) => "constant".to_string(),
+            Task::Random(_, _) => "random".to_string(),
+            Task::Puredata { object_name, .. } => object_name,
+            Task::Audiograph { class_name, .. } => class_name,
+        },
+    }
+}
+
+// Returns `true` if the WCETs of all the nodes are known or can be estimated,
+// the DSPs being measured with the buffer size and the sample rate of JACK
+fn has_wcets(graph: &TaskGraph) -> bool {
+    graph.get_deadline().is_some()
+        || (0..graph.get_nb_node()).all(|node_index| {
+            graph.get_known_wcet(node_index).is_some()
+                || graph.get_dsp(node_index).lock().unwrap().is_none()
+        })
+}
+
+/// Returns a task graph in the DOT format. Each node is labelled with its
+/// name, its kind and, when its WCET is known or can be estimated, its WCET,
+/// its t-level and its b-level, the critical path being highlighted. The
+/// edges are labelled with their communication costs.
+///
+/// With a `Schedule`, the nodes are filled with the colour of the processors
+/// they are scheduled on, which are added to their labels.
+///
+/// # Arguments
+/// * `graph` - The graph to draw
+/// * `schedule` - The `Schedule` of the graph, if any
+pub fn render_dot(graph: &mut TaskGraph, schedule: Option<&Schedule>) -> String {
+    let nb_nodes = graph.get_nb_node();
+
+    // The WCET, the t-level and the b-level of each node
+    let levels: Option<Vec<(f64, f64, f64)>> = if has_wcets(graph) {
+        Some(
+            (0..nb_nodes)
+                .map(|node_index| {
+                    (
+                        graph.get_wcet(node_index).unwrap_or(0.0),
+                        graph.get_t_level(node_index).unwrap_or(0.0),
+                        graph.get_b_level(node_index).unwrap_or(0.0),
+                    )
+                })
+                .collect(),
+        )
+    } else {
+        None
+    };
+
+    let critical_path = levels.as_ref().map_or(0.0, |levels| {
+        levels
+            .iter()
+            .map(|(_, t_level, b_level)| t_level + b_level)
+            .fold(0.0, f64::max)
+    });
+    let tolerance = critical_path * 1e-9;
+
+    let is_critical = |node_index: usize| {
+        levels.as_ref().map_or(false, |levels| {
+            let (_, t_level, b_level) = levels[node_index];
+            critical_path - (t_level + b_level) <= tolerance
+        })
+    };
+
+    // The processors each node is scheduled on
+    let mut processors: Vec<Vec<usize>> = vec![Vec::new(); nb_nodes];
+
+    if let Some(schedule) = schedule {
+        for (proc_index, processor) in schedule.processors.iter().enumerate() {
+            for time_slot in &processor.time_slots {
+                let node_processors = &mut processors[time_slot.get_node()];
+
+                if !node_processors.contains(&proc_index) {
+                    node_processors.push(proc_index);
+                }
+            }
+        }
+    }
+
+    let mut dot =
+        String::from("strict digraph {\n  node [shape=box, style=filled, fillcolor=\"white\"];\n");
+
+    if let Some(schedule) = schedule {
+        let title = match schedule.algorithm {
+            Some(ref algorithm) => format!(
+                "{} on {} processors",
+                algorithm,
+                schedule.get_nb_processor()
+            ),
+            None => format!("{} processors", schedule.get_nb_processor()),
+        };
+
+        writeln!(dot, "  label=\"{}\";\n  labelloc=t;", escape(&title)).unwrap();
+    }
+
+    for node_index in 0..nb_nodes {
+        let mut label = format!(
+            "{}\\n{}",
+            escape(&graph.get_node_name(node_index)),
+            escape(&get_kind(graph, node_index))
+        );
+
+        if let Some(ref levels) = levels {
+            let (wcet, t_level, b_level) = levels[node_index];
+
+            write!(
+                label,
+                "\\nWCET {}\\nt-level {}, b-level {}",
+                format_time(wcet, critical_path),
+                format_time(t_level, critical_path),
+                format_time(b_level, critical_path)
+            )
+            .unwrap();
+        }
+
+        let node_processors = &processors[node_index];
+
+        if !node_processors.is_empty() {
+            let names: Vec<String> = node_processors
+                .iter()
+                .map(|proc_index| format!("P{}", proc_index))
+                .collect();
+            write!(label, "\\n{}", names.join(", ")).unwrap();
+        }
+
+        write!(dot, "  n{} [label=\"{}\"", node_index, label).unwrap();
+
+        if let Some(&proc_index) = node_processors.first() {
+            write!(
+                dot,
+                ", fillcolor=\"{}\"",
+                PROCESSOR_COLORS[proc_index % PROCESSOR_COLORS.len()]
+            )
+            .unwrap();
+        }
+
+        if is_critical(node_index) {
+            write!(dot, ", color=\"{}\", penwidth=2", CRITICAL_COLOR).unwrap();
+        }
+
+        dot.push_str("];\n");
+    }
+
+    let mut edges: Vec<(usize, usize)> = graph.get_edges().keys().cloned().collect();
+    edges.sort();
+
+    for (src, dst) in edges {
+        let cost = graph.get_communication_cost(src, dst);
+        let mut attributes = Vec::new();
+
+        if let Some(cost) = cost {
+            attributes.push(format!("label=\"{}\"", format_time(cost, critical_path)));
+        }
+
+        // An edge of the critical path links two critical nodes without slack
+        let critical_edge = levels.as_ref().map_or(false, |levels| {
+            let (src_wcet, src_t_level, _) = levels[src];
+            let (_, dst_t_level, _) = levels[dst];
+
+            is_critical(src)
+                && is_critical(dst)
+                && (src_t_level + src_wcet + cost.unwrap_or(0.0) - dst_t_level).abs() <= tolerance
+        });
+
+        if critical_edge {
+            attributes.push(format!("color=\"{}\", penwidth=2", CRITICAL_COLOR));
+        }
+
+        if attributes.is_empty() {
+            writeln!(dot, "  n{} -> n{};", src, dst).unwrap();
+        } else {
+            writeln!(dot, "  n{} -> n{} [{}];", src, dst, attributes.join(", ")).unwrap();
+        }
+    }
+
+    dot.push_str("}\n");
+
+    dot
+}
+
+#[cfg(test)]
+mod dot_test {
+    use super::*;
+
+    #[test]
+    fn test_render_dot() {
+        let mut g = TaskGraph::new(4, 4);
+
+        for &wcet in &[1.0, 2.0, 1.0, 1.0] {
+            g.add_task(Task::Constant(wcet));
+        }
+
+        g.add_edge(0, 1);
+        g.add_edge(0, 2);
+        g.add_edge(1, 3);
+        g.add_edge(2, 3);
+        g.set_communication_cost(0, 2, 0.5);
+
+        let dot = render_dot(&mut g, None);
+
+        assert!(dot.starts_with("strict digraph {"));
+        assert!(dot.contains("n1 [label=\"1\\nconstant\\nWCET 2.00\\nt-level 1.00, b-level 3.00\""));
+        assert!(dot.contains("n0 -> n2 [label=\"0.50\"];"));
+
+        // The critical path goes through the node 1
+        assert_eq!(dot.matches(CRITICAL_COLOR).count(), 5);
+        assert!(!dot.contains(
+            "n2 [label=\"2\\nconstant\\nWCET 1.00\\nt-level 1.50, b-level 2.00\", color"
+        ));
+
+        let mut sche = Schedule::new();
+
+        sche.add_processor();
+        sche.add_processor();
+        sche.processors[0].add_timeslot(0, 0.0, 1.0);
+        sche.processors[0].add_timeslot(1, 1.0, 3.0);
+        sche.processors[0].add_timeslot(3, 3.0, 4.0);
+        sche.processors[1].add_timeslot(2, 1.5, 2.5);
+
+        let dot = render_dot(&mut g, Some(&sche));
+
+        assert!(dot.contains("b-level 2.00\\nP1\", fillcolor=\"#ffffb3\"];"));
+        assert_eq!(dot.matches("fillcolor=\"#8dd3c7\"").count(), 3);
+    }
+}
@@ -0,0 +1,128 @@
+//! This module generates random audio graphs whose DSPs can be executed, to
+//! benchmark the executors and the scheduling algorithms.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use task_graph::graph::TaskGraph;
+use task_graph::task::DspTask;
+
+// The probability for a node to have no input, when it could have some
+const SOURCE_PROBABILITY: f64 = 0.2;
+
+/// Generates a random audio graph whose DSPs are compatible with their edges.
+/// Each node takes its inputs from the free outputs of the nodes generated
+/// before it: a node without input is an oscillator with one output, a node
+/// with one input is a modulator or copies its input to several outputs, and
+/// a node with several inputs mixes them into one output. The free outputs are
+/// mixed into a single sink, and the same seed always gives the same graph.
+///
+/// # Arguments
+/// * `nb_nodes` - The number of nodes, at least 3 with the last mixer and the
+///   sink
+/// * `max_degree` - The maximal number of inputs or outputs of a node
+/// * `seed` - The seed of the random generator
+pub fn random_audio_graph(nb_nodes: usize, max_degree: usize, seed: u64) -> TaskGraph {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let max_degree = max_degree.max(1);
+    let nb_inner_nodes = nb_nodes.max(3) - 2;
+    let mut predecessors: Vec<Vec<usize>> = Vec::with_capacity(nb_inner_nodes);
+    let mut nb_free_outputs = Vec::with_capacity(nb_inner_nodes);
+    let mut successors = vec![Vec::new(); nb_inner_nodes];
+
+    for node_index in 0..nb_inner_nodes {
+        let mut candidates: Vec<usize> = (0..node_index)
+            .filter(|&src| nb_free_outputs[src] > 0)
+            .collect();
+        let mut node_predecessors = Vec::new();
+
+        if !candidates.is_empty() && !rng.gen_bool(SOURCE_PROBABILITY) {
+            let nb_inputs = rng.gen_range(1, max_degree.min(candidates.len()) + 1);
+
+            while node_predecessors.len() < nb_inputs {
+                let src = candidates.swap_remove(rng.gen_range(0, candidates.len()));
+
+                nb_free_outputs[src] -= 1;
+                successors[src].push(node_index);
+                node_predecessors.push(src);
+            }
+        }
+
+        // Only a node with one input can copy it to several outputs
+        nb_free_outputs.push(if node_predecessors.len() == 1 {
+            rng.gen_range(1, max_degree + 1)
+        } else {
+            1
+        });
+        predecessors.push(node_predecessors);
+    }
+
+    let exit_nodes: Vec<usize> = (0..nb_inner_nodes)
+        .filter(|&node_index| successors[node_index].is_empty())
+        .collect();
+    let mut graph = TaskGraph::new(nb_inner_nodes + 2, nb_inner_nodes * max_degree + 1);
+
+    for (node_index, node_predecessors) in predecessors.iter().enumerate() {
+        let id = format!("n{}", node_index);
+        let nb_inputs = node_predecessors.len();
+        let nb_outputs = successors[node_index].len().max(1);
+
+        graph.add_dsp(match (nb_inputs, nb_outputs) {
+            (0, _) => DspTask::new_oscillator(id, rng.gen_range(110, 880) as f32, 1.0),
+            (1, 1) => DspTask::new_modulator(id, rng.gen_range(1, 20) as f32, 1.0),
+            _ => DspTask::new_io_adaptor(id, nb_inputs, nb_outputs),
+        });
+    }
+
+    let mix = graph.add_dsp(DspTask::new_io_adaptor(
+        "mix".to_string(),
+        exit_nodes.len(),
+        1,
+    ));
+    let sink = graph.add_dsp(DspTask::new_sink("out".to_string(), 1));
+
+    for (src, node_successors) in successors.iter().enumerate() {
+        for (src_port, &dst) in node_successors.iter().enumerate() {
+            let dst_port = predecessors[dst].iter().position(|&p| p == src).unwrap();
+
+            graph.add_edge(src, dst);
+            graph.set_ports(src, dst, src_port as u32, dst_port as u32);
+        }
+    }
+
+    for (dst_port, &src) in exit_nodes.iter().enumerate() {
+        graph.add_edge(src, mix);
+        graph.set_ports(src, mix, 0, dst_port as u32);
+    }
+
+    graph.add_edge(mix, sink);
+    graph.set_ports(mix, sink, 0, 0);
+
+    graph
+}
+
+#[cfg(test)]
+mod generator_test {
+    use super::*;
+
+    #[test]
+    fn test_random_audio_graph() {
+        let mut graph = random_audio_graph(50, 3, 7);
+
+        assert_eq!(graph.get_nb_node(), 50);
+        assert_eq!(graph.get_entry_nodes()[0], 0);
+        assert_eq!(graph.get_exit_nodes(), vec![49]);
+        assert_eq!(graph.get_predecessors(49), Some(vec![48]));
+
+        for node_index in 0..48 {
+            let nb_inputs = graph.get_predecessors(node_index).unwrap().len();
+
+            assert!(nb_inputs <= 3);
+            assert!(!graph.get_successors(node_index).unwrap().is_empty());
+        }
+
+        // The same seed gives the same graph
+        assert_eq!(random_audio_graph(50, 3, 7).get_hash(), graph.get_hash());
+        assert_ne!(random_audio_graph(50, 3, 8).get_hash(), graph.get_hash());
+    }
+}
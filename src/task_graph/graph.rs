@@ -7,25 +7,46 @@ use std::path::Path;
 use std::process::Command;
 use std::sync::{Arc, Mutex};
 
+use crate::midi::MidiMapping;
+use crate::static_scheduling::platform::Platform;
 use crate::static_scheduling::schedule::Schedule;
+use crate::static_scheduling::validation::{validate, validate_on};
+use crate::wcet::database::WcetDatabase;
+use crate::wcet::profiler::WcetSource;
 
+use super::analysis::GraphAnalysis;
+use super::dot::render_dot;
 use super::node::Node;
 use super::state::TaskState;
 use super::task::DspTask;
 use super::task::Task;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct TaskGraph {
     nodes: Vec<Node>,
     // The edges are a hash map associating two (the source and the destination of the arc) nodes
     // with a communication cost.
     edges: HashMap<(usize, usize), Option<f64>>,
+    // The output port of the source and the input port of the destination of
+    // the edges connecting DSPs
+    ports: HashMap<(usize, usize), (u32, u32)>,
+    // The JACK ports the ports of the exit and input nodes are connected to
+    connections: HashMap<usize, Vec<String>>,
+    // The WAV files played by the input nodes in the offline runs
+    input_files: HashMap<usize, String>,
+    // The MIDI messages driving the parameters of the nodes, and the MIDI file
+    // played in the offline runs
+    midi_mappings: Vec<MidiMapping>,
+    midi_file: Option<String>,
     entry_nodes: Vec<usize>,
     exit_nodes: Vec<usize>,
     adj_list: Vec<(Vec<usize>, Vec<usize>)>,
 
     sample_rate: Option<usize>,
     buffer_size: Option<usize>,
+    wcet_source: WcetSource,
+    // The analysis of the graph, until a node, an edge or a cost changes
+    analysis: Option<GraphAnalysis>,
 }
 
 impl TaskGraph {
@@ -44,11 +65,18 @@ impl TaskGraph {
         TaskGraph {
             nodes: Vec::with_capacity(nodes_count),
             edges: HashMap::with_capacity(edges_count),
+            ports: HashMap::new(),
+            connections: HashMap::new(),
+            input_files: HashMap::new(),
+            midi_mappings: Vec::new(),
+            midi_file: None,
             entry_nodes: Vec::new(),
             exit_nodes: Vec::new(),
             adj_list,
             sample_rate: None,
             buffer_size: None,
+            wcet_source: WcetSource::Max,
+            analysis: None,
         }
     }
 
@@ -176,6 +204,39 @@ impl TaskGraph {
         }
     }
 
+    /// Returns the WCET of the node if it is known, without estimating it.
+    ///
+    /// # Arguments
+    /// * `node_index` - The index of the node
+    pub fn get_known_wcet(&self, node_index: usize) -> Option<f64> {
+        self.nodes.get(node_index).and_then(|node| node.wcet)
+    }
+
+    /// Sets the WCET of the node, replacing its estimation, and returns
+    /// `true` if the node exists.
+    ///
+    /// # Arguments
+    /// * `node_index` - The index of the node
+    /// * `wcet` - The WCET of the node
+    pub fn set_wcet(&mut self, node_index: usize, wcet: f64) -> bool {
+        match self.nodes.get_mut(node_index) {
+            Some(node) => {
+                node.wcet = Some(wcet);
+                self.analysis = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the task of the node if there is one.
+    ///
+    /// # Arguments
+    /// * `node_index` - The index of the node
+    pub fn get_task(&self, node_index: usize) -> Option<Task> {
+        self.nodes.get(node_index).map(|node| node.task.clone())
+    }
+
     /// Returns the state of the node if there is one.
     ///
     /// # Arguments
@@ -202,6 +263,73 @@ impl TaskGraph {
         }
     }
 
+    /// Returns the JACK ports the port of an exit or an input node is
+    /// connected to, as given by the graph file.
+    ///
+    /// # Arguments
+    /// * `node_index` - The index of the node
+    pub fn get_connections(&self, node_index: usize) -> &[String] {
+        self.connections
+            .get(&node_index)
+            .map_or(&[], |connections| connections.as_slice())
+    }
+
+    /// Sets the JACK ports the port of an exit or an input node is connected
+    /// to.
+    ///
+    /// # Arguments
+    /// * `node_index` - The index of the node
+    /// * `connections` - The full names of the JACK ports
+    pub fn set_connections(&mut self, node_index: usize, connections: Vec<String>) {
+        self.connections.insert(node_index, connections);
+    }
+
+    /// Returns the WAV file an input node plays when the graph is run
+    /// offline, if it has one.
+    ///
+    /// # Arguments
+    /// * `node_index` - The index of the input node
+    pub fn get_input_file(&self, node_index: usize) -> Option<&str> {
+        self.input_files.get(&node_index).map(|path| path.as_str())
+    }
+
+    /// Sets the WAV file an input node plays when the graph is run offline.
+    ///
+    /// # Arguments
+    /// * `node_index` - The index of the input node
+    /// * `path` - The path of the file
+    pub fn set_input_file(&mut self, node_index: usize, path: &str) {
+        self.input_files.insert(node_index, path.to_string());
+    }
+
+    /// Returns the mappings of the MIDI messages to the parameters of the
+    /// nodes.
+    pub fn get_midi_mappings(&self) -> &[MidiMapping] {
+        &self.midi_mappings
+    }
+
+    /// Adds a mapping of MIDI messages to a parameter of a node.
+    ///
+    /// # Arguments
+    /// * `mapping` - The mapping
+    pub fn add_midi_mapping(&mut self, mapping: MidiMapping) {
+        self.midi_mappings.push(mapping);
+    }
+
+    /// Returns the MIDI file played when the graph is run offline, if it has
+    /// one.
+    pub fn get_midi_file(&self) -> Option<&str> {
+        self.midi_file.as_deref()
+    }
+
+    /// Sets the MIDI file played when the graph is run offline.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the file
+    pub fn set_midi_file(&mut self, path: &str) {
+        self.midi_file = Some(path.to_string());
+    }
+
     /// Decrements the activation count of a node and mark it as ready if it
     /// reaches 0.
     ///
@@ -242,37 +370,79 @@ impl TaskGraph {
             .unwrap_or(None)
     }
 
-    /// Returns the t-level of a node.
+    /// Sets the communication cost between two nodes and returns `true` if
+    /// the edge exists.
     ///
     /// # Arguments
-    /// * `node_index` - The index of the node
-    pub fn get_t_level(&mut self, node_index: usize) -> Option<f64> {
-        let top_ord = self.get_topological_order();
-        let mut t_levels: Vec<f64> = std::iter::repeat(0.0).take(self.nodes.len()).collect();
-
-        for i in top_ord {
-            let mut max: f64 = 0.0;
-
-            for x in self.get_predecessors(i).unwrap_or_default() {
-                if t_levels[x]
-                    + self.get_wcet(x).unwrap()
-                    + self.get_communication_cost(x, i).unwrap_or(0.0)
-                    > max
-                {
-                    max = t_levels[x]
-                        + self.get_wcet(x).unwrap()
-                        + self.get_communication_cost(x, i).unwrap_or(0.0);
-                }
+    /// * `src_node_index` - The index of the source node
+    /// * `dst_node_index` - The index of the destination node
+    /// * `cost` - The communication cost of the edge
+    pub fn set_communication_cost(
+        &mut self,
+        src_node_index: usize,
+        dst_node_index: usize,
+        cost: f64,
+    ) -> bool {
+        match self.edges.get_mut(&(src_node_index, dst_node_index)) {
+            Some(edge_cost) => {
+                *edge_cost = Some(cost);
+                self.analysis = None;
+                true
             }
+            None => false,
+        }
+    }
 
-            if i == node_index {
-                break;
-            }
+    /// Returns the ports connected by an edge, if they are known.
+    ///
+    /// # Arguments
+    /// * `src_node_index` - The index of the source node
+    /// * `dst_node_index` - The index of the destination node
+    pub fn get_ports(&self, src_node_index: usize, dst_node_index: usize) -> Option<(u32, u32)> {
+        self.ports.get(&(src_node_index, dst_node_index)).cloned()
+    }
 
-            t_levels[i] = max;
+    /// Sets the ports connected by an edge and returns `true` if the edge
+    /// exists.
+    ///
+    /// # Arguments
+    /// * `src_node_index` - The index of the source node
+    /// * `dst_node_index` - The index of the destination node
+    /// * `src_port` - The output port of the source node
+    /// * `dst_port` - The input port of the destination node
+    pub fn set_ports(
+        &mut self,
+        src_node_index: usize,
+        dst_node_index: usize,
+        src_port: u32,
+        dst_port: u32,
+    ) -> bool {
+        if self.edges.contains_key(&(src_node_index, dst_node_index)) {
+            self.ports
+                .insert((src_node_index, dst_node_index), (src_port, dst_port));
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the analysis of the graph: the levels of its nodes, its
+    /// critical path and its parallelism. It is computed once until the graph
+    /// changes.
+    pub fn analyze(&mut self) -> &GraphAnalysis {
+        if self.analysis.is_none() {
+            self.analysis = Some(GraphAnalysis::new(self));
         }
 
-        t_levels.get(node_index).map(|val| *val)
+        self.analysis.as_ref().unwrap()
+    }
+
+    /// Returns the t-level of a node.
+    ///
+    /// # Arguments
+    /// * `node_index` - The index of the node
+    pub fn get_t_level(&mut self, node_index: usize) -> Option<f64> {
+        self.analyze().t_levels.get(node_index).cloned()
     }
 
     /// Returns the b-level of a node.
@@ -280,28 +450,7 @@ impl TaskGraph {
     /// # Arguments
     /// * `node_index` - The index of the node
     pub fn get_b_level(&mut self, node_index: usize) -> Option<f64> {
-        let rev_top_ord = self.get_rev_topological_order();
-        let mut b_levels: Vec<f64> = std::iter::repeat(0.0).take(self.nodes.len()).collect();
-
-        for i in rev_top_ord {
-            let mut max: f64 = 0.0;
-
-            for y in self.get_successors(i).unwrap_or_default() {
-                let comm_cost = self.get_communication_cost(i, y).unwrap_or(0.0);
-
-                if comm_cost + b_levels[y] > max {
-                    max = comm_cost + b_levels[y];
-                }
-            }
-
-            b_levels[i] = self.get_wcet(i).unwrap() + max;
-
-            if i == node_index {
-                break;
-            }
-        }
-
-        b_levels.get(node_index).map(|val| *val)
+        self.analyze().b_levels.get(node_index).cloned()
     }
 
     /// Returns the static level of a node.
@@ -309,26 +458,7 @@ impl TaskGraph {
     /// # Arguments
     /// * `node_index` - The index of the node
     pub fn get_static_level(&mut self, node_index: usize) -> Option<f64> {
-        let rev_top_ord = self.get_rev_topological_order();
-        let mut s_levels: Vec<f64> = std::iter::repeat(0.0).take(self.nodes.len()).collect();
-
-        for i in rev_top_ord {
-            let mut max: f64 = 0.0;
-
-            for y in self.get_successors(i).unwrap_or_default() {
-                if s_levels[y] > max {
-                    max = s_levels[y];
-                }
-            }
-
-            s_levels[i] = self.get_wcet(i).unwrap() + max;
-
-            if i == node_index {
-                break;
-            }
-        }
-
-        s_levels.get(node_index).map(|val| *val)
+        self.analyze().static_levels.get(node_index).cloned()
     }
 
     /// Adds a task to the graph and returns the index of its node.
@@ -337,6 +467,7 @@ impl TaskGraph {
     /// * `task` - The task to add
     pub fn add_task(&mut self, task: Task) -> usize {
         self.nodes.push(Node::new(task));
+        self.analysis = None;
 
         self.nodes.len() - 1
     }
@@ -347,6 +478,7 @@ impl TaskGraph {
     /// * `dsp` - The `DspTask` to add
     pub fn add_dsp(&mut self, dsp: DspTask) -> usize {
         self.nodes.push(Node::with_dsp(dsp));
+        self.analysis = None;
 
         self.nodes.len() - 1
     }
@@ -362,6 +494,7 @@ impl TaskGraph {
             self.adj_list[dst_node_index].1.push(src_node_index);
 
             self.edges.insert((src_node_index, dst_node_index), None);
+            self.analysis = None;
 
             true
         } else {
@@ -387,38 +520,142 @@ impl TaskGraph {
         }
     }
 
-    /// Writes the graph in the DOT format.
+    /// Returns the buffer size if it has been set.
+    pub fn get_buffer_size(&self) -> Option<usize> {
+        self.buffer_size
+    }
+
+    /// Returns the sample rate if it has been set.
+    pub fn get_sample_rate(&self) -> Option<usize> {
+        self.sample_rate
+    }
+
+    /// Sets how the WCETs of the DSPs are measured, forgetting the WCETs
+    /// already measured.
     ///
     /// # Arguments
-    /// * `path` - The path of the file in which the DOT graph will be written
-    pub fn write_dot(&self, path: &str) -> Result<(), std::io::Error> {
-        let mut dot_file = String::new();
+    /// * `wcet_source` - The maximum of the execution times or a pWCET
+    pub fn set_wcet_source(&mut self, wcet_source: WcetSource) {
+        self.wcet_source = wcet_source;
+        self.analysis = None;
+
+        for node in self.nodes.iter_mut() {
+            node.wcet_source = wcet_source;
 
-        dot_file.push_str("strict digraph{\n");
+            if node.dsp_task.lock().unwrap().is_some() {
+                node.wcet = None;
+            }
+        }
+    }
 
-        for (i, node) in self.nodes.iter().enumerate() {
-            let line = match (*node.dsp_task.lock().unwrap()).as_ref() {
-                None => format!("{};\n", i),
-                Some(dsp) => format!("{};\n", dsp.id),
+    /// Returns how the WCETs of the DSPs are measured.
+    pub fn get_wcet_source(&self) -> WcetSource {
+        self.wcet_source
+    }
+
+    /// Sets the WCETs of the DSPs profiled in a database for the buffer size,
+    /// the sample rate and the `WcetSource` of the graph, and returns the
+    /// number of nodes whose WCET has been set.
+    ///
+    /// # Arguments
+    /// * `database` - The profiles of the DSPs
+    pub fn load_wcets(&mut self, database: &WcetDatabase) -> usize {
+        let (buffer_size, sample_rate) = match (self.buffer_size, self.sample_rate) {
+            (Some(buffer_size), Some(sample_rate)) => (buffer_size, sample_rate),
+            _ => return 0,
+        };
+
+        let mut nb_loaded = 0;
+
+        for node in self.nodes.iter_mut() {
+            let wcet = match *node.dsp_task.lock().unwrap() {
+                Some(ref dsp_task) => {
+                    database.get(&dsp_task.dsp, buffer_size, sample_rate, self.wcet_source)
+                }
+                None => None,
             };
 
-            dot_file.push_str(&line);
+            if let Some(wcet) = wcet {
+                node.wcet = Some(wcet);
+                nb_loaded += 1;
+            }
         }
 
-        for ((s, t), _) in &self.edges {
-            let line = if let (Some(src_dsp), Some(dst_dsp)) = (
-                (*self.nodes[*s].dsp_task.lock().unwrap()).as_ref(),
-                (*self.nodes[*t].dsp_task.lock().unwrap()).as_ref(),
-            ) {
-                format!("{} -> {};\n", src_dsp.id, dst_dsp.id)
-            } else {
-                format!("{} -> {};\n", s, t)
-            };
+        if nb_loaded > 0 {
+            self.analysis = None;
+        }
+
+        nb_loaded
+    }
 
-            dot_file.push_str(line.as_str());
+    /// Returns the duration of an audio cycle, in seconds, if the sample rate
+    /// and the buffer size have been set.
+    pub fn get_deadline(&self) -> Option<f64> {
+        match (self.buffer_size, self.sample_rate) {
+            (Some(buffer_size), Some(sample_rate)) => Some(buffer_size as f64 / sample_rate as f64),
+            _ => None,
         }
+    }
 
-        dot_file.push_str("}\n");
+    /// Returns a hash of the structure of the graph, its nodes and its edges,
+    /// identifying the graph a schedule was made for.
+    pub fn get_hash(&self) -> u64 {
+        // FNV-1a, which is stable across executions and Rust versions
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut hash_str = |text: &str| {
+            for byte in text.bytes().chain(std::iter::once(b'\n')) {
+                hash ^= u64::from(byte);
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        };
+
+        hash_str(&self.get_nb_node().to_string());
+
+        for node_index in 0..self.get_nb_node() {
+            hash_str(&self.get_node_name(node_index));
+        }
+
+        let mut edges: Vec<&(usize, usize)> = self.edges.keys().collect();
+        edges.sort();
+
+        for (src, dst) in edges {
+            hash_str(&format!("{} {}", src, dst));
+        }
+
+        hash
+    }
+
+    /// Returns the name of a node: the ID of its DSP or of its audio graph
+    /// node if it has one, its index otherwise.
+    ///
+    /// # Arguments
+    /// * `node_index` - The index of the node
+    pub fn get_node_name(&self, node_index: usize) -> String {
+        let node = &self.nodes[node_index];
+
+        if let Some(dsp) = (*node.dsp_task.lock().unwrap()).as_ref() {
+            return dsp.id.clone();
+        }
+
+        match node.task {
+            Task::Audiograph { ref id, .. } if !id.is_empty() => id.clone(),
+            _ => node_index.to_string(),
+        }
+    }
+
+    /// Writes the graph in the DOT format, annotated with the WCETs, the levels
+    /// and the critical path, and coloured by processor if a `Schedule` is
+    /// given.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the file in which the DOT graph will be written
+    /// * `schedule` - The `Schedule` of the graph, if any
+    pub fn write_dot(
+        &mut self,
+        path: &str,
+        schedule: Option<&Schedule>,
+    ) -> Result<(), std::io::Error> {
+        let dot_file = render_dot(self, schedule);
 
         let mut file = File::create(path).expect("Impossible to create file.");
 
@@ -430,33 +667,28 @@ impl TaskGraph {
     /// # Arguments
     /// * `schedule` - The schedule to check against the graph.
     pub fn is_valid_schedule(&mut self, schedule: &Schedule) -> bool {
-        for node_index in self.get_topological_order() {
-            match schedule.get_time_slot(node_index) {
-                None => return false,
-                Some(time_slot) => {
-                    if let Some(wcet) = self.get_wcet(node_index) {
-                        let time_slot_duration =
-                            time_slot.get_completion_time() - time_slot.get_start_time();
-
-                        if (time_slot_duration - wcet) >= 0.00004 {
-                            return false;
-                        }
-                    }
-
-                    for predecessor in self.get_predecessors(node_index).unwrap_or_default() {
-                        if schedule.get_time_slot(predecessor).is_none() {
-                            return false;
-                        }
-                    }
-                }
-            }
-        }
+        validate(self, schedule).is_empty()
+    }
 
-        true
+    /// Returns `true` if the schedule respects the dependencies of the nodes of the task graph,
+    /// the processors of the schedule running at the speeds of the processors of the platform.
+    ///
+    /// # Arguments
+    /// * `schedule` - The schedule to check against the graph.
+    /// * `platform` - The processors the graph is scheduled on.
+    pub fn is_valid_schedule_on(&mut self, schedule: &Schedule, platform: &Platform) -> bool {
+        validate_on(self, schedule, platform).is_empty()
     }
 }
 
-pub fn create_dot(graph: &TaskGraph, graph_name: &str) {
+/// Writes the DOT representation of a graph in `tmp/` and renders it in a PDF
+/// file with `dot`.
+///
+/// # Arguments
+/// * `graph` - The graph to draw
+/// * `graph_name` - The name of the files
+/// * `schedule` - The `Schedule` of the graph, if any
+pub fn create_dot(graph: &mut TaskGraph, graph_name: &str, schedule: Option<&Schedule>) {
     let tmp_dot = format!("tmp/{}.dot", graph_name);
 
     println!("Creating tmp directory");
@@ -468,20 +700,23 @@ pub fn create_dot(graph: &TaskGraph, graph_name: &str) {
 
     println!("Writing the DOT file");
     graph
-        .write_dot(tmp_dot.as_str())
+        .write_dot(tmp_dot.as_str(), schedule)
         .unwrap_or_else(|e| panic!("failed to output graph: {}", e));
 
     let pdf_filename = format!("tmp/{}.pdf", graph_name);
 
     println!("Running dot");
 
-    Command::new("dot")
+    // The DOT file is kept when Graphviz is not installed
+    if let Err(e) = Command::new("dot")
         .arg("-Tpdf")
         .arg(tmp_dot)
         .arg("-o")
         .arg(pdf_filename)
         .output()
-        .unwrap_or_else(|e| panic!("failed to execute process: {}", e));
+    {
+        println!("Failed to run dot: {}", e);
+    }
 }
 
 #[cfg(test)]
@@ -1,3 +1,6 @@
+pub mod analysis;
+pub mod dot;
+pub mod generator;
 pub mod graph;
 pub mod node;
 pub mod state;
@@ -1,14 +1,13 @@
-use std::sync::{Arc, Mutex, RwLock};
-use std::time::{Duration, Instant};
+use std::sync::{Arc, Mutex};
 
 use rand::Rng;
 
-use crate::dsp::{DspEdge, DspNode};
+use crate::wcet::profiler::{measure_dsp, WcetSource};
 
 use super::state::TaskState;
 use super::task::{DspTask, Task};
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Node {
     pub task: Task,
     pub dsp_task: Arc<Mutex<Option<DspTask>>>,
@@ -17,6 +16,7 @@ pub struct Node {
 
     pub sample_rate: Option<usize>,
     pub buffer_size: Option<usize>,
+    pub wcet_source: WcetSource, // How the WCET of a DSP is measured
 }
 
 impl Node {
@@ -30,6 +30,7 @@ impl Node {
             state: TaskState::WaitingDependencies(0),
             sample_rate: None,
             buffer_size: None,
+            wcet_source: WcetSource::Max,
         }
     }
 
@@ -42,6 +43,7 @@ impl Node {
             state: TaskState::WaitingDependencies(0),
             sample_rate: None,
             buffer_size: None,
+            wcet_source: WcetSource::Max,
         }
     }
 
@@ -82,70 +84,14 @@ impl Node {
                 };
             }
             Some(dsp) => {
-                let dsp = &dsp.dsp;
-
-                let mut max_duration = Duration::new(0, 0);
-
-                for _ in 0..50 {
-                    let timer = Instant::now();
-
-                    match dsp {
-                        DspNode::Oscillator(mut o) => {
-                            o.process(Arc::new(RwLock::new(DspEdge::new(
-                                self.buffer_size.expect("buffer size not set"),
-                                self.sample_rate.expect("sample rate not set"),
-                            ))));
-                        }
-                        DspNode::Modulator(mut m) => {
-                            m.process(
-                                Arc::new(RwLock::new(DspEdge::new(
-                                    self.buffer_size.expect("buffer size not set"),
-                                    self.sample_rate.expect("sample rate not set"),
-                                ))),
-                                Arc::new(RwLock::new(DspEdge::new(
-                                    self.buffer_size.expect("buffer size not set"),
-                                    self.sample_rate.expect("sample rate not set"),
-                                ))),
-                            );
-                        }
-                        DspNode::InputsOutputsAdaptor(mut ioa) => {
-                            ioa.process(
-                                vec![
-                                    Arc::new(RwLock::new(DspEdge::new(
-                                        self.buffer_size.expect("buffer size not set"),
-                                        self.sample_rate.expect("sample rate not set"),
-                                    ))),
-                                    Arc::new(RwLock::new(DspEdge::new(
-                                        self.buffer_size.expect("buffer size not set"),
-                                        self.sample_rate.expect("sample rate not set"),
-                                    ))),
-                                ],
-                                vec![Arc::new(RwLock::new(DspEdge::new(
-                                    self.buffer_size.expect("buffer size not set"),
-                                    self.sample_rate.expect("sample rate not set"),
-                                )))],
-                            );
-                        }
-                        DspNode::Sink(mut s) => {
-                            let mut vec = vec![0.0; self.buffer_size.expect("buffer size not set")];
-                            let mut buffer = vec.as_mut_slice();
-
-                            s.set_buffer(buffer.as_mut_ptr(), 60);
-                            s.process(Arc::new(RwLock::new(DspEdge::new(
-                                self.buffer_size.expect("buffer size not set"),
-                                self.sample_rate.expect("sample rate not set"),
-                            ))));
-                        }
-                    }
-
-                    let duration = timer.elapsed();
-
-                    if max_duration < duration {
-                        max_duration = duration;
-                    }
-                }
-
-                self.wcet = Some(f64::from(max_duration.subsec_micros()) / 1_000_000.0);
+                let durations = measure_dsp(
+                    &dsp.dsp,
+                    self.buffer_size.expect("buffer size not set"),
+                    self.sample_rate.expect("sample rate not set"),
+                    self.wcet_source.get_nb_runs(),
+                );
+
+                self.wcet = Some(self.wcet_source.estimate(&durations));
             }
         }
     }
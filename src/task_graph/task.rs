@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 use std::fmt;
 
-use crate::dsp::{DspNode, InputsOutputsAdaptor, Modulator, Oscillator, Sink};
+use crate::dsp::{DspNode, Input, InputsOutputsAdaptor, Modulator, Oscillator, Sink};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Task {
@@ -52,13 +52,13 @@ pub struct DspTask {
 }
 
 impl DspTask {
-    pub fn new_oscillator(id: String, frequency: u32, volume: f32) -> DspTask {
+    pub fn new_oscillator(id: String, frequency: f32, volume: f32) -> DspTask {
         let osc = DspNode::Oscillator(Oscillator::new(0.0, frequency, volume));
 
         DspTask { id, dsp: osc }
     }
 
-    pub fn new_modulator(id: String, frequency: u32, volume: f32) -> DspTask {
+    pub fn new_modulator(id: String, frequency: f32, volume: f32) -> DspTask {
         let modul = DspNode::Modulator(Modulator::new(0.0, frequency, volume));
 
         DspTask { id, dsp: modul }
@@ -82,6 +82,12 @@ impl DspTask {
             dsp: io_adaptor,
         }
     }
+
+    pub fn new_input(id: String, nb_channels: usize) -> DspTask {
+        let input = DspNode::Input(Input::new(nb_channels));
+
+        DspTask { id, dsp: input }
+    }
 }
 
 impl fmt::Debug for DspTask {
@@ -0,0 +1,410 @@
+//! This module stores the WCETs measured for the DSPs in a text file, keyed by
+//! the host CPU, the kind and the parameters of the DSP, the buffer size and
+//! the sample rate:
+//!
+//! ```text
+//! cpu Intel(R) Core(TM) i7-8550U CPU @ 1.80GHz
+//! osc 440,1 256 48000 max 0.000012
+//! mix 2,1 256 48000 pwcet:1e-9 0.0000034
+//! ```
+//!
+//! A `cpu` line gives the CPU of the profiles of the following lines, which
+//! give the kind and the parameters of a DSP, the buffer size, the sample
+//! rate, the `WcetSource` and the WCET, in seconds.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Error as FmtError, Formatter};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use dsp::DspNode;
+use wcet::profiler::WcetSource;
+
+/// The path of the database used by the executables.
+pub const DATABASE_PATH: &str = "tmp/wcet_profiles.txt";
+
+const CPUINFO_PATH: &str = "/proc/cpuinfo";
+
+#[derive(Debug)]
+/// The errors which can happen when loading a database.
+pub enum DatabaseError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl Display for DatabaseError {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), FmtError> {
+        match self {
+            DatabaseError::Io(e) => write!(fmt, "{}", e),
+            DatabaseError::Parse { line, message } => write!(fmt, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl From<io::Error> for DatabaseError {
+    fn from(e: io::Error) -> DatabaseError {
+        DatabaseError::Io(e)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// What the WCET of a DSP depends on.
+pub struct ProfileKey {
+    pub cpu: String,
+    pub kind: String,
+    pub parameters: String, // The parameters of the DSP, separated by commas
+    pub buffer_size: usize,
+    pub sample_rate: usize,
+    pub source: String, // The name of the `WcetSource`
+}
+
+impl ProfileKey {
+    /// Creates the `ProfileKey` of a DSP.
+    ///
+    /// # Arguments
+    /// * `cpu` - The model of the CPU executing the DSP
+    /// * `dsp` - The DSP
+    /// * `buffer_size` - The size of the audio buffers
+    /// * `sample_rate` - The sample rate
+    /// * `source` - How the WCET is derived from the execution times
+    pub fn new(
+        cpu: &str,
+        dsp: &DspNode,
+        buffer_size: usize,
+        sample_rate: usize,
+        source: WcetSource,
+    ) -> ProfileKey {
+        let (kind, parameters) = match dsp {
+            DspNode::Oscillator(osc) => (
+                "osc",
+                format!("{},{}", osc.get_frequency(), osc.get_volume()),
+            ),
+            DspNode::Modulator(modulator) => (
+                "mod",
+                format!("{},{}", modulator.get_frequency(), modulator.get_volume()),
+            ),
+            DspNode::InputsOutputsAdaptor(adaptor) => (
+                "mix",
+                format!("{},{}", adaptor.get_nb_inputs(), adaptor.get_nb_outputs()),
+            ),
+            DspNode::Sink(sink) => ("sink", format!("{}", sink.get_nb_channels())),
+            DspNode::Input(input) => ("input", format!("{}", input.get_nb_channels())),
+        };
+
+        ProfileKey {
+            cpu: cpu.to_string(),
+            kind: kind.to_string(),
+            parameters,
+            buffer_size,
+            sample_rate,
+            source: source.get_name(),
+        }
+    }
+}
+
+/// Returns the model of the CPU of the host, or `unknown` if it can not be
+/// read from `/proc/cpuinfo`.
+pub fn get_host_cpu() -> String {
+    fs::read_to_string(CPUINFO_PATH)
+        .ok()
+        .and_then(|cpuinfo| {
+            cpuinfo
+                .lines()
+                .filter(|line| line.starts_with("model name"))
+                .filter_map(|line| line.split(':').nth(1))
+                .map(|model| model.trim().to_string())
+                .next()
+        })
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// The WCETs measured for the DSPs, on one or several hosts.
+pub struct WcetDatabase {
+    pub cpu: String, // The CPU of the profiles looked up and added
+    entries: HashMap<ProfileKey, f64>,
+}
+
+impl WcetDatabase {
+    /// Creates an empty `WcetDatabase` for the CPU of the host.
+    pub fn new() -> WcetDatabase {
+        WcetDatabase::with_cpu(&get_host_cpu())
+    }
+
+    /// Creates an empty `WcetDatabase` for a given CPU.
+    ///
+    /// # Arguments
+    /// * `cpu` - The model of the CPU
+    pub fn with_cpu(cpu: &str) -> WcetDatabase {
+        WcetDatabase {
+            cpu: cpu.to_string(),
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Returns the number of profiles, for all the CPUs.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if there is no profile.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the WCET of a DSP on the CPU of the database, if it has been
+    /// profiled.
+    ///
+    /// # Arguments
+    /// * `dsp` - The DSP
+    /// * `buffer_size` - The size of the audio buffers
+    /// * `sample_rate` - The sample rate
+    /// * `source` - How the WCET is derived from the execution times
+    pub fn get(
+        &self,
+        dsp: &DspNode,
+        buffer_size: usize,
+        sample_rate: usize,
+        source: WcetSource,
+    ) -> Option<f64> {
+        self.entries
+            .get(&ProfileKey::new(
+                &self.cpu,
+                dsp,
+                buffer_size,
+                sample_rate,
+                source,
+            ))
+            .cloned()
+    }
+
+    /// Stores the WCET of a DSP on the CPU of the database, replacing the
+    /// previous profile.
+    ///
+    /// # Arguments
+    /// * `dsp` - The DSP
+    /// * `buffer_size` - The size of the audio buffers
+    /// * `sample_rate` - The sample rate
+    /// * `source` - How the WCET is derived from the execution times
+    /// * `wcet` - The WCET of the DSP, in seconds
+    pub fn insert(
+        &mut self,
+        dsp: &DspNode,
+        buffer_size: usize,
+        sample_rate: usize,
+        source: WcetSource,
+        wcet: f64,
+    ) {
+        self.entries.insert(
+            ProfileKey::new(&self.cpu, dsp, buffer_size, sample_rate, source),
+            wcet,
+        );
+    }
+
+    /// Returns the content of the database file, the profiles being sorted.
+    pub fn write(&self) -> String {
+        let mut keys: Vec<&ProfileKey> = self.entries.keys().collect();
+        keys.sort();
+
+        let mut content = String::new();
+        let mut cpu = None;
+
+        for key in keys {
+            if cpu != Some(&key.cpu) {
+                content.push_str(&format!("cpu {}\n", key.cpu));
+                cpu = Some(&key.cpu);
+            }
+
+            content.push_str(&format!(
+                "{} {} {} {} {} {:?}\n",
+                key.kind,
+                key.parameters,
+                key.buffer_size,
+                key.sample_rate,
+                key.source,
+                self.entries[key]
+            ));
+        }
+
+        content
+    }
+
+    /// Parses the content of a database file, for the CPU of the host.
+    ///
+    /// # Arguments
+    /// * `content` - The content of the file
+    pub fn read(content: &str) -> Result<WcetDatabase, DatabaseError> {
+        WcetDatabase::read_with_cpu(content, &get_host_cpu())
+    }
+
+    /// Parses the content of a database file, for a given CPU.
+    ///
+    /// # Arguments
+    /// * `content` - The content of the file
+    /// * `cpu` - The model of the CPU
+    pub fn read_with_cpu(content: &str, cpu: &str) -> Result<WcetDatabase, DatabaseError> {
+        let mut database = WcetDatabase::with_cpu(cpu);
+        let mut current_cpu: Option<String> = None;
+
+        for (i, line) in content.lines().enumerate() {
+            let parse_error = |message: &str| DatabaseError::Parse {
+                line: i + 1,
+                message: message.to_string(),
+            };
+
+            let line = line.split('#').next().unwrap().trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(cpu) = line.strip_prefix("cpu ") {
+                current_cpu = Some(cpu.trim().to_string());
+                continue;
+            }
+
+            let words: Vec<&str> = line.split_whitespace().collect();
+
+            // The profiles saved before the probabilistic WCETs have no
+            // source, their WCETs being the maximum execution times
+            let words = match words.as_slice() {
+                &[kind, parameters, buffer_size, sample_rate, wcet] => {
+                    vec![kind, parameters, buffer_size, sample_rate, "max", wcet]
+                }
+                _ => words,
+            };
+
+            match words.as_slice() {
+                [kind, parameters, buffer_size, sample_rate, source, wcet] => {
+                    if WcetSource::from_name(source).is_none() {
+                        return Err(parse_error("bad WCET source"));
+                    }
+
+                    let key = ProfileKey {
+                        cpu: current_cpu
+                            .clone()
+                            .ok_or_else(|| parse_error("profile outside of a cpu"))?,
+                        kind: kind.to_string(),
+                        parameters: parameters.to_string(),
+                        buffer_size: buffer_size
+                            .parse()
+                            .map_err(|_| parse_error("bad buffer size"))?,
+                        sample_rate: sample_rate
+                            .parse()
+                            .map_err(|_| parse_error("bad sample rate"))?,
+                        source: source.to_string(),
+                    };
+                    let wcet: f64 = wcet.parse().map_err(|_| parse_error("bad WCET"))?;
+
+                    database.entries.insert(key, wcet);
+                }
+                _ => return Err(parse_error("unexpected line")),
+            }
+        }
+
+        Ok(database)
+    }
+
+    /// Loads a database file for the CPU of the host, an empty database being
+    /// returned if the file does not exist.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<WcetDatabase, DatabaseError> {
+        match fs::read_to_string(path) {
+            Ok(content) => WcetDatabase::read(&content),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(WcetDatabase::new()),
+            Err(e) => Err(DatabaseError::Io(e)),
+        }
+    }
+
+    /// Saves the database in a file, creating its directory if needed.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        if let Some(directory) = path.as_ref().parent() {
+            fs::create_dir_all(directory)?;
+        }
+
+        fs::write(path, self.write())
+    }
+}
+
+impl Default for WcetDatabase {
+    fn default() -> WcetDatabase {
+        WcetDatabase::new()
+    }
+}
+
+#[cfg(test)]
+mod database_test {
+    use super::*;
+    use dsp::{InputsOutputsAdaptor, Oscillator};
+
+    #[test]
+    fn test_round_trip() {
+        let osc = DspNode::Oscillator(Oscillator::new(0.0, 440.0, 1.0));
+        let mix = DspNode::InputsOutputsAdaptor(InputsOutputsAdaptor::new(2, 1));
+
+        let mut database = WcetDatabase::with_cpu("cpu A");
+        let pwcet = WcetSource::PWcet(1e-9);
+
+        database.insert(&osc, 256, 48000, WcetSource::Max, 0.000012);
+        database.insert(&mix, 256, 48000, WcetSource::Max, 0.000003);
+        database.insert(&osc, 256, 48000, pwcet, 0.000015);
+
+        let mut other = WcetDatabase::with_cpu("cpu B");
+        other.insert(&osc, 256, 48000, WcetSource::Max, 0.00002);
+
+        let content = format!("{}{}", database.write(), other.write());
+        let loaded = WcetDatabase::read_with_cpu(&content, "cpu A").unwrap();
+
+        assert_eq!(loaded.len(), 4);
+        assert_eq!(
+            loaded.get(&osc, 256, 48000, WcetSource::Max),
+            Some(0.000012)
+        );
+        assert_eq!(loaded.get(&osc, 256, 48000, pwcet), Some(0.000015));
+        assert_eq!(
+            loaded.get(&mix, 256, 48000, WcetSource::Max),
+            Some(0.000003)
+        );
+        assert_eq!(loaded.get(&osc, 512, 48000, WcetSource::Max), None);
+        assert_eq!(loaded.get(&osc, 256, 44100, WcetSource::Max), None);
+
+        let loaded = WcetDatabase::read_with_cpu(&content, "cpu B").unwrap();
+
+        assert_eq!(loaded.get(&osc, 256, 48000, WcetSource::Max), Some(0.00002));
+        assert_eq!(loaded.write(), content);
+
+        // The profiles without a source are maximum execution times
+        let content = "cpu cpu A\nosc 440,1 256 48000 0.1\n";
+        let loaded = WcetDatabase::read_with_cpu(content, "cpu A").unwrap();
+
+        assert_eq!(loaded.get(&osc, 256, 48000, WcetSource::Max), Some(0.1));
+        assert_eq!(loaded.get(&osc, 256, 48000, pwcet), None);
+
+        match WcetDatabase::read_with_cpu("osc 440,1 256 48000 max 0.1\n", "cpu A") {
+            Err(DatabaseError::Parse { line: 1, .. }) => {}
+            _ => panic!("a profile outside of a cpu is accepted"),
+        }
+    }
+
+    #[test]
+    fn test_save() {
+        let directory = std::env::temp_dir().join("audio_graph_database_test");
+        let path = directory.join("profiles").join("wcet_profiles.txt");
+        let _ = fs::remove_dir_all(&directory);
+
+        let mut database = WcetDatabase::with_cpu("cpu A");
+        let osc = DspNode::Oscillator(Oscillator::new(0.0, 440.0, 1.0));
+        database.insert(&osc, 256, 48000, WcetSource::Max, 0.000012);
+        database.save(&path).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), database.write());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,8 @@
+//! Profiling of the WCETs of the DSPs, stored in a database so that they are
+//! measured once for a host, probabilistic WCETs, and observation of the
+//! execution times of the nodes during the audio cycles.
+
+pub mod database;
+pub mod observation;
+pub mod profiler;
+pub mod pwcet;
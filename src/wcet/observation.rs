@@ -0,0 +1,327 @@
+//! This module keeps statistics on the execution times of the nodes observed
+//! during the audio cycles, so that a graph can be scheduled again with the
+//! WCETs of its real executions.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use task_graph::graph::TaskGraph;
+
+/// The number of most recent execution times kept for the percentiles.
+pub const WINDOW_SIZE: usize = 1024;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+/// The execution times observed for a node, in seconds.
+pub struct NodeStatistics {
+    pub nb_samples: usize,
+    pub max: f64,
+    pub total: f64,
+    window: VecDeque<f64>, // The last `WINDOW_SIZE` execution times
+}
+
+impl NodeStatistics {
+    /// Adds an execution time.
+    ///
+    /// # Arguments
+    /// * `time` - The execution time, in seconds
+    pub fn record(&mut self, time: f64) {
+        self.nb_samples += 1;
+        self.total += time;
+        self.max = self.max.max(time);
+
+        if self.window.len() == WINDOW_SIZE {
+            self.window.pop_front();
+        }
+
+        self.window.push_back(time);
+    }
+
+    /// Returns the mean execution time, if there is one.
+    pub fn get_mean(&self) -> Option<f64> {
+        if self.nb_samples == 0 {
+            None
+        } else {
+            Some(self.total / self.nb_samples as f64)
+        }
+    }
+
+    /// Returns a percentile of the most recent execution times, with the
+    /// nearest-rank method, if there is one.
+    ///
+    /// # Arguments
+    /// * `percentile` - The percentile, between 0 and 100
+    pub fn get_percentile(&self, percentile: f64) -> Option<f64> {
+        if self.window.is_empty() {
+            return None;
+        }
+
+        let mut times: Vec<f64> = self.window.iter().cloned().collect();
+        times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let rank = (percentile / 100.0 * times.len() as f64).ceil() as usize;
+
+        Some(times[rank.max(1).min(times.len()) - 1])
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// How the WCET of a node is derived from its observed execution times.
+pub enum WcetEstimator {
+    Max,
+    Percentile(f64), // Between 0 and 100
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// When and how a graph is scheduled again from its observed execution times.
+pub struct ReschedulePolicy {
+    pub estimator: WcetEstimator,
+    pub min_samples: usize, // Nodes with fewer observations keep their WCET
+    // Relative difference between an estimated WCET and the WCET of the
+    // schedule above which the graph is scheduled again, if any
+    pub drift_threshold: Option<f64>,
+}
+
+impl Default for ReschedulePolicy {
+    fn default() -> ReschedulePolicy {
+        ReschedulePolicy {
+            estimator: WcetEstimator::Percentile(99.0),
+            min_samples: 100,
+            drift_threshold: None,
+        }
+    }
+}
+
+// The execution times of a node, in nanoseconds, preallocated so that the
+// thread executing the node records them without locking nor allocating
+struct AtomicNodeStatistics {
+    nb_samples: AtomicUsize,
+    max: AtomicU64,
+    total: AtomicU64,
+    window: Box<[AtomicU64]>, // The last `WINDOW_SIZE` execution times, in a ring
+}
+
+impl AtomicNodeStatistics {
+    // Creates empty statistics
+    fn new() -> AtomicNodeStatistics {
+        AtomicNodeStatistics {
+            nb_samples: AtomicUsize::new(0),
+            max: AtomicU64::new(0),
+            total: AtomicU64::new(0),
+            window: (0..WINDOW_SIZE).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    // Adds an execution time, the node being executed by a single thread at a
+    // time
+    fn record(&self, nanos: u64) {
+        let nb_samples = self.nb_samples.load(Ordering::Relaxed);
+
+        self.window[nb_samples % WINDOW_SIZE].store(nanos, Ordering::Relaxed);
+        self.max.fetch_max(nanos, Ordering::Relaxed);
+        self.total.fetch_add(nanos, Ordering::Relaxed);
+        self.nb_samples.store(nb_samples + 1, Ordering::Release);
+    }
+
+    // Returns a copy of the statistics, in seconds
+    fn load(&self) -> NodeStatistics {
+        let nb_samples = self.nb_samples.load(Ordering::Acquire);
+        let first = nb_samples.saturating_sub(WINDOW_SIZE);
+        let to_secs = |nanos: u64| Duration::from_nanos(nanos).as_secs_f64();
+
+        NodeStatistics {
+            nb_samples,
+            max: to_secs(self.max.load(Ordering::Relaxed)),
+            total: to_secs(self.total.load(Ordering::Relaxed)),
+            window: (first..nb_samples)
+                .map(|sample| to_secs(self.window[sample % WINDOW_SIZE].load(Ordering::Relaxed)))
+                .collect(),
+        }
+    }
+}
+
+/// The statistics of the nodes of a graph, shared by the threads executing
+/// them.
+pub struct ExecutionStatistics {
+    nodes: Vec<AtomicNodeStatistics>,
+}
+
+impl ExecutionStatistics {
+    /// Creates empty statistics.
+    ///
+    /// # Arguments
+    /// * `nb_nodes` - The number of nodes of the graph
+    pub fn new(nb_nodes: usize) -> ExecutionStatistics {
+        ExecutionStatistics {
+            nodes: (0..nb_nodes).map(|_| AtomicNodeStatistics::new()).collect(),
+        }
+    }
+
+    /// Adds an execution time of a node, without locking nor allocating.
+    ///
+    /// # Arguments
+    /// * `node_index` - The index of the node
+    /// * `duration` - The execution time
+    pub fn record(&self, node_index: usize, duration: Duration) {
+        if let Some(node) = self.nodes.get(node_index) {
+            node.record(duration.as_nanos() as u64);
+        }
+    }
+
+    /// Returns the statistics of a node.
+    ///
+    /// # Arguments
+    /// * `node_index` - The index of the node
+    pub fn get(&self, node_index: usize) -> Option<NodeStatistics> {
+        self.nodes.get(node_index).map(AtomicNodeStatistics::load)
+    }
+
+    /// Returns the WCET of a node estimated from its execution times, if it
+    /// has been observed at least `min_samples` times.
+    ///
+    /// # Arguments
+    /// * `node_index` - The index of the node
+    /// * `estimator` - How the WCET is estimated
+    /// * `min_samples` - The number of observations needed
+    pub fn estimate(
+        &self,
+        node_index: usize,
+        estimator: WcetEstimator,
+        min_samples: usize,
+    ) -> Option<f64> {
+        let node = self.get(node_index)?;
+
+        if node.nb_samples == 0 || node.nb_samples < min_samples {
+            return None;
+        }
+
+        match estimator {
+            WcetEstimator::Max => Some(node.max),
+            WcetEstimator::Percentile(percentile) => node.get_percentile(percentile),
+        }
+    }
+
+    /// Returns the highest relative difference between the estimated WCETs of
+    /// the nodes and their WCETs in the graph, the nodes without enough
+    /// observations or without a positive WCET being ignored.
+    ///
+    /// # Arguments
+    /// * `graph` - The graph which was scheduled
+    /// * `estimator` - How the WCETs are estimated
+    /// * `min_samples` - The number of observations needed for a node
+    pub fn get_drift(
+        &self,
+        graph: &TaskGraph,
+        estimator: WcetEstimator,
+        min_samples: usize,
+    ) -> f64 {
+        (0..self.nodes.len())
+            .filter_map(|node_index| {
+                let wcet = graph
+                    .get_known_wcet(node_index)
+                    .filter(|&wcet| wcet > 0.0)?;
+                let estimation = self.estimate(node_index, estimator, min_samples)?;
+
+                Some((estimation - wcet).abs() / wcet)
+            })
+            .fold(0.0, f64::max)
+    }
+
+    /// Sets the WCETs of the nodes of the graph to their estimations and
+    /// returns the number of nodes whose WCET has been set.
+    ///
+    /// # Arguments
+    /// * `graph` - The graph whose WCETs are set
+    /// * `estimator` - How the WCETs are estimated
+    /// * `min_samples` - The number of observations needed for a node
+    pub fn apply(
+        &self,
+        graph: &mut TaskGraph,
+        estimator: WcetEstimator,
+        min_samples: usize,
+    ) -> usize {
+        let mut nb_applied = 0;
+
+        for node_index in 0..self.nodes.len() {
+            if let Some(wcet) = self.estimate(node_index, estimator, min_samples) {
+                graph.set_wcet(node_index, wcet);
+                nb_applied += 1;
+            }
+        }
+
+        nb_applied
+    }
+}
+
+#[cfg(test)]
+mod observation_test {
+    use super::*;
+    use task_graph::task::Task;
+
+    #[test]
+    fn test_statistics() {
+        let mut node = NodeStatistics::default();
+
+        for i in 1..=100 {
+            node.record(f64::from(i));
+        }
+
+        assert_eq!(node.nb_samples, 100);
+        assert_eq!(node.max, 100.0);
+        assert_eq!(node.get_mean(), Some(50.5));
+        assert_eq!(node.get_percentile(99.0), Some(99.0));
+        assert_eq!(node.get_percentile(50.0), Some(50.0));
+        assert_eq!(node.get_percentile(0.0), Some(1.0));
+        assert_eq!(NodeStatistics::default().get_percentile(50.0), None);
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut g = TaskGraph::new(2, 1);
+
+        g.add_task(Task::Constant(1.0));
+        g.add_task(Task::Constant(1.0));
+        g.add_edge(0, 1);
+        g.get_wcet(0);
+        g.get_wcet(1);
+
+        let statistics = ExecutionStatistics::new(2);
+
+        for _ in 0..10 {
+            statistics.record(0, Duration::from_millis(1500));
+        }
+
+        statistics.record(1, Duration::from_millis(500));
+
+        let node = statistics.get(0).unwrap();
+
+        assert_eq!(node.nb_samples, 10);
+        assert_eq!(node.max, 1.5);
+        assert_eq!(node.get_mean(), Some(1.5));
+
+        assert_eq!(statistics.get_drift(&g, WcetEstimator::Max, 5), 0.5);
+        assert_eq!(statistics.get_drift(&g, WcetEstimator::Max, 1), 0.5);
+        assert_eq!(statistics.apply(&mut g, WcetEstimator::Max, 5), 1);
+        assert_eq!(g.get_known_wcet(0), Some(1.5));
+        assert_eq!(g.get_known_wcet(1), Some(1.0));
+        assert_eq!(statistics.get_drift(&g, WcetEstimator::Max, 5), 0.0);
+    }
+
+    #[test]
+    fn test_window() {
+        let statistics = ExecutionStatistics::new(1);
+
+        for i in 0..WINDOW_SIZE + 10 {
+            statistics.record(0, Duration::from_nanos(i as u64));
+        }
+
+        let node = statistics.get(0).unwrap();
+
+        assert_eq!(node.nb_samples, WINDOW_SIZE + 10);
+        assert_eq!(node.get_percentile(0.0), Some(10e-9));
+        assert_eq!(
+            node.get_percentile(100.0),
+            Some(Duration::from_nanos(WINDOW_SIZE as u64 + 9).as_secs_f64())
+        );
+    }
+}
@@ -0,0 +1,278 @@
+//! This module measures the execution times of the DSPs and sets the WCETs of
+//! the nodes of a graph from a `WcetDatabase`.
+
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use dsp::{DspEdge, DspNode};
+use task_graph::graph::TaskGraph;
+use wcet::database::{DatabaseError, WcetDatabase};
+use wcet::pwcet::{estimate_pwcet, BLOCK_SIZE};
+
+/// The number of executions of a DSP measured for its WCET.
+pub const NB_RUNS: usize = 50;
+
+/// The number of executions of a DSP measured for its probabilistic WCET.
+pub const NB_PWCET_RUNS: usize = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// How the WCET of a DSP is derived from the execution times measured.
+pub enum WcetSource {
+    Max,        // The highest of `NB_RUNS` execution times
+    PWcet(f64), // The probabilistic WCET at an exceedance probability
+}
+
+impl WcetSource {
+    /// Returns the name of the source, as stored in the profiles.
+    pub fn get_name(&self) -> String {
+        match self {
+            WcetSource::Max => "max".to_string(),
+            WcetSource::PWcet(exceedance_probability) => {
+                format!("pwcet:{:e}", exceedance_probability)
+            }
+        }
+    }
+
+    /// Returns the source with a name, if there is one.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the source
+    pub fn from_name(name: &str) -> Option<WcetSource> {
+        if name == "max" {
+            return Some(WcetSource::Max);
+        }
+
+        let exceedance_probability: f64 = name.strip_prefix("pwcet:")?.parse().ok()?;
+
+        if exceedance_probability > 0.0 && exceedance_probability < 1.0 {
+            Some(WcetSource::PWcet(exceedance_probability))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number of executions to measure.
+    pub fn get_nb_runs(&self) -> usize {
+        match self {
+            WcetSource::Max => NB_RUNS,
+            WcetSource::PWcet(_) => NB_PWCET_RUNS,
+        }
+    }
+
+    /// Returns the WCET of execution times, in seconds.
+    ///
+    /// # Arguments
+    /// * `durations` - The execution times measured
+    pub fn estimate(&self, durations: &[Duration]) -> f64 {
+        let samples: Vec<f64> = durations
+            .iter()
+            .map(|duration| duration.as_secs_f64())
+            .collect();
+
+        match self {
+            WcetSource::Max => samples.into_iter().fold(0.0, f64::max),
+            WcetSource::PWcet(exceedance_probability) => {
+                estimate_pwcet(&samples, BLOCK_SIZE, *exceedance_probability)
+            }
+        }
+    }
+}
+
+// Returns a buffer of silence
+fn new_edge(buffer_size: usize, sample_rate: usize) -> Arc<RwLock<DspEdge>> {
+    Arc::new(RwLock::new(DspEdge::new(buffer_size, sample_rate)))
+}
+
+/// Returns the durations of executions of a copy of a DSP on buffers of
+/// silence.
+///
+/// # Arguments
+/// * `dsp` - The DSP to measure
+/// * `buffer_size` - The size of the audio buffers
+/// * `sample_rate` - The sample rate
+/// * `nb_runs` - The number of executions
+pub fn measure_dsp(
+    dsp: &DspNode,
+    buffer_size: usize,
+    sample_rate: usize,
+    nb_runs: usize,
+) -> Vec<Duration> {
+    let mut durations = Vec::with_capacity(nb_runs);
+
+    // The copy of the DSP and its buffers are allocated before the timer
+    // starts and freed after it stops, so that only the processing is measured
+    for _ in 0..nb_runs {
+        let duration = match dsp.clone() {
+            DspNode::Oscillator(mut o) => {
+                let output = new_edge(buffer_size, sample_rate);
+                let timer = Instant::now();
+
+                o.process(output.clone());
+                timer.elapsed()
+            }
+            DspNode::Modulator(mut m) => {
+                let input = new_edge(buffer_size, sample_rate);
+                let output = new_edge(buffer_size, sample_rate);
+                let timer = Instant::now();
+
+                m.process(input.clone(), output.clone());
+                timer.elapsed()
+            }
+            DspNode::InputsOutputsAdaptor(mut ioa) => {
+                let inputs: Vec<_> = (0..ioa.get_nb_inputs())
+                    .map(|_| new_edge(buffer_size, sample_rate))
+                    .collect();
+                let outputs: Vec<_> = (0..ioa.get_nb_outputs())
+                    .map(|_| new_edge(buffer_size, sample_rate))
+                    .collect();
+                let _edges = (inputs.clone(), outputs.clone());
+                let timer = Instant::now();
+
+                ioa.process(inputs, outputs);
+                timer.elapsed()
+            }
+            DspNode::Sink(mut s) => {
+                let mut buffer = vec![0.0; buffer_size];
+                let input = new_edge(buffer_size, sample_rate);
+
+                s.set_buffer(buffer.as_mut_ptr(), buffer_size as u32);
+
+                let timer = Instant::now();
+
+                s.process(input.clone());
+                timer.elapsed()
+            }
+            DspNode::Input(mut i) => {
+                let buffer = vec![0.0; buffer_size];
+                let output = new_edge(buffer_size, sample_rate);
+
+                i.set_buffer(buffer.as_ptr(), buffer_size as u32);
+
+                let timer = Instant::now();
+
+                i.process(output.clone());
+                timer.elapsed()
+            }
+        };
+
+        durations.push(duration);
+    }
+
+    durations
+}
+
+/// Sets the WCETs of the DSPs of a graph, profiling the DSPs which are not in
+/// the database yet with the `WcetSource` of the graph and adding them to it.
+/// Returns the number of DSPs profiled, or `None` if the buffer size and the
+/// sample rate of the graph have not been set.
+///
+/// # Arguments
+/// * `graph` - The graph whose WCETs are set
+/// * `database` - The profiles of the DSPs
+/// * `nb_runs` - The number of executions measured for a new profile
+pub fn profile_graph(
+    graph: &mut TaskGraph,
+    database: &mut WcetDatabase,
+    nb_runs: usize,
+) -> Option<usize> {
+    let buffer_size = graph.get_buffer_size()?;
+    let sample_rate = graph.get_sample_rate()?;
+    let source = graph.get_wcet_source();
+    let mut nb_profiled = 0;
+
+    for node_index in 0..graph.get_nb_node() {
+        let dsp = match *graph.get_dsp(node_index).lock().unwrap() {
+            Some(ref dsp_task) => dsp_task.dsp.clone(),
+            None => continue,
+        };
+
+        if database
+            .get(&dsp, buffer_size, sample_rate, source)
+            .is_none()
+        {
+            let wcet = source.estimate(&measure_dsp(&dsp, buffer_size, sample_rate, nb_runs));
+
+            database.insert(&dsp, buffer_size, sample_rate, source, wcet);
+            nb_profiled += 1;
+        }
+    }
+
+    graph.load_wcets(database);
+
+    Some(nb_profiled)
+}
+
+/// Sets the WCETs of the DSPs of a graph from a database file, profiling the
+/// missing DSPs and saving them in the file. Returns the number of DSPs
+/// profiled.
+///
+/// # Arguments
+/// * `graph` - The graph whose WCETs are set, with its buffer size and its
+///   sample rate
+/// * `path` - The path of the database file
+pub fn load_profiles(graph: &mut TaskGraph, path: &str) -> Result<usize, DatabaseError> {
+    let mut database = WcetDatabase::load(path)?;
+    let nb_runs = graph.get_wcet_source().get_nb_runs();
+    let nb_profiled = profile_graph(graph, &mut database, nb_runs).unwrap_or(0);
+
+    if nb_profiled > 0 {
+        database.save(path)?;
+    }
+
+    Ok(nb_profiled)
+}
+
+#[cfg(test)]
+mod profiler_test {
+    use super::*;
+    use task_graph::task::DspTask;
+
+    #[test]
+    fn test_profile_graph() {
+        let mut g = TaskGraph::new(3, 2);
+
+        g.add_dsp(DspTask::new_oscillator("a".to_string(), 440.0, 1.0));
+        g.add_dsp(DspTask::new_io_adaptor("m".to_string(), 1, 2));
+        g.add_dsp(DspTask::new_sink("s".to_string(), 1));
+        g.add_edge(0, 1);
+        g.add_edge(1, 2);
+
+        let mut database = WcetDatabase::with_cpu("test");
+
+        assert_eq!(profile_graph(&mut g, &mut database, 5), None);
+
+        g.set_buffer_size(64);
+        g.set_sample_rate(48000);
+
+        assert_eq!(profile_graph(&mut g, &mut database, 5), Some(3));
+        assert_eq!(database.len(), 3);
+
+        // The profiled WCETs are reused
+        let osc = g.get_dsp(0).lock().unwrap().as_ref().unwrap().dsp.clone();
+        database.insert(&osc, 64, 48000, WcetSource::Max, 0.5);
+
+        assert_eq!(profile_graph(&mut g, &mut database, 5), Some(0));
+        assert_eq!(g.get_known_wcet(0), Some(0.5));
+
+        // The probabilistic WCETs are profiled apart
+        g.set_wcet_source(WcetSource::PWcet(1e-9));
+
+        assert_eq!(profile_graph(&mut g, &mut database, 100), Some(3));
+        assert_eq!(database.len(), 6);
+        assert_ne!(g.get_known_wcet(0), Some(0.5));
+    }
+
+    #[test]
+    fn test_wcet_source() {
+        for &source in &[WcetSource::Max, WcetSource::PWcet(1e-9)] {
+            assert_eq!(WcetSource::from_name(&source.get_name()), Some(source));
+        }
+
+        assert_eq!(WcetSource::from_name("pwcet:2"), None);
+
+        let durations = vec![Duration::from_nanos(1500), Duration::from_nanos(300)];
+
+        // Sub-microsecond execution times are kept
+        assert_eq!(WcetSource::Max.estimate(&durations), 0.0000015);
+    }
+}
@@ -0,0 +1,163 @@
+//! This module estimates probabilistic WCETs with the extreme value theory:
+//! the maxima of blocks of execution times are fitted with a Gumbel
+//! distribution, whose tail gives the execution time exceeded with a given
+//! probability.
+
+/// The number of execution times of a block whose maximum is kept.
+pub const BLOCK_SIZE: usize = 20;
+
+// The Euler-Mascheroni constant, mean of the standard Gumbel distribution
+const EULER_MASCHERONI: f64 = 0.577_215_664_901_532_9;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// A Gumbel distribution, the law of the maxima of execution times.
+pub struct Gumbel {
+    pub location: f64,
+    pub scale: f64,
+}
+
+impl Gumbel {
+    /// Fits a Gumbel distribution to maxima with the method of moments,
+    /// returning `None` if there are fewer than two maxima.
+    ///
+    /// # Arguments
+    /// * `maxima` - The maxima of the blocks of samples
+    pub fn fit(maxima: &[f64]) -> Option<Gumbel> {
+        if maxima.len() < 2 {
+            return None;
+        }
+
+        let nb_maxima = maxima.len() as f64;
+        let mean = maxima.iter().sum::<f64>() / nb_maxima;
+        let variance = maxima
+            .iter()
+            .map(|maximum| (maximum - mean) * (maximum - mean))
+            .sum::<f64>()
+            / (nb_maxima - 1.0);
+
+        let scale = variance.sqrt() * 6f64.sqrt() / std::f64::consts::PI;
+
+        Some(Gumbel {
+            location: mean - EULER_MASCHERONI * scale,
+            scale,
+        })
+    }
+
+    /// Returns the probability that a maximum is at most `x`.
+    ///
+    /// # Arguments
+    /// * `x` - The value
+    pub fn cdf(&self, x: f64) -> f64 {
+        if self.scale == 0.0 {
+            return if x < self.location { 0.0 } else { 1.0 };
+        }
+
+        (-(-(x - self.location) / self.scale).exp()).exp()
+    }
+
+    /// Returns the value a maximum exceeds with a given probability.
+    ///
+    /// # Arguments
+    /// * `exceedance_probability` - The probability, between 0 and 1
+    pub fn get_quantile(&self, exceedance_probability: f64) -> f64 {
+        // -ln(1 - p), precise for the tiny probabilities of a pWCET
+        let tail = -(-exceedance_probability).ln_1p();
+
+        self.location - self.scale * tail.ln()
+    }
+}
+
+/// Returns the maxima of the consecutive blocks of samples, the last
+/// incomplete block being dropped.
+///
+/// # Arguments
+/// * `samples` - The execution times
+/// * `block_size` - The number of samples of a block
+pub fn get_block_maxima(samples: &[f64], block_size: usize) -> Vec<f64> {
+    samples
+        .chunks(block_size.max(1))
+        .filter(|block| block.len() == block_size.max(1))
+        .map(|block| block.iter().cloned().fold(f64::MIN, f64::max))
+        .collect()
+}
+
+/// Returns the probabilistic WCET of execution times: the time exceeded by an
+/// execution with the given probability, according to the Gumbel distribution
+/// of the maxima of blocks of `block_size` samples. It is never lower than the
+/// highest sample, which is returned when there are too few samples for a
+/// fit.
+///
+/// # Arguments
+/// * `samples` - The execution times
+/// * `block_size` - The number of samples of a block
+/// * `exceedance_probability` - The probability that an execution exceeds the
+///   pWCET, between 0 and 1
+pub fn estimate_pwcet(samples: &[f64], block_size: usize, exceedance_probability: f64) -> f64 {
+    let max = samples.iter().cloned().fold(0.0, f64::max);
+    let block_size = block_size.max(1);
+
+    match Gumbel::fit(&get_block_maxima(samples, block_size)) {
+        // A block exceeds a time if one of its executions does
+        Some(gumbel) => {
+            let block_probability =
+                -(block_size as f64 * (-exceedance_probability).ln_1p()).exp_m1();
+
+            gumbel.get_quantile(block_probability).max(max)
+        }
+        None => max,
+    }
+}
+
+#[cfg(test)]
+mod pwcet_test {
+    use super::*;
+
+    // Returns samples of a Gumbel distribution, at regularly spaced quantiles
+    fn get_gumbel_samples(gumbel: &Gumbel, nb_samples: usize) -> Vec<f64> {
+        (0..nb_samples)
+            .map(|i| {
+                let probability = (i as f64 + 0.5) / nb_samples as f64;
+                gumbel.location - gumbel.scale * (-probability.ln()).ln()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fit() {
+        let gumbel = Gumbel {
+            location: 10.0,
+            scale: 2.0,
+        };
+        let fitted = Gumbel::fit(&get_gumbel_samples(&gumbel, 10000)).unwrap();
+
+        assert!((fitted.location - 10.0).abs() < 0.05);
+        assert!((fitted.scale - 2.0).abs() < 0.05);
+
+        let quantile = gumbel.get_quantile(0.01);
+
+        assert!((gumbel.cdf(quantile) - 0.99).abs() < 1e-9);
+        assert!(Gumbel::fit(&[1.0]).is_none());
+    }
+
+    #[test]
+    fn test_estimate_pwcet() {
+        assert_eq!(
+            get_block_maxima(&[1.0, 3.0, 2.0, 5.0, 4.0], 2),
+            vec![3.0, 5.0]
+        );
+
+        // Too few samples for a fit
+        assert_eq!(estimate_pwcet(&[1.0, 3.0, 2.0], BLOCK_SIZE, 1e-9), 3.0);
+
+        let samples: Vec<f64> = (0..1000)
+            .map(|i| 1e-6 * (1.0 + f64::from((i * 7919) % 1000) / 1000.0))
+            .collect();
+        let max = samples.iter().cloned().fold(0.0, f64::max);
+
+        let pwcet_3 = estimate_pwcet(&samples, BLOCK_SIZE, 1e-3);
+        let pwcet_9 = estimate_pwcet(&samples, BLOCK_SIZE, 1e-9);
+
+        assert!(pwcet_3 >= max);
+        assert!(pwcet_9 > pwcet_3);
+    }
+}